@@ -0,0 +1,155 @@
+// 端末の桁数で揃える幅指定
+//
+// format!("{:4}", "the\u{301}")は結合文字も1文字と数え、"{:6}"は"うどん"を6桁分の幅と見なさない。
+// Paddedは書記素クラスタ単位で表示幅を測り、詰め物と省略記号による切り詰めも桁数で行う。
+
+use std::borrow::Cow;
+use std::fmt;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::width::{AmbiguousWidth, DisplayWidth};
+
+/// 寄せ方（"{:<}", "{:^}", "{:>}"に相当）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    /// '<', '^', '>'から
+    pub fn from_char(ch: char) -> Option<Align> {
+        match ch {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        }
+    }
+}
+
+/// 表示幅で詰め物をするDisplayアダプタ
+#[derive(Clone, Debug)]
+pub struct Padded<T> {
+    value: T,
+    width: usize,
+    align: Align,
+    fill: char,
+    max_width: Option<usize>,
+    ambiguous: AmbiguousWidth,
+    ellipsis: Cow<'static, str>,
+}
+
+impl<T: fmt::Display> Padded<T> {
+    /// 左寄せ・空白詰め・切り詰めなし
+    pub fn new(value: T, width: usize) -> Padded<T> {
+        Padded {
+            value,
+            width,
+            align: Align::Left,
+            fill: ' ',
+            max_width: None,
+            ambiguous: AmbiguousWidth::Narrow,
+            ellipsis: Cow::Borrowed("…"),
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Padded<T> {
+        self.align = align;
+        self
+    }
+
+    pub fn left(self) -> Padded<T> {
+        self.align(Align::Left)
+    }
+
+    pub fn center(self) -> Padded<T> {
+        self.align(Align::Center)
+    }
+
+    pub fn right(self) -> Padded<T> {
+        self.align(Align::Right)
+    }
+
+    /// 詰め物の文字。全角文字なら2桁ずつ詰め、半端な1桁は空白にする
+    pub fn fill(mut self, fill: char) -> Padded<T> {
+        self.fill = fill;
+        self
+    }
+
+    /// 表示幅がmax_widthを超えるなら、省略記号を含めてmax_width以内に切り詰める
+    pub fn truncate(mut self, max_width: usize) -> Padded<T> {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Ambiguousの文字（"○"など）を何桁として数えるか
+    pub fn ambiguous_width(mut self, ambiguous: AmbiguousWidth) -> Padded<T> {
+        self.ambiguous = ambiguous;
+        self
+    }
+
+    /// 切り詰めたときに末尾に付ける文字列（既定は"…"）
+    pub fn ellipsis<S: Into<Cow<'static, str>>>(mut self, ellipsis: S) -> Padded<T> {
+        self.ellipsis = ellipsis.into();
+        self
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Padded<T> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.value.to_string();
+        let text = match self.max_width {
+            Some(max_width) => truncate_with(&text, max_width, &self.ellipsis, self.ambiguous),
+            None => Cow::Borrowed(text.as_str()),
+        };
+        let padding = self.width.saturating_sub(text.display_width_with(self.ambiguous));
+        let (before, after) = match self.align {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+        self.write_fill(dest, before)?;
+        dest.write_str(&text)?;
+        self.write_fill(dest, after)
+    }
+}
+
+impl<T: fmt::Display> Padded<T> {
+    fn write_fill(&self, dest: &mut fmt::Formatter, columns: usize) -> fmt::Result {
+        let fill_width = self.fill.display_width_with(self.ambiguous).max(1);
+        for _ in 0..columns / fill_width {
+            write!(dest, "{}", self.fill)?;
+        }
+        for _ in 0..columns % fill_width {
+            dest.write_str(" ")?;
+        }
+        Ok(())
+    }
+}
+
+/// 表示幅がmax_widthを超えるなら書記素クラスタの境界で切り詰め、省略記号を付ける
+pub fn truncate<'a>(text: &'a str, max_width: usize, ellipsis: &str) -> Cow<'a, str> {
+    truncate_with(text, max_width, ellipsis, AmbiguousWidth::Narrow)
+}
+
+/// Ambiguousの幅を指定して切り詰める
+pub fn truncate_with<'a>(text: &'a str, max_width: usize, ellipsis: &str, ambiguous: AmbiguousWidth) -> Cow<'a, str> {
+    if text.display_width_with(ambiguous) <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let budget = max_width.saturating_sub(ellipsis.display_width_with(ambiguous));
+    let mut used = 0;
+    let mut end = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        let width = grapheme.display_width_with(ambiguous);
+        if used + width > budget {
+            break;
+        }
+        used += width;
+        end = start + grapheme.len();
+    }
+    Cow::Owned(format!("{}{}", &text[..end], ellipsis))
+}
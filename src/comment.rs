@@ -0,0 +1,301 @@
+// 設定ファイル向けのコメント除去・抽出
+//
+// 文字列リテラル内の"#"や"//"をコメントと誤認しないよう、引用符とエスケープを追いかけながら走査する。
+// 除去後も改行は残すため、エラーメッセージの行番号は元のテキストと一致する。
+
+use std::fmt;
+use std::ops::Range;
+
+/// 言語ごとのコメント・文字列リテラルの書式
+#[derive(Clone, Debug, Default)]
+pub struct CommentSyntax {
+    line: Vec<String>,
+    block: Vec<(String, String)>,
+    quotes: Vec<(char, bool)>, // (引用符, エスケープ文字が有効か)
+    escape: Option<char>,
+    escape_in_code: bool,
+    doubled_quote: bool,
+    line_start_only: bool,
+    word_start_only: bool,
+}
+
+impl CommentSyntax {
+    /// コメントも文字列リテラルも持たない書式。ビルダーメソッドで組み立てる
+    pub fn new() -> CommentSyntax {
+        CommentSyntax::default()
+    }
+
+    /// シェルスクリプト: 語の先頭の`#`、'…'はエスケープなし、"…"と地の文はバックスラッシュエスケープ
+    pub fn shell() -> CommentSyntax {
+        CommentSyntax::new()
+            .line_comment("#")
+            .raw_quote('\'')
+            .quote('"')
+            .escape('\\')
+            .escape_in_code(true)
+            .word_start_only(true)
+    }
+
+    /// C系言語: `//`と`/* */`、'…'と"…"はバックスラッシュエスケープ
+    pub fn c() -> CommentSyntax {
+        CommentSyntax::new()
+            .line_comment("//")
+            .block_comment("/*", "*/")
+            .quote('"')
+            .quote('\'')
+            .escape('\\')
+    }
+
+    /// SQL: `--`と`/* */`、引用符は二重にしてエスケープする
+    pub fn sql() -> CommentSyntax {
+        CommentSyntax::new()
+            .line_comment("--")
+            .block_comment("/*", "*/")
+            .quote('\'')
+            .quote('"')
+            .doubled_quote(true)
+    }
+
+    /// INIファイル: 行頭の`;`と`#`
+    pub fn ini() -> CommentSyntax {
+        CommentSyntax::new()
+            .line_comment(";")
+            .line_comment("#")
+            .quote('"')
+            .line_start_only(true)
+    }
+
+    pub fn line_comment(mut self, start: &str) -> CommentSyntax {
+        self.line.push(start.to_string());
+        self
+    }
+
+    pub fn block_comment(mut self, start: &str, end: &str) -> CommentSyntax {
+        self.block.push((start.to_string(), end.to_string()));
+        self
+    }
+
+    /// エスケープ文字が有効な引用符
+    pub fn quote(mut self, quote: char) -> CommentSyntax {
+        self.quotes.push((quote, true));
+        self
+    }
+
+    /// エスケープ文字が無効な引用符（シェルの'…'など）
+    pub fn raw_quote(mut self, quote: char) -> CommentSyntax {
+        self.quotes.push((quote, false));
+        self
+    }
+
+    pub fn escape(mut self, escape: char) -> CommentSyntax {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// 文字列リテラルの外でもエスケープ文字を解釈する（シェルの`\#`など）
+    pub fn escape_in_code(mut self, yes: bool) -> CommentSyntax {
+        self.escape_in_code = yes;
+        self
+    }
+
+    /// 文字列リテラル内の引用符を二重にしてエスケープする（SQLの'It''s'など）
+    pub fn doubled_quote(mut self, yes: bool) -> CommentSyntax {
+        self.doubled_quote = yes;
+        self
+    }
+
+    /// 行コメントを行頭（空白のみ先行）でだけ認める
+    pub fn line_start_only(mut self, yes: bool) -> CommentSyntax {
+        self.line_start_only = yes;
+        self
+    }
+
+    /// 行コメントを語の先頭（行頭か空白の直後）でだけ認める
+    pub fn word_start_only(mut self, yes: bool) -> CommentSyntax {
+        self.word_start_only = yes;
+        self
+    }
+
+    /// テキスト中のコメントを出現順に抽出する
+    pub fn comments<'a>(&self, src: &'a str) -> Result<Vec<Comment<'a>>, CommentError> {
+        let mut comments = Vec::new();
+        let mut pos = 0;
+        let mut line = 1;
+        let mut line_start = 0;
+
+        while let Some(ch) = src[pos..].chars().next() {
+            let rest = &src[pos..];
+
+            if self.escape_in_code && Some(ch) == self.escape {
+                pos += ch.len_utf8();
+                if let Some(escaped) = src[pos..].chars().next() {
+                    if escaped == '\n' {
+                        line += 1;
+                        line_start = pos + 1;
+                    }
+                    pos += escaped.len_utf8();
+                }
+                continue;
+            }
+
+            if let Some(&(quote, escapable)) = self.quotes.iter().find(|&&(q, _)| q == ch) {
+                let (start_line, column) = (line, pos - line_start + 1);
+                pos += ch.len_utf8();
+                loop {
+                    let c = match src[pos..].chars().next() {
+                        Some(c) => c,
+                        None => return Err(CommentError::UnterminatedString { line: start_line, column }),
+                    };
+                    pos += c.len_utf8();
+                    if c == '\n' {
+                        line += 1;
+                        line_start = pos;
+                    } else if escapable && Some(c) == self.escape {
+                        if let Some(escaped) = src[pos..].chars().next() {
+                            if escaped == '\n' {
+                                line += 1;
+                                line_start = pos + 1;
+                            }
+                            pos += escaped.len_utf8();
+                        }
+                    } else if c == quote {
+                        if self.doubled_quote && src[pos..].starts_with(quote) {
+                            pos += quote.len_utf8();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if let Some(start) = self.line.iter().find(|start| rest.starts_with(start.as_str())) {
+                if self.line_comment_allowed(&src[line_start..pos]) {
+                    let end = rest.find('\n').map_or(src.len(), |i| pos + i);
+                    let end = if src[..end].ends_with('\r') { end - 1 } else { end };
+                    comments.push(Comment {
+                        kind: CommentKind::Line,
+                        text: &src[pos + start.len()..end],
+                        span: pos..end,
+                        line,
+                    });
+                    pos = end;
+                    continue;
+                }
+            }
+
+            if let Some((start, end)) = self.block.iter().find(|(start, _)| rest.starts_with(start.as_str())) {
+                let body = pos + start.len();
+                let close = match src[body..].find(end.as_str()) {
+                    Some(i) => body + i,
+                    None => {
+                        return Err(CommentError::UnterminatedComment { line, column: pos - line_start + 1 })
+                    }
+                };
+                let text = &src[body..close];
+                comments.push(Comment {
+                    kind: CommentKind::Block,
+                    text,
+                    span: pos..close + end.len(),
+                    line,
+                });
+                if let Some(i) = text.rfind('\n') {
+                    line += text.matches('\n').count();
+                    line_start = body + i + 1;
+                }
+                pos = close + end.len();
+                continue;
+            }
+
+            pos += ch.len_utf8();
+            if ch == '\n' {
+                line += 1;
+                line_start = pos;
+            }
+        }
+        Ok(comments)
+    }
+
+    /// コメントを取り除いたテキストを返す
+    ///
+    /// 複数行にわたるブロックコメントの改行は残すため、行番号は変わらない。
+    /// 行コメントの直前にあった空白も取り除く。
+    pub fn strip(&self, src: &str) -> Result<String, CommentError> {
+        let mut stripped = String::with_capacity(src.len());
+        let mut last = 0;
+        for comment in self.comments(src)? {
+            stripped.push_str(&src[last..comment.span.start]);
+            match comment.kind {
+                CommentKind::Line => {
+                    let kept = stripped.trim_end_matches(&[' ', '\t'][..]).len();
+                    stripped.truncate(kept);
+                }
+                CommentKind::Block => {
+                    let newlines = comment.text.matches('\n').count();
+                    if newlines > 0 {
+                        stripped.extend(std::iter::repeat_n('\n', newlines));
+                    } else {
+                        // C言語と同様、"a/**/b"が"ab"と繋がらないよう空白1つに置き換える
+                        let before = stripped.chars().next_back().is_some_and(|ch| !ch.is_whitespace());
+                        let after = src[comment.span.end..].chars().next().is_some_and(|ch| !ch.is_whitespace());
+                        if before && after {
+                            stripped.push(' ');
+                        }
+                    }
+                }
+            }
+            last = comment.span.end;
+        }
+        stripped.push_str(&src[last..]);
+        Ok(stripped)
+    }
+
+    fn line_comment_allowed(&self, before: &str) -> bool {
+        if self.line_start_only {
+            before.trim().is_empty()
+        } else if self.word_start_only {
+            before.chars().next_back().is_none_or(char::is_whitespace)
+        } else {
+            true
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+/// 抽出したコメント
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment<'a> {
+    pub kind: CommentKind,
+    /// 区切り記号を除いたコメント本文
+    pub text: &'a str,
+    /// 区切り記号を含む、元テキスト上のバイト範囲
+    pub span: Range<usize>,
+    /// コメントが始まる行（1始まり）
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommentError {
+    UnterminatedString { line: usize, column: usize },
+    UnterminatedComment { line: usize, column: usize },
+}
+
+impl fmt::Display for CommentError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommentError::UnterminatedString { line, column } => {
+                write!(dest, "{}:{}: unterminated string literal", line, column)
+            }
+            CommentError::UnterminatedComment { line, column } => {
+                write!(dest, "{}:{}: unterminated block comment", line, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommentError {}
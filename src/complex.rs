@@ -0,0 +1,298 @@
+// 複素数
+//
+// main.rsのComplex { r, i }は"{:#}"で極座標表示に切り替えるだけで、"{:.3}"・"{:+}"・幅を無視し、
+// 出力した"-0.5 + 0.866i"や"2 ⊿ 90°"を読み戻せなかった。Complex<T>はFormatterの指定に従って書き、
+// 直交形式と極形式の両方をFromStrで読み、四則演算ができる。
+
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use crate::align::{Align, Padded};
+
+/// 成分に使える実数型（f32とf64）
+pub trait Real:
+    Copy
+    + PartialOrd
+    + fmt::Display
+    + FromStr
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn abs(self) -> Self;
+    fn is_sign_negative(self) -> bool;
+    fn is_nan(self) -> bool;
+    fn hypot(self, other: Self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    /// ラジアンから度（90°がちょうど90になるようπで割ってから180を掛ける）
+    fn degrees(self) -> Self;
+    fn radians(self) -> Self;
+}
+
+macro_rules! real {
+    ($($t:ident),*) => {
+        $(impl Real for $t {
+            fn zero() -> $t {
+                0.0
+            }
+
+            fn one() -> $t {
+                1.0
+            }
+
+            fn abs(self) -> $t {
+                $t::abs(self)
+            }
+
+            fn is_sign_negative(self) -> bool {
+                $t::is_sign_negative(self)
+            }
+
+            fn is_nan(self) -> bool {
+                $t::is_nan(self)
+            }
+
+            fn hypot(self, other: $t) -> $t {
+                $t::hypot(self, other)
+            }
+
+            fn atan2(self, other: $t) -> $t {
+                $t::atan2(self, other)
+            }
+
+            fn sin_cos(self) -> ($t, $t) {
+                $t::sin_cos(self)
+            }
+
+            fn degrees(self) -> $t {
+                self / std::$t::consts::PI * 180.0
+            }
+
+            fn radians(self) -> $t {
+                self / 180.0 * std::$t::consts::PI
+            }
+        })*
+    };
+}
+
+real!(f32, f64);
+
+/// 直交形式re + im·iの複素数
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T> Complex<T> {
+    pub fn new(re: T, im: T) -> Complex<T> {
+        Complex { re, im }
+    }
+}
+
+impl<T: Real> Complex<T> {
+    /// 絶対値と偏角（ラジアン）から
+    pub fn from_polar(abs: T, arg: T) -> Complex<T> {
+        let (sin, cos) = arg.sin_cos();
+        Complex { re: abs * cos, im: abs * sin }
+    }
+
+    pub fn abs(&self) -> T {
+        self.re.hypot(self.im)
+    }
+
+    /// 偏角（ラジアン、-π〜π）
+    pub fn arg(&self) -> T {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Complex<T> {
+        Complex { re: self.re, im: -self.im }
+    }
+}
+
+impl<T: Copy + Add<Output = T>> Add for Complex<T> {
+    type Output = Complex<T>;
+
+    fn add(self, other: Complex<T>) -> Complex<T> {
+        Complex { re: self.re + other.re, im: self.im + other.im }
+    }
+}
+
+impl<T: Copy + Sub<Output = T>> Sub for Complex<T> {
+    type Output = Complex<T>;
+
+    fn sub(self, other: Complex<T>) -> Complex<T> {
+        Complex { re: self.re - other.re, im: self.im - other.im }
+    }
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Mul for Complex<T> {
+    type Output = Complex<T>;
+
+    fn mul(self, other: Complex<T>) -> Complex<T> {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>> Div for Complex<T> {
+    type Output = Complex<T>;
+
+    fn div(self, other: Complex<T>) -> Complex<T> {
+        let norm = other.re * other.re + other.im * other.im;
+        Complex {
+            re: (self.re * other.re + self.im * other.im) / norm,
+            im: (self.im * other.re - self.re * other.im) / norm,
+        }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Complex<T> {
+    type Output = Complex<T>;
+
+    fn neg(self) -> Complex<T> {
+        Complex { re: -self.re, im: -self.im }
+    }
+}
+
+/// 実数倍
+impl<T: Copy + Mul<Output = T>> Mul<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn mul(self, scale: T) -> Complex<T> {
+        Complex { re: self.re * scale, im: self.im * scale }
+    }
+}
+
+impl<T: Copy + Div<Output = T>> Div<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn div(self, scale: T) -> Complex<T> {
+        Complex { re: self.re / scale, im: self.im / scale }
+    }
+}
+
+impl<T: Real> fmt::Display for Complex<T> {
+    /// "-0.5 + 0.866i"、"{:#}"は極形式の"2 ⊿ 90°"。"{:.3}"は各成分の小数点以下の桁数、
+    /// "{:+}"は先頭の成分にも符号を付け、幅と寄せ方（既定は右寄せ）は全体に効く
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let component = |value: T| match dest.precision() {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        };
+        let (first, text) = if dest.alternate() {
+            let abs = self.abs();
+            (abs, format!("{} ⊿ {}°", component(abs), component(self.arg().degrees())))
+        } else {
+            let sign = if self.im < T::zero() { '-' } else { '+' };
+            (self.re, format!("{} {} {}i", component(self.re), sign, component(self.im.abs())))
+        };
+        // stdと同じく、-0は"-0"のままにし、NaNには'+'を付けない
+        let text = if dest.sign_plus() && !first.is_sign_negative() && !first.is_nan() {
+            format!("+{}", text)
+        } else {
+            text
+        };
+        let align = match dest.align() {
+            Some(fmt::Alignment::Left) => Align::Left,
+            Some(fmt::Alignment::Center) => Align::Center,
+            _ => Align::Right,
+        };
+        let (width, fill) = (dest.width().unwrap_or(0), dest.fill());
+        write!(dest, "{}", Padded::new(text, width).align(align).fill(fill))
+    }
+}
+
+/// 解析の失敗。positionは文字列中のバイト位置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseComplexError {
+    Empty,
+    InvalidNumber { position: usize },
+}
+
+impl fmt::Display for ParseComplexError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseComplexError::Empty => write!(dest, "empty string"),
+            ParseComplexError::InvalidNumber { position } => write!(dest, "invalid number at byte {}", position),
+        }
+    }
+}
+
+impl std::error::Error for ParseComplexError {}
+
+// 前後の空白を除いたtext[start..end]を実数として読む。符号の後の空白（"- 0.866"）も許す
+fn parse_real<T: Real>(text: &str, start: usize, end: usize) -> Result<T, ParseComplexError> {
+    let part = &text[start..end];
+    let leading = part.len() - part.trim_start().len();
+    let part = part.trim();
+    let negative = part.starts_with('-');
+    let sign_len = usize::from(part.starts_with(['+', '-']));
+    let digits = part[sign_len..].trim_start();
+    // 位置は符号の後の数値の先頭
+    let position = start + leading + part.len() - digits.len();
+    let invalid = ParseComplexError::InvalidNumber { position };
+    // 符号を読んだ後も符号で始まるなら"--1"のような誤り
+    if digits.starts_with(['+', '-']) {
+        return Err(invalid);
+    }
+    let value: T = digits.parse().map_err(|_| invalid)?;
+    Ok(if negative { -value } else { value })
+}
+
+impl<T: Real> FromStr for Complex<T> {
+    type Err = ParseComplexError;
+
+    /// 直交形式（"-0.5 + 0.866i", "3", "-2i", "i", "1-j"）と、極形式（"2 ⊿ 90°", "2∠90°"。°がなければラジアン）
+    fn from_str(text: &str) -> Result<Complex<T>, ParseComplexError> {
+        if text.trim().is_empty() {
+            return Err(ParseComplexError::Empty);
+        }
+        if let Some((index, angle)) = text.char_indices().find(|&(_, ch)| ch == '⊿' || ch == '∠') {
+            let abs: T = parse_real(text, 0, index)?;
+            let angle_start = index + angle.len_utf8();
+            let rest = text[angle_start..].trim_end();
+            let arg: T = match rest.strip_suffix('°') {
+                Some(degrees) => parse_real::<T>(text, angle_start, angle_start + degrees.len())?.radians(),
+                None => parse_real(text, angle_start, angle_start + rest.len())?,
+            };
+            return Ok(Complex::from_polar(abs, arg));
+        }
+
+        let trimmed = text.trim_end();
+        let imaginary_end = match trimmed.as_bytes().last() {
+            Some(b'i') | Some(b'j') => trimmed.len() - 1,
+            _ => {
+                let re = parse_real(text, 0, trimmed.len())?;
+                return Ok(Complex { re, im: T::zero() });
+            }
+        };
+        // 虚部の始まりは最後の'+'か'-'。先頭の符号と指数部の符号（"1e-3"）は除く
+        let bytes = text.as_bytes();
+        let split = (1..imaginary_end).rev().find(|&i| {
+            (bytes[i] == b'+' || bytes[i] == b'-')
+                && !matches!(bytes[..i].iter().rev().find(|b| !b.is_ascii_whitespace()), Some(b'e') | Some(b'E') | None)
+        });
+        let (re, im_start) = match split {
+            Some(split) => (parse_real(text, 0, split)?, split),
+            None => (T::zero(), 0),
+        };
+        // "i"や"-i"のように係数がなければ±1
+        let coefficient = text[im_start..imaginary_end].trim();
+        let im = match coefficient {
+            "" | "+" => T::one(),
+            "-" => -T::one(),
+            _ => parse_real(text, im_start, imaginary_end)?,
+        };
+        Ok(Complex { re, im })
+    }
+}
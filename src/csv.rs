@@ -0,0 +1,354 @@
+// RFC 4180のCSVと、区切り文字を変えたTSV/DSVの読み書き
+//
+// split(',')では引用符で囲まれたフィールド中の','や改行で壊れてしまう。
+// Readerはio::BufReadから1レコードずつ読み、フィールドは可能な限り内部バッファを借用する
+// （引用符の二重化やエスケープを解く必要があるフィールドだけがCow::Ownedになる）。
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// 区切り文字・引用符・エスケープ文字の組
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dialect {
+    pub delimiter: char,
+    pub quote: Option<char>,
+    /// Noneなら引用符を二重にしてエスケープする（RFC 4180）
+    pub escape: Option<char>,
+    /// Writerが書く行末
+    pub terminator: &'static str,
+}
+
+impl Dialect {
+    /// RFC 4180: ','区切り、'"'で囲み、'""'でエスケープ、行末はCRLF
+    pub fn csv() -> Dialect {
+        Dialect { delimiter: ',', quote: Some('"'), escape: None, terminator: "\r\n" }
+    }
+
+    /// タブ区切り。引用符の扱いはCSVと同じで、行末はLF
+    pub fn tsv() -> Dialect {
+        Dialect { delimiter: '\t', terminator: "\n", ..Dialect::csv() }
+    }
+
+    /// 任意の区切り文字
+    pub fn dsv(delimiter: char) -> Dialect {
+        Dialect { delimiter, terminator: "\n", ..Dialect::csv() }
+    }
+
+    pub fn quote(mut self, quote: Option<char>) -> Dialect {
+        self.quote = quote;
+        self
+    }
+
+    pub fn escape(mut self, escape: Option<char>) -> Dialect {
+        self.escape = escape;
+        self
+    }
+
+    pub fn terminator(mut self, terminator: &'static str) -> Dialect {
+        self.terminator = terminator;
+        self
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::csv()
+    }
+}
+
+/// 1レコード分のフィールド。Readerの内部バッファを借用している
+pub type Record<'a> = Vec<Cow<'a, str>>;
+
+/// ストリーミングリーダー
+pub struct Reader<R> {
+    source: R,
+    dialect: Dialect,
+    buffer: String,
+    line: usize,
+    first: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(source: R, dialect: Dialect) -> Reader<R> {
+        Reader { source, dialect, buffer: String::new(), line: 0, first: true }
+    }
+
+    /// 次のレコードを読む。終端に達したらNone
+    ///
+    /// 返すレコードは内部バッファを借用するため、次の呼び出しまでに使い終える必要がある。
+    /// 所有したレコードが欲しい場合はrecords()を使う。
+    pub fn read_record(&mut self) -> Option<Result<Record<'_>, CsvError>> {
+        match self.fill_record() {
+            Ok(Some(start_line)) => {
+                let mut text = self.buffer.as_str();
+                if self.first {
+                    // Excel等が出力するUTF-8のBOMは最初のフィールドに含めない
+                    text = text.strip_prefix('\u{feff}').unwrap_or(text);
+                    self.first = false;
+                }
+                let text = text.strip_suffix('\n').unwrap_or(text);
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                Some(parse_fields(text, &self.dialect, start_line))
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// 所有したレコードを返すイテレータ
+    pub fn records(self) -> Records<R> {
+        Records { reader: self }
+    }
+
+    // 引用符が閉じるまで行を読み足し、レコードの開始行を返す。読み足した行だけを走査する
+    fn fill_record(&mut self) -> Result<Option<usize>, CsvError> {
+        self.buffer.clear();
+        if self.source.read_line(&mut self.buffer)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        let start_line = self.line;
+        let mut scan = QuoteScan::new();
+        let first_line = if self.first {
+            self.buffer.strip_prefix('\u{feff}').unwrap_or(&self.buffer)
+        } else {
+            &self.buffer
+        };
+        scan.feed(first_line, &self.dialect);
+        while scan.inside {
+            let scanned = self.buffer.len();
+            if self.source.read_line(&mut self.buffer)? == 0 {
+                return Err(CsvError::UnterminatedQuote { line: start_line });
+            }
+            self.line += 1;
+            scan.feed(&self.buffer[scanned..], &self.dialect);
+        }
+        Ok(Some(start_line))
+    }
+}
+
+/// Reader::records()が返すイテレータ
+pub struct Records<R> {
+    reader: Reader<R>,
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Vec<String>, CsvError>;
+
+    fn next(&mut self) -> Option<Result<Vec<String>, CsvError>> {
+        self.reader.read_record().map(|record| {
+            record.map(|fields| fields.into_iter().map(Cow::into_owned).collect())
+        })
+    }
+}
+
+// レコードの途中で引用符の中にいるかを、行を読み足すたびに続きから判定する。
+// parse_fieldsと同じく、引用符はフィールドの先頭にあるときだけ開き、途中の引用符（5'11"）は文字として扱う
+struct QuoteScan {
+    inside: bool,
+    field_start: bool,
+    // 直前に引用符を閉じた。続く引用符は二重化された引用符
+    closed: bool,
+}
+
+impl QuoteScan {
+    fn new() -> QuoteScan {
+        QuoteScan { inside: false, field_start: true, closed: false }
+    }
+
+    fn feed(&mut self, text: &str, dialect: &Dialect) {
+        let quote = match dialect.quote {
+            Some(quote) => quote,
+            None => return,
+        };
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            if self.inside {
+                if Some(ch) == dialect.escape {
+                    chars.next();
+                } else if ch == quote {
+                    self.inside = false;
+                    self.closed = true;
+                }
+                continue;
+            }
+            let reopen = self.closed && dialect.escape.is_none();
+            self.closed = false;
+            if ch == quote && (self.field_start || reopen) {
+                self.inside = true;
+                self.field_start = false;
+            } else {
+                self.field_start = ch == dialect.delimiter;
+            }
+        }
+    }
+}
+
+/// 1レコード分のテキスト（行末を除く）をフィールドに分ける
+///
+/// Readerを介さず、メモリ上の1行を解析するのにも使える。lineはエラー報告用の行番号。
+pub fn parse_fields<'a>(text: &'a str, dialect: &Dialect, line: usize) -> Result<Record<'a>, CsvError> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    loop {
+        let rest = &text[pos..];
+        let quote = dialect.quote.filter(|&quote| rest.starts_with(quote));
+        let end = if let Some(quote) = quote {
+            let (field, close) = unquote(text, pos + quote.len_utf8(), quote, dialect.escape)
+                .ok_or(CsvError::UnterminatedQuote { line })?;
+            fields.push(field);
+            let after = close + quote.len_utf8();
+            if after < text.len() && !text[after..].starts_with(dialect.delimiter) {
+                let column = text[..after].chars().count() + 1;
+                return Err(CsvError::UnexpectedCharacter { line, column });
+            }
+            after
+        } else {
+            let len = rest.find(dialect.delimiter).unwrap_or(rest.len());
+            fields.push(Cow::Borrowed(&rest[..len]));
+            pos + len
+        };
+        if end >= text.len() {
+            return Ok(fields);
+        }
+        pos = end + dialect.delimiter.len_utf8();
+    }
+}
+
+// 引用符で囲まれたフィールドを解き、(フィールド, 閉じ引用符の位置)を返す
+fn unquote(text: &str, start: usize, quote: char, escape: Option<char>) -> Option<(Cow<'_, str>, usize)> {
+    let mut owned: Option<String> = None;
+    let mut segment = start;
+    let mut pos = start;
+    loop {
+        let offset = text[pos..].find(|ch| ch == quote || Some(ch) == escape)?;
+        let at = pos + offset;
+        let ch = text[at..].chars().next()?;
+        if Some(ch) == escape {
+            let escaped = text[at + ch.len_utf8()..].chars().next()?;
+            let buffer = owned.get_or_insert_with(String::new);
+            buffer.push_str(&text[segment..at]);
+            buffer.push(escaped);
+            pos = at + ch.len_utf8() + escaped.len_utf8();
+            segment = pos;
+        } else if escape.is_none() && text[at + ch.len_utf8()..].starts_with(quote) {
+            let buffer = owned.get_or_insert_with(String::new);
+            buffer.push_str(&text[segment..at]);
+            buffer.push(quote);
+            pos = at + 2 * quote.len_utf8();
+            segment = pos;
+        } else {
+            let field = match owned {
+                Some(mut buffer) => {
+                    buffer.push_str(&text[segment..at]);
+                    Cow::Owned(buffer)
+                }
+                None => Cow::Borrowed(&text[start..at]),
+            };
+            return Some((field, at));
+        }
+    }
+}
+
+/// 必要なときだけフィールドを引用符で囲むライター
+pub struct Writer<W> {
+    sink: W,
+    dialect: Dialect,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(sink: W, dialect: Dialect) -> Writer<W> {
+        Writer { sink, dialect }
+    }
+
+    pub fn write_record<I, S>(&mut self, fields: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let fields: Vec<S> = fields.into_iter().collect();
+        // 空のフィールド1つだけのレコードは、囲まないと空行になってしまう
+        let lone_empty = fields.len() == 1 && fields[0].as_ref().is_empty();
+        let mut line = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                line.push(self.dialect.delimiter);
+            }
+            self.push_field(&mut line, field.as_ref(), lone_empty);
+        }
+        line.push_str(self.dialect.terminator);
+        self.sink.write_all(line.as_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    fn push_field(&self, line: &mut String, field: &str, force_quote: bool) {
+        let quote = match self.dialect.quote {
+            Some(quote) => quote,
+            None => {
+                line.push_str(field);
+                return;
+            }
+        };
+        let escape = self.dialect.escape;
+        let needs_quote = force_quote
+            || field.contains(|ch| {
+                ch == self.dialect.delimiter || ch == quote || ch == '\n' || ch == '\r' || Some(ch) == escape
+            });
+        if !needs_quote {
+            line.push_str(field);
+            return;
+        }
+        line.push(quote);
+        for ch in field.chars() {
+            if ch == quote || Some(ch) == escape {
+                line.push(escape.unwrap_or(quote));
+            }
+            line.push(ch);
+        }
+        line.push(quote);
+    }
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// 引用符が閉じないままレコード（またはファイル）が終わった
+    UnterminatedQuote { line: usize },
+    /// 閉じ引用符の直後に区切り文字でも行末でもない文字がある
+    UnexpectedCharacter { line: usize, column: usize },
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> CsvError {
+        CsvError::Io(error)
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CsvError::Io(ref error) => write!(dest, "{}", error),
+            CsvError::UnterminatedQuote { line } => write!(dest, "line {}: unterminated quoted field", line),
+            CsvError::UnexpectedCharacter { line, column } => {
+                write!(dest, "line {}, column {}: unexpected character after closing quote", line, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            CsvError::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,509 @@
+// 浮動小数点数の最短表記・正確な10進展開・厳格な解析
+//
+// "{:.6}"や"{:e}"は桁数を決めて丸めるので、読み戻すと元のf64と一致するとは限らない。Shortestは読み戻して
+// 同じf64になる最短の10進表記を、Exactは2進数の値をそのまま10進に展開した表記を返す。
+// 最短表記の桁はBurger & Dybvigの方法（多倍長整数で丸めの境界を正確に比べる）で生成する。
+// FloatGrammarは"inf"や"nan"、".5"のように先頭が数字でない表記を拒む、文法を選べる解析器。
+
+use std::cmp::Ordering;
+use std::fmt;
+
+// 下位の桁から32ビットずつ並べた多倍長の非負整数。上位の0は持たない
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Big(Vec<u32>);
+
+impl Big {
+    fn new(value: u64) -> Big {
+        let mut big = Big(vec![value as u32, (value >> 32) as u32]);
+        big.trim();
+        big
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in &mut self.0 {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+        self.trim();
+    }
+
+    fn mul_pow2(&mut self, exponent: u32) {
+        if self.0.is_empty() {
+            return;
+        }
+        let bits = exponent % 32;
+        if bits > 0 {
+            let mut carry = 0u32;
+            for limb in &mut self.0 {
+                let shifted = (*limb << bits) | carry;
+                carry = *limb >> (32 - bits);
+                *limb = shifted;
+            }
+            if carry > 0 {
+                self.0.push(carry);
+            }
+        }
+        let words = (exponent / 32) as usize;
+        self.0.splice(0..0, std::iter::repeat_n(0, words));
+    }
+
+    fn mul_pow5(&mut self, exponent: u32) {
+        // 5^13が32ビットに収まる最大の累乗
+        for _ in 0..exponent / 13 {
+            self.mul_small(1_220_703_125);
+        }
+        self.mul_small(5u32.pow(exponent % 13));
+    }
+
+    fn mul_pow10(&mut self, exponent: u32) {
+        self.mul_pow5(exponent);
+        self.mul_pow2(exponent);
+    }
+
+    fn add(&self, other: &Big) -> Big {
+        let mut sum = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.0.len().max(other.0.len()) {
+            let total = u64::from(*self.0.get(i).unwrap_or(&0)) + u64::from(*other.0.get(i).unwrap_or(&0)) + carry;
+            sum.push(total as u32);
+            carry = total >> 32;
+        }
+        sum.push(carry as u32);
+        let mut sum = Big(sum);
+        sum.trim();
+        sum
+    }
+
+    // selfはother以上であること
+    fn sub_assign(&mut self, other: &Big) {
+        let mut borrow = 0i64;
+        for i in 0..self.0.len() {
+            let difference = i64::from(self.0[i]) - i64::from(*other.0.get(i).unwrap_or(&0)) - borrow;
+            borrow = i64::from(difference < 0);
+            self.0[i] = (difference + (borrow << 32)) as u32;
+        }
+        self.trim();
+    }
+
+    fn div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.0.iter_mut().rev() {
+            let current = (remainder << 32) | u64::from(*limb);
+            *limb = (current / u64::from(divisor)) as u32;
+            remainder = current % u64::from(divisor);
+        }
+        self.trim();
+        remainder as u32
+    }
+
+    fn to_decimal(&self) -> String {
+        let mut value = self.clone();
+        let mut chunks = Vec::new();
+        while !value.0.is_empty() {
+            chunks.push(value.div_small(1_000_000_000));
+        }
+        let mut text = chunks.pop().map_or("0".to_string(), |chunk| chunk.to_string());
+        for chunk in chunks.iter().rev() {
+            text.push_str(&format!("{:09}", chunk));
+        }
+        text
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Big) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Big) -> Ordering {
+        self.0.len().cmp(&other.0.len()).then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+// 仮数部と2の指数（値はmantissa×2^exponent）、下の境界が上の境界より近いか（2の累乗のとき）
+fn decompose(value: f64) -> (u64, i32, bool) {
+    let bits = value.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    if biased == 0 {
+        (fraction, -1074, false)
+    } else {
+        (fraction | (1 << 52), biased - 1075, fraction == 0 && biased > 1)
+    }
+}
+
+/// 10進の桁と指数。値はd.ddd…×10^exponent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Digits {
+    pub negative: bool,
+    pub digits: String,
+    pub exponent: i32,
+}
+
+/// 読み戻して同じf64になる最短の桁。有限でない値はNone
+///
+/// 最短の候補が複数あるときは元の値に最も近いものを選ぶ。0は"0"と指数0になる。
+pub fn shortest_digits(value: f64) -> Option<Digits> {
+    if !value.is_finite() {
+        return None;
+    }
+    let negative = value.is_sign_negative();
+    if value == 0.0 {
+        return Some(Digits { negative, digits: "0".to_string(), exponent: 0 });
+    }
+    let value = value.abs();
+    let (mantissa, exponent, asymmetric) = decompose(value);
+    // 仮数部が偶数なら、境界ちょうどの値も読み戻すと元の値に丸められる
+    let inclusive = mantissa % 2 == 0;
+
+    // value = r / s、丸めの境界までの距離がm_plus / sとm_minus / s
+    let (mut r, mut s, mut m_plus, mut m_minus) = (Big::new(mantissa), Big::new(1), Big::new(1), Big::new(1));
+    let shift = if asymmetric { 2 } else { 1 };
+    if exponent >= 0 {
+        r.mul_pow2(exponent as u32 + shift);
+        s.mul_pow2(shift);
+        m_plus.mul_pow2(exponent as u32 + shift - 1);
+        m_minus.mul_pow2(exponent as u32);
+    } else {
+        r.mul_pow2(shift);
+        s.mul_pow2((-exponent) as u32 + shift);
+        m_plus.mul_pow2(shift - 1);
+    }
+
+    // 先頭の桁の位置を見積もり、s > r + m_plusになるよう1つ直す
+    let mut k = (value.log10() - 1e-10).ceil() as i32;
+    if k >= 0 {
+        s.mul_pow10(k as u32);
+    } else {
+        r.mul_pow10((-k) as u32);
+        m_plus.mul_pow10((-k) as u32);
+        m_minus.mul_pow10((-k) as u32);
+    }
+    let above_high = |r: &Big, m_plus: &Big, s: &Big| match r.add(m_plus).cmp(s) {
+        Ordering::Greater => true,
+        Ordering::Equal => inclusive,
+        Ordering::Less => false,
+    };
+    if above_high(&r, &m_plus, &s) {
+        s.mul_small(10);
+        k += 1;
+    }
+
+    let mut digits = String::new();
+    loop {
+        r.mul_small(10);
+        m_plus.mul_small(10);
+        m_minus.mul_small(10);
+        let mut digit = 0u8;
+        while r >= s {
+            r.sub_assign(&s);
+            digit += 1;
+        }
+        let below_low = match r.cmp(&m_minus) {
+            Ordering::Less => true,
+            Ordering::Equal => inclusive,
+            Ordering::Greater => false,
+        };
+        let above = above_high(&r, &m_plus, &s);
+        let digit = match (below_low, above) {
+            (false, false) => {
+                digits.push((b'0' + digit) as char);
+                continue;
+            }
+            (true, false) => digit,
+            (false, true) => digit + 1,
+            // どちらに丸めても読み戻せるなら近いほう
+            (true, true) => {
+                if r.add(&r) < s {
+                    digit
+                } else {
+                    digit + 1
+                }
+            }
+        };
+        digits.push((b'0' + digit) as char);
+        break;
+    }
+    Some(Digits { negative, digits, exponent: k - 1 })
+}
+
+/// 読み戻して同じf64になる最短の表記
+///
+/// 10^-7以上10^21未満は"0.1"や"123456"のように小数点で、それ以外は"1e21"や"1.5e-8"のように指数で書く。
+/// "{:e}"なら常に指数で書く。
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Shortest(pub f64);
+
+impl Shortest {
+    fn write(&self, dest: &mut fmt::Formatter, scientific: bool) -> fmt::Result {
+        let digits = match shortest_digits(self.0) {
+            Some(digits) => digits,
+            None => return dest.pad(&self.0.to_string()),
+        };
+        let sign = if digits.negative { "-" } else { "" };
+        let (first, rest) = digits.digits.split_at(1);
+        let exponent = digits.exponent;
+        let text = if scientific || !(-7..21).contains(&exponent) {
+            let point = if rest.is_empty() { "" } else { "." };
+            format!("{}{}{}{}e{}", sign, first, point, rest, exponent)
+        } else if exponent < 0 {
+            format!("{}0.{}{}", sign, "0".repeat((-exponent - 1) as usize), digits.digits)
+        } else {
+            let integer_len = exponent as usize + 1;
+            let all = &digits.digits;
+            if all.len() <= integer_len {
+                format!("{}{}{}", sign, all, "0".repeat(integer_len - all.len()))
+            } else {
+                format!("{}{}.{}", sign, &all[..integer_len], &all[integer_len..])
+            }
+        };
+        dest.pad(&text)
+    }
+}
+
+impl fmt::Display for Shortest {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        self.write(dest, false)
+    }
+}
+
+impl fmt::LowerExp for Shortest {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        self.write(dest, true)
+    }
+}
+
+/// 2進数の値を丸めずに10進に展開した表記（0.1は"0.1000000000000000055511151231257827021181583404541015625"）
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Exact(pub f64);
+
+impl fmt::Display for Exact {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        if !self.0.is_finite() {
+            return dest.pad(&self.0.to_string());
+        }
+        let sign = if self.0.is_sign_negative() { "-" } else { "" };
+        let (mantissa, exponent, _) = decompose(self.0.abs());
+        let mut value = Big::new(mantissa);
+        let text = if exponent >= 0 {
+            value.mul_pow2(exponent as u32);
+            format!("{}{}", sign, value.to_decimal())
+        } else {
+            // mantissa / 2^k = mantissa×5^k / 10^k
+            let places = (-exponent) as usize;
+            value.mul_pow5(places as u32);
+            let decimal = value.to_decimal();
+            let decimal = format!("{}{}", "0".repeat((places + 1).saturating_sub(decimal.len())), decimal);
+            let (integer, fraction) = decimal.split_at(decimal.len() - places);
+            let fraction = fraction.trim_end_matches('0');
+            if fraction.is_empty() {
+                format!("{}{}", sign, integer)
+            } else {
+                format!("{}{}.{}", sign, integer, fraction)
+            }
+        };
+        dest.pad(&text)
+    }
+}
+
+/// 解析の失敗。positionは文字列中のバイト位置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    Unexpected { position: usize, found: char },
+    ExpectedDigit { position: usize },
+    /// 有限のf64で表せない（"1e400"）
+    OutOfRange,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Empty => write!(dest, "empty string"),
+            ParseError::Unexpected { position, found } => write!(dest, "unexpected '{}' at byte {}", found, position),
+            ParseError::ExpectedDigit { position } => write!(dest, "expected a digit at byte {}", position),
+            ParseError::OutOfRange => write!(dest, "value out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 受け付ける浮動小数点数の文法
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FloatGrammar {
+    plus_sign: bool,
+    leading_dot: bool,
+    trailing_dot: bool,
+    exponent: bool,
+    underscores: bool,
+    special: bool,
+    overflow: bool,
+}
+
+impl FloatGrammar {
+    /// "-"[0-9]+("."[0-9]+)?([eE][+-]?[0-9]+)?だけを受け付け、無限大になる値も拒む
+    pub fn strict() -> FloatGrammar {
+        FloatGrammar {
+            plus_sign: false,
+            leading_dot: false,
+            trailing_dot: false,
+            exponent: true,
+            underscores: false,
+            special: false,
+            overflow: false,
+        }
+    }
+
+    /// f64::from_strと同じ文法（"+.5", "5.", "inf", "NaN", "1e400"も受け付ける）
+    pub fn rust() -> FloatGrammar {
+        FloatGrammar {
+            plus_sign: true,
+            leading_dot: true,
+            trailing_dot: true,
+            exponent: true,
+            underscores: false,
+            special: true,
+            overflow: true,
+        }
+    }
+
+    /// 先頭の'+'
+    pub fn plus_sign(mut self, allow: bool) -> FloatGrammar {
+        self.plus_sign = allow;
+        self
+    }
+
+    /// 整数部のない".5"
+    pub fn leading_dot(mut self, allow: bool) -> FloatGrammar {
+        self.leading_dot = allow;
+        self
+    }
+
+    /// 小数部のない"5."
+    pub fn trailing_dot(mut self, allow: bool) -> FloatGrammar {
+        self.trailing_dot = allow;
+        self
+    }
+
+    /// "1e-3"の指数部
+    pub fn exponent(mut self, allow: bool) -> FloatGrammar {
+        self.exponent = allow;
+        self
+    }
+
+    /// 数字の間の'_'（"1_000.5"）
+    pub fn underscores(mut self, allow: bool) -> FloatGrammar {
+        self.underscores = allow;
+        self
+    }
+
+    /// "inf", "infinity", "nan"（大文字小文字は区別しない）
+    pub fn special(mut self, allow: bool) -> FloatGrammar {
+        self.special = allow;
+        self
+    }
+
+    /// 大きすぎて無限大になる値
+    pub fn overflow(mut self, allow: bool) -> FloatGrammar {
+        self.overflow = allow;
+        self
+    }
+
+    pub fn parse(&self, text: &str) -> Result<f64, ParseError> {
+        if text.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let bytes = text.as_bytes();
+        let unexpected = |position: usize| match text[position..].chars().next() {
+            Some(found) => ParseError::Unexpected { position, found },
+            None => ParseError::ExpectedDigit { position },
+        };
+        let mut i = 0;
+        if bytes[0] == b'-' || (bytes[0] == b'+' && self.plus_sign) {
+            i += 1;
+        }
+        let special = &text[i..];
+        if self.special && ["inf", "infinity", "nan"].iter().any(|word| special.eq_ignore_ascii_case(word)) {
+            return Ok(text.parse().expect("special value"));
+        }
+
+        let integer_start = i;
+        i = self.digits(text, i)?;
+        let has_integer = i > integer_start;
+        let leading_dot = self.leading_dot && bytes.get(i) == Some(&b'.');
+        if !(has_integer || leading_dot) {
+            return Err(match bytes.get(i) {
+                Some(&b) if b.is_ascii_digit() || b == b'.' => ParseError::ExpectedDigit { position: i },
+                _ => unexpected(i),
+            });
+        }
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            let fraction_start = i;
+            i = self.digits(text, i)?;
+            if i == fraction_start && !(self.trailing_dot && has_integer) {
+                return Err(ParseError::ExpectedDigit { position: i });
+            }
+        }
+        if self.exponent && matches!(bytes.get(i), Some(b'e') | Some(b'E')) {
+            i += 1;
+            if matches!(bytes.get(i), Some(b'+') | Some(b'-')) {
+                i += 1;
+            }
+            let exponent_start = i;
+            i = self.digits(text, i)?;
+            if i == exponent_start {
+                return Err(ParseError::ExpectedDigit { position: i });
+            }
+        }
+        if i < text.len() {
+            return Err(unexpected(i));
+        }
+
+        // ここまでで検査した文字列はf64::from_strの文法にも合う
+        let value: f64 = text.replace('_', "").parse().expect("validated float");
+        if value.is_infinite() && !self.overflow {
+            return Err(ParseError::OutOfRange);
+        }
+        Ok(value)
+    }
+
+    // 数字の並びの終わり。'_'は数字の間にだけ置ける
+    fn digits(&self, text: &str, mut i: usize) -> Result<usize, ParseError> {
+        let bytes = text.as_bytes();
+        let start = i;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'0'..=b'9' => i += 1,
+                b'_' if self.underscores => {
+                    let next_digit = bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit());
+                    if i == start || !next_digit {
+                        return Err(ParseError::Unexpected { position: i, found: '_' });
+                    }
+                    i += 1;
+                }
+                _ => break,
+            }
+        }
+        Ok(i)
+    }
+}
+
+/// FloatGrammar::strict()で解析する
+pub fn parse_strict(text: &str) -> Result<f64, ParseError> {
+    FloatGrammar::strict().parse(text)
+}
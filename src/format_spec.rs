@@ -0,0 +1,605 @@
+// 実行時に解釈するformat!の書式文字列
+//
+// 設定ファイルに"{lsb:02x} {msb:02x} {insn}"のような書式を置いておき、実行時の値で埋めたい。
+// std::fmtの書式仕様（引数の位置・名前・暗黙の順番、詰め物と寄せ方、符号、'#'、'0'、幅、精度、
+// "1$"や"width$"による参照、"{{"と"}}"）を解析し、Argsに入れた値で出力する。
+// 幅はformat!と同じく文字数で数える（表示幅で揃えたい場合はalign::Paddedを使う）。
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::align::Align;
+
+/// 引数の参照。暗黙の"{}"は解析時に位置へ置き換える
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Argument {
+    Index(usize),
+    Name(String),
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Argument::Index(index) => write!(dest, "{}", index),
+            Argument::Name(ref name) => write!(dest, "{}", name),
+        }
+    }
+}
+
+/// 幅や精度の値
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Count {
+    Is(usize),
+    /// "1$"、"width$"、精度の".*"
+    Param(Argument),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    Plus,
+    Minus,
+}
+
+/// 書式の型（"{:?}"の'?'、"{:x}"の'x'など）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatType {
+    Display,
+    Debug,
+    /// "x?"
+    LowerHexDebug,
+    /// "X?"
+    UpperHexDebug,
+    LowerHex,
+    UpperHex,
+    Octal,
+    Binary,
+    LowerExp,
+    UpperExp,
+}
+
+impl FormatType {
+    fn parse(text: &str) -> Option<FormatType> {
+        match text {
+            "" => Some(FormatType::Display),
+            "?" => Some(FormatType::Debug),
+            "x?" => Some(FormatType::LowerHexDebug),
+            "X?" => Some(FormatType::UpperHexDebug),
+            "x" => Some(FormatType::LowerHex),
+            "X" => Some(FormatType::UpperHex),
+            "o" => Some(FormatType::Octal),
+            "b" => Some(FormatType::Binary),
+            "e" => Some(FormatType::LowerExp),
+            "E" => Some(FormatType::UpperExp),
+            _ => None,
+        }
+    }
+}
+
+/// ':'の後ろの書式仕様
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatSpec {
+    pub fill: char,
+    pub align: Option<Align>,
+    pub sign: Option<Sign>,
+    pub alternate: bool,
+    pub zero_pad: bool,
+    pub width: Option<Count>,
+    pub precision: Option<Count>,
+    pub format_type: FormatType,
+}
+
+impl Default for FormatSpec {
+    fn default() -> FormatSpec {
+        FormatSpec {
+            fill: ' ',
+            align: None,
+            sign: None,
+            alternate: false,
+            zero_pad: false,
+            width: None,
+            precision: None,
+            format_type: FormatType::Display,
+        }
+    }
+}
+
+/// 書式文字列の断片
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Piece {
+    /// "{{"と"}}"を解いた文字列
+    Literal(String),
+    Placeholder {
+        /// '{'のバイト位置
+        position: usize,
+        argument: Argument,
+        spec: FormatSpec,
+    },
+}
+
+/// 解析済みの書式文字列
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatString {
+    pieces: Vec<Piece>,
+}
+
+impl FormatString {
+    pub fn parse(template: &str) -> Result<FormatString, FormatError> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut next_index = 0;
+        let mut pos = 0;
+        while let Some(ch) = template[pos..].chars().next() {
+            let rest = &template[pos..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                literal.push(ch);
+                pos += 2;
+            } else if ch == '}' {
+                return Err(FormatError::UnmatchedBrace { position: pos });
+            } else if ch == '{' {
+                let close = match rest[1..].find(['{', '}']) {
+                    Some(i) if rest[1 + i..].starts_with('}') => pos + 1 + i,
+                    _ => return Err(FormatError::UnmatchedBrace { position: pos }),
+                };
+                if !literal.is_empty() {
+                    pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                }
+                let (argument, spec) = parse_placeholder(&template[pos + 1..close], pos + 1, &mut next_index)?;
+                pieces.push(Piece::Placeholder { position: pos, argument, spec });
+                pos = close + 1;
+            } else {
+                literal.push(ch);
+                pos += ch.len_utf8();
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(FormatString { pieces })
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    /// 引数の値で埋める
+    pub fn render(&self, args: &Args) -> Result<String, FormatError> {
+        let mut result = String::new();
+        for piece in &self.pieces {
+            match *piece {
+                Piece::Literal(ref text) => result.push_str(text),
+                Piece::Placeholder { position, ref argument, ref spec } => {
+                    let value = args
+                        .get(argument)
+                        .ok_or_else(|| FormatError::MissingArgument { position, argument: argument.clone() })?;
+                    let width = resolve_count(spec.width.as_ref(), args, position)?;
+                    let precision = resolve_count(spec.precision.as_ref(), args, position)?;
+                    result.push_str(&format_value(value, spec, width, precision, position)?);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+// '{'と'}'の間を解析する。offsetはエラー報告用の開始位置
+fn parse_placeholder(
+    inner: &str,
+    offset: usize,
+    next_index: &mut usize,
+) -> Result<(Argument, FormatSpec), FormatError> {
+    let (argument_text, spec_text) = match inner.find(':') {
+        Some(i) => (inner[..i].trim(), Some((&inner[i + 1..], offset + i + 1))),
+        None => (inner.trim(), None),
+    };
+    let argument = if argument_text.is_empty() {
+        None
+    } else {
+        Some(parse_argument(argument_text).ok_or_else(|| FormatError::InvalidSpec {
+            position: offset,
+            message: format!("invalid argument {:?}", argument_text),
+        })?)
+    };
+
+    let (mut spec, star) = match spec_text {
+        Some((text, position)) => parse_spec(text, position)?,
+        None => (FormatSpec::default(), false),
+    };
+    // ".*"は値より先に次の引数を精度として使う
+    if star {
+        spec.precision = Some(Count::Param(Argument::Index(*next_index)));
+        *next_index += 1;
+    }
+    let argument = argument.unwrap_or_else(|| {
+        *next_index += 1;
+        Argument::Index(*next_index - 1)
+    });
+    Ok((argument, spec))
+}
+
+fn parse_argument(text: &str) -> Option<Argument> {
+    if text.bytes().all(|b| b.is_ascii_digit()) {
+        text.parse().ok().map(Argument::Index)
+    } else if is_identifier(text) {
+        Some(Argument::Name(text.to_string()))
+    } else {
+        None
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|ch| ch == '_' || ch.is_alphabetic())
+        && chars.all(|ch| ch == '_' || ch.is_alphanumeric())
+}
+
+// [[fill]align][sign]['#']['0'][width]['.' precision]type。精度が".*"ならtrueも返す
+fn parse_spec(text: &str, offset: usize) -> Result<(FormatSpec, bool), FormatError> {
+    let mut spec = FormatSpec::default();
+    let mut star = false;
+    let mut rest = text;
+    let position = |rest: &str| offset + text.len() - rest.len();
+
+    let mut chars = rest.chars();
+    let first = chars.next();
+    if let Some(align) = chars.next().and_then(Align::from_char) {
+        spec.fill = first.unwrap_or(' ');
+        spec.align = Some(align);
+        rest = chars.as_str();
+    } else if let Some(align) = first.and_then(Align::from_char) {
+        spec.align = Some(align);
+        rest = &rest[1..];
+    }
+
+    if let Some(stripped) = rest.strip_prefix('+') {
+        spec.sign = Some(Sign::Plus);
+        rest = stripped;
+    } else if let Some(stripped) = rest.strip_prefix('-') {
+        spec.sign = Some(Sign::Minus);
+        rest = stripped;
+    }
+    if let Some(stripped) = rest.strip_prefix('#') {
+        spec.alternate = true;
+        rest = stripped;
+    }
+    // "0$"は0番目の引数を幅にする指定
+    if rest.starts_with('0') && !rest[1..].starts_with('$') {
+        spec.zero_pad = true;
+        rest = &rest[1..];
+    }
+
+    if let Some((count, after)) = parse_count(rest) {
+        spec.width = Some(count);
+        rest = after;
+    }
+    if let Some(after_dot) = rest.strip_prefix('.') {
+        if let Some(after) = after_dot.strip_prefix('*') {
+            // 引数の位置はparse_placeholderで決める
+            star = true;
+            rest = after;
+        } else if let Some((count, after)) = parse_count(after_dot) {
+            spec.precision = Some(count);
+            rest = after;
+        } else {
+            return Err(FormatError::InvalidSpec {
+                position: position(after_dot),
+                message: "expected precision after '.'".to_string(),
+            });
+        }
+    }
+
+    spec.format_type = FormatType::parse(rest).ok_or_else(|| FormatError::InvalidSpec {
+        position: position(rest),
+        message: format!("unknown format type {:?}", rest),
+    })?;
+    Ok((spec, star))
+}
+
+// 整数か、"$"の付いた引数参照
+fn parse_count(text: &str) -> Option<(Count, &str)> {
+    let len = text.find(|ch: char| ch != '_' && !ch.is_alphanumeric()).unwrap_or(text.len());
+    let word = &text[..len];
+    if word.is_empty() {
+        return None;
+    }
+    if let Some(after) = text[len..].strip_prefix('$') {
+        return parse_argument(word).map(|argument| (Count::Param(argument), after));
+    }
+    // "{:x}"のxは幅でなく型
+    let digits = text.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(text.len());
+    text[..digits].parse().ok().map(|width| (Count::Is(width), &text[digits..]))
+}
+
+fn resolve_count(count: Option<&Count>, args: &Args, position: usize) -> Result<Option<usize>, FormatError> {
+    let argument = match count {
+        None => return Ok(None),
+        Some(&Count::Is(n)) => return Ok(Some(n)),
+        Some(Count::Param(argument)) => argument,
+    };
+    match args.get(argument) {
+        Some(&Value::UInt(n)) => Ok(Some(n as usize)),
+        Some(&Value::Int(n, _)) if n >= 0 => Ok(Some(n as usize)),
+        Some(_) => Err(FormatError::InvalidCount { position, argument: argument.clone() }),
+        None => Err(FormatError::MissingArgument { position, argument: argument.clone() }),
+    }
+}
+
+/// 実行時の値
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    /// 値と元の型のビット数。"{:x}"で負の値を元の幅の2の補数にする（-1i8は"ff"）
+    Int(i64, u32),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match *self {
+            Value::Str(_) => "string",
+            Value::Int(..) | Value::UInt(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Value {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::Str(value)
+    }
+}
+
+macro_rules! value_from {
+    ($variant:ident, $target:ty: $($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(value: $t) -> Value {
+                Value::$variant(value as $target)
+            }
+        })*
+    };
+}
+
+macro_rules! signed_value_from {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(value: $t) -> Value {
+                Value::Int(value as i64, <$t>::BITS)
+            }
+        })*
+    };
+}
+
+signed_value_from!(i8, i16, i32, i64, isize);
+value_from!(UInt, u64: u8, u16, u32, u64, usize);
+value_from!(Float, f64: f32, f64);
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Value {
+        Value::Char(value)
+    }
+}
+
+/// 位置引数と名前付き引数
+#[derive(Clone, Debug, Default)]
+pub struct Args {
+    positional: Vec<Value>,
+    named: HashMap<String, Value>,
+}
+
+impl Args {
+    pub fn new() -> Args {
+        Args::default()
+    }
+
+    /// 次の位置引数を追加する
+    pub fn arg<V: Into<Value>>(mut self, value: V) -> Args {
+        self.positional.push(value.into());
+        self
+    }
+
+    pub fn named<V: Into<Value>>(mut self, name: &str, value: V) -> Args {
+        self.named.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn get(&self, argument: &Argument) -> Option<&Value> {
+        match *argument {
+            Argument::Index(index) => self.positional.get(index),
+            Argument::Name(ref name) => self.named.get(name),
+        }
+    }
+}
+
+impl From<HashMap<String, Value>> for Args {
+    fn from(named: HashMap<String, Value>) -> Args {
+        Args { positional: Vec::new(), named }
+    }
+}
+
+// 値を書式化し、幅に詰める
+fn format_value(
+    value: &Value,
+    spec: &FormatSpec,
+    width: Option<usize>,
+    precision: Option<usize>,
+    position: usize,
+) -> Result<String, FormatError> {
+    let unsupported = || FormatError::UnsupportedType { position, format_type: spec.format_type, value: value.kind() };
+    let integer = matches!(*value, Value::Int(..) | Value::UInt(_));
+    // 2の補数で表す整数は負にならないので、"{:+x}"は常に'+'を付ける
+    let radix = integer
+        && matches!(
+            spec.format_type,
+            FormatType::LowerHex
+                | FormatType::UpperHex
+                | FormatType::Octal
+                | FormatType::Binary
+                | FormatType::LowerHexDebug
+                | FormatType::UpperHexDebug
+        );
+    let negative = match *value {
+        Value::Int(n, _) => n < 0 && !radix,
+        Value::Float(f) => f.is_sign_negative() && !f.is_nan(),
+        _ => false,
+    };
+    let numeric = integer || matches!(*value, Value::Float(_));
+    // stdと同じく、NaNには"{:+}"でも符号を付けない
+    let nan = matches!(*value, Value::Float(f) if f.is_nan());
+
+    // 符号と"0x"などの接頭辞、本体
+    let mut prefix = String::new();
+    let body = match spec.format_type {
+        FormatType::Display | FormatType::Debug | FormatType::LowerHexDebug | FormatType::UpperHexDebug => {
+            let debug = spec.format_type != FormatType::Display;
+            match *value {
+                Value::Str(ref s) if debug => format!("{:?}", s),
+                Value::Str(ref s) => truncate_chars(s, precision),
+                Value::Char(c) if debug => format!("{:?}", c),
+                Value::Char(c) => truncate_chars(c.encode_utf8(&mut [0; 4]), precision),
+                Value::Bool(b) => truncate_chars(if b { "true" } else { "false" }, precision),
+                Value::Int(n, _) if !radix => n.unsigned_abs().to_string(),
+                Value::Int(n, bits) => radix_debug(twos_complement(n, bits), spec.format_type),
+                Value::UInt(n) => radix_debug(n, spec.format_type),
+                Value::Float(f) => match (precision, debug) {
+                    (Some(p), _) => format!("{:.*}", p, f.abs()),
+                    (None, true) => format!("{:?}", f.abs()),
+                    (None, false) => format!("{}", f.abs()),
+                },
+            }
+        }
+        FormatType::LowerHex | FormatType::UpperHex | FormatType::Octal | FormatType::Binary => {
+            if spec.alternate {
+                prefix.push_str(match spec.format_type {
+                    FormatType::Octal => "0o",
+                    FormatType::Binary => "0b",
+                    _ => "0x",
+                });
+            }
+            // 負の整数は元の型の幅の2の補数で表す（format!("{:x}", -1i8)と同じ）
+            let bits = match *value {
+                Value::Int(n, bits) => twos_complement(n, bits),
+                Value::UInt(n) => n,
+                _ => return Err(unsupported()),
+            };
+            match spec.format_type {
+                FormatType::LowerHex => format!("{:x}", bits),
+                FormatType::UpperHex => format!("{:X}", bits),
+                FormatType::Octal => format!("{:o}", bits),
+                _ => format!("{:b}", bits),
+            }
+        }
+        FormatType::LowerExp | FormatType::UpperExp => {
+            // 整数はf64に変換せず、10進の桁から書く（2^53を超える値も桁が変わらない）
+            let upper = spec.format_type == FormatType::UpperExp;
+            match *value {
+                Value::Int(n, _) => exponent(n.unsigned_abs(), precision, upper),
+                Value::UInt(n) => exponent(n, precision, upper),
+                Value::Float(f) => exponent(f.abs(), precision, upper),
+                _ => return Err(unsupported()),
+            }
+        }
+    };
+    if numeric {
+        if negative {
+            prefix.insert(0, '-');
+        } else if spec.sign == Some(Sign::Plus) && !nan {
+            prefix.insert(0, '+');
+        }
+    }
+
+    let len = prefix.chars().count() + body.chars().count();
+    let padding = width.unwrap_or(0).saturating_sub(len);
+    if spec.zero_pad && numeric {
+        // 符号と接頭辞の後ろを0で埋める
+        return Ok(format!("{}{}{}", prefix, "0".repeat(padding), body));
+    }
+    let align = spec.align.unwrap_or(if numeric { Align::Right } else { Align::Left });
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::Right => (padding, 0),
+    };
+    let fill = |count: usize| std::iter::repeat_n(spec.fill, count).collect::<String>();
+    Ok(format!("{}{}{}{}", fill(before), prefix, body, fill(after)))
+}
+
+fn truncate_chars(text: &str, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => text.chars().take(precision).collect(),
+        None => text.to_string(),
+    }
+}
+
+fn exponent<T: fmt::LowerExp + fmt::UpperExp>(magnitude: T, precision: Option<usize>, upper: bool) -> String {
+    match (precision, upper) {
+        (Some(p), false) => format!("{:.*e}", p, magnitude),
+        (Some(p), true) => format!("{:.*E}", p, magnitude),
+        (None, false) => format!("{:e}", magnitude),
+        (None, true) => format!("{:E}", magnitude),
+    }
+}
+
+fn twos_complement(n: i64, bits: u32) -> u64 {
+    if bits >= 64 {
+        n as u64
+    } else {
+        n as u64 & ((1 << bits) - 1)
+    }
+}
+
+fn radix_debug(n: u64, format_type: FormatType) -> String {
+    match format_type {
+        FormatType::LowerHexDebug => format!("{:x}", n),
+        FormatType::UpperHexDebug => format!("{:X}", n),
+        _ => n.to_string(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// 閉じていない'{'、または対応する'{'のない'}'
+    UnmatchedBrace { position: usize },
+    InvalidSpec { position: usize, message: String },
+    MissingArgument { position: usize, argument: Argument },
+    /// 幅・精度として参照した引数が非負の整数でない
+    InvalidCount { position: usize, argument: Argument },
+    /// "{:x}"に文字列を渡したなど、値の型が書式の型に合わない
+    UnsupportedType { position: usize, format_type: FormatType, value: &'static str },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormatError::UnmatchedBrace { position } => write!(dest, "unmatched brace at byte {}", position),
+            FormatError::InvalidSpec { position, ref message } => write!(dest, "{} at byte {}", message, position),
+            FormatError::MissingArgument { position, ref argument } => {
+                write!(dest, "missing argument '{}' for placeholder at byte {}", argument, position)
+            }
+            FormatError::InvalidCount { position, ref argument } => {
+                write!(dest, "argument '{}' used as width or precision is not a non-negative integer at byte {}",
+                       argument, position)
+            }
+            FormatError::UnsupportedType { position, format_type, value } => {
+                write!(dest, "cannot format {} with {:?} at byte {}", value, format_type, position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
@@ -0,0 +1,267 @@
+// /etc/hostsの読み書き
+//
+// 各行は「IPアドレス ホスト名 [別名...] [# コメント]」。コメント行や空白の揃え方は保持し、
+// 変更していない行は元のテキストのまま書き戻す。行末がCRLFのファイルはCRLFのまま書き戻す。
+
+use std::fmt;
+use std::net::IpAddr;
+
+/// 1行分のエントリ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub address: IpAddr,
+    /// IPv6のスコープ（"fe80::1%lo0"の"lo0"）
+    pub scope: Option<String>,
+    pub hostname: String,
+    pub aliases: Vec<String>,
+    /// 行末コメント（'#'の後ろ）
+    pub comment: Option<String>,
+}
+
+impl Entry {
+    /// 正式なホスト名と別名を順に返す
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.hostname.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.names().any(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        write!(dest, "{}", self.address)?;
+        if let Some(ref scope) = self.scope {
+            write!(dest, "%{}", scope)?;
+        }
+        write!(dest, "\t{}", self.hostname)?;
+        for alias in &self.aliases {
+            write!(dest, " {}", alias)?;
+        }
+        if let Some(ref comment) = self.comment {
+            write!(dest, " #{}", comment)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Line {
+    // originalとcrは行末の'\r'を除いたテキストと、'\r'があったか
+    Entry { entry: Entry, original: String, cr: bool },
+    // 空行とコメントだけの行（'\r'を含む元のテキスト）
+    Other(String),
+}
+
+/// hostsファイル全体
+#[derive(Clone, Debug)]
+pub struct HostsFile {
+    lines: Vec<Line>,
+    trailing_newline: bool,
+    // 最初の行がCRLFなら、追加した行もCRLFにする
+    crlf: bool,
+}
+
+impl Default for HostsFile {
+    fn default() -> HostsFile {
+        HostsFile::new()
+    }
+}
+
+impl HostsFile {
+    pub fn new() -> HostsFile {
+        HostsFile { lines: Vec::new(), trailing_newline: true, crlf: false }
+    }
+
+    pub fn parse(text: &str) -> Result<HostsFile, HostsError> {
+        let mut lines = Vec::new();
+        for (index, raw) in text.split_terminator('\n').enumerate() {
+            let line = raw.strip_suffix('\r').unwrap_or(raw);
+            match parse_line(line).map_err(|(column, kind)| HostsError { line: index + 1, column, kind })? {
+                Some(entry) => lines.push(Line::Entry { entry, original: line.to_string(), cr: line.len() < raw.len() }),
+                None => lines.push(Line::Other(raw.to_string())),
+            }
+        }
+        let crlf = text.find('\n').is_some_and(|end| text[..end].ends_with('\r'));
+        Ok(HostsFile { lines, trailing_newline: text.is_empty() || text.ends_with('\n'), crlf })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.lines.iter().filter_map(|line| match *line {
+            Line::Entry { ref entry, .. } => Some(entry),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// ホスト名（別名を含む、大文字・小文字は区別しない）に対応するアドレスを出現順に返す
+    pub fn lookup(&self, name: &str) -> Vec<IpAddr> {
+        self.entries().filter(|entry| entry.has_name(name)).map(|entry| entry.address).collect()
+    }
+
+    /// アドレスに対応するホスト名と別名を返す
+    pub fn names_of(&self, address: IpAddr) -> Vec<&str> {
+        self.entries().filter(|entry| entry.address == address).flat_map(Entry::names).collect()
+    }
+
+    /// エントリを末尾に追加する。ホスト名が不正ならエラー
+    pub fn add(&mut self, address: IpAddr, hostname: &str, aliases: &[&str]) -> Result<(), HostsError> {
+        let line = self.lines.len() + 1;
+        for name in std::iter::once(&hostname).chain(aliases) {
+            if !is_valid_hostname(name) {
+                return Err(HostsError { line, column: 0, kind: HostsErrorKind::InvalidHostname(name.to_string()) });
+            }
+        }
+        let entry = Entry {
+            address,
+            scope: None,
+            hostname: hostname.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            comment: None,
+        };
+        self.lines.push(Line::Entry { entry, original: String::new(), cr: self.crlf });
+        Ok(())
+    }
+
+    /// ホスト名・別名を取り除き、取り除いた数を返す
+    ///
+    /// 正式なホスト名を取り除いた場合は最初の別名が繰り上がり、名前がなくなった行は削除する。
+    pub fn remove(&mut self, name: &str) -> usize {
+        let mut removed = 0;
+        self.lines.retain_mut(|line| {
+            let entry = match *line {
+                Line::Entry { ref mut entry, .. } => entry,
+                Line::Other(_) => return true,
+            };
+            let before = entry.aliases.len();
+            entry.aliases.retain(|alias| !alias.eq_ignore_ascii_case(name));
+            removed += before - entry.aliases.len();
+            if entry.hostname.eq_ignore_ascii_case(name) {
+                removed += 1;
+                if entry.aliases.is_empty() {
+                    return false;
+                }
+                entry.hostname = entry.aliases.remove(0);
+            }
+            true
+        });
+        removed
+    }
+}
+
+impl fmt::Display for HostsFile {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                writeln!(dest)?;
+            }
+            match *line {
+                Line::Entry { ref entry, ref original, cr } => {
+                    if parse_line(original).ok().flatten().as_ref() == Some(entry) {
+                        dest.write_str(original)?;
+                    } else {
+                        write!(dest, "{}", entry)?;
+                    }
+                    if cr {
+                        dest.write_str("\r")?;
+                    }
+                }
+                Line::Other(ref text) => dest.write_str(text)?,
+            }
+        }
+        if self.trailing_newline && !self.lines.is_empty() {
+            writeln!(dest)?;
+        }
+        Ok(())
+    }
+}
+
+// エントリがない行はOk(None)、エラーは(桁, 種類)
+fn parse_line(line: &str) -> Result<Option<Entry>, (usize, HostsErrorKind)> {
+    let (content, comment) = match line.find('#') {
+        Some(i) => (&line[..i], Some(line[i + 1..].to_string())),
+        None => (line, None),
+    };
+    let mut fields = tokens(content);
+    let (column, address) = match fields.next() {
+        Some(field) => field,
+        None => return Ok(None),
+    };
+    let invalid = || (column, HostsErrorKind::InvalidAddress(address.to_string()));
+    // スコープはIPv6のアドレスにだけ付けられる
+    let (address, scope) = match address.split_once('%') {
+        Some((address, scope)) if !scope.is_empty() => (address, Some(scope.to_string())),
+        Some(_) => return Err(invalid()),
+        None => (address, None),
+    };
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    if scope.is_some() && !address.is_ipv6() {
+        return Err(invalid());
+    }
+
+    let mut names = Vec::new();
+    for (column, name) in fields {
+        if !is_valid_hostname(name) {
+            return Err((column, HostsErrorKind::InvalidHostname(name.to_string())));
+        }
+        names.push(name.to_string());
+    }
+    if names.is_empty() {
+        // コメントの前、アドレスの直後を指す
+        return Err((content.trim_end().len() + 1, HostsErrorKind::MissingHostname));
+    }
+    let hostname = names.remove(0);
+    Ok(Some(Entry { address, scope, hostname, aliases: names, comment }))
+}
+
+// 空白区切りの語と、その桁（1始まりのバイト位置）
+fn tokens(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split([' ', '\t'])
+        .scan(0, |offset, field| {
+            let column = *offset + 1;
+            *offset += field.len() + 1;
+            Some((column, field))
+        })
+        .filter(|&(_, field)| !field.is_empty())
+}
+
+/// RFC 1123のホスト名か（ラベルは英数字とハイフン、1〜63文字、全体で253文字以内）
+pub fn is_valid_hostname(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostsErrorKind {
+    InvalidAddress(String),
+    InvalidHostname(String),
+    MissingHostname,
+}
+
+/// 位置付きのエラー。行・桁は1始まりで、桁はバイト単位（add()で生じたエラーの桁は0）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostsError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: HostsErrorKind,
+}
+
+impl fmt::Display for HostsError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        write!(dest, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            HostsErrorKind::InvalidAddress(ref address) => write!(dest, "invalid address {:?}", address),
+            HostsErrorKind::InvalidHostname(ref name) => write!(dest, "invalid hostname {:?}", name),
+            HostsErrorKind::MissingHostname => write!(dest, "missing hostname"),
+        }
+    }
+}
+
+impl std::error::Error for HostsError {}
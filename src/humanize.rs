@@ -0,0 +1,380 @@
+// 人が読む大きさ・時間・SI接頭辞の表記
+//
+// "{:8.2} km/s"や"{:12.3e}"では桁の大きさを読み手が数えることになる。ByteSizeは"1.5 KiB"（"{:#}"なら
+// "1.5 kB"）、HumanDurationは"3h 02m 05s"（"{:#}"ならISO 8601の"PT3H2M5S"）、Siは"1.23k"や"4.5µ"と書く。
+// いずれも"{:.3}"で小数点以下の桁数、"{:>10}"で幅と寄せ方（既定は右寄せ）を指定でき、
+// 設定ファイルから同じ文字列を読み戻せるようにFromStrも実装する。
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::align::{Align, Padded};
+
+/// 解析の失敗。positionは文字列中のバイト位置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    InvalidNumber { position: usize },
+    UnknownUnit { position: usize, unit: String },
+    OutOfRange,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Empty => write!(dest, "empty string"),
+            ParseError::InvalidNumber { position } => write!(dest, "invalid number at byte {}", position),
+            ParseError::UnknownUnit { position, ref unit } => write!(dest, "unknown unit '{}' at byte {}", unit, position),
+            ParseError::OutOfRange => write!(dest, "value out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// 幅・寄せ方・詰め物の指定を適用する。"{:.2}"の精度は数値の桁数に使うので、Formatter::padのように切り詰めない
+fn pad(dest: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    let align = match dest.align() {
+        Some(fmt::Alignment::Left) => Align::Left,
+        Some(fmt::Alignment::Center) => Align::Center,
+        _ => Align::Right,
+    };
+    let width = dest.width().unwrap_or(0);
+    let fill = dest.fill();
+    write!(dest, "{}", Padded::new(text, width).align(align).fill(fill))
+}
+
+// 先頭の空白を除いた位置と、数値部分・残りに分ける。数値は符号なしの"12"や"1.5"
+fn split_number(text: &str, start: usize) -> Result<(&str, &str, usize), ParseError> {
+    let end = text
+        .char_indices()
+        .find(|&(_, ch)| !(ch.is_ascii_digit() || ch == '.'))
+        .map_or(text.len(), |(i, _)| i);
+    let number = &text[..end];
+    let valid = !number.is_empty()
+        && number.matches('.').count() <= 1
+        && number.bytes().any(|b| b.is_ascii_digit());
+    if !valid {
+        return Err(ParseError::InvalidNumber { position: start });
+    }
+    let rest = &text[end..];
+    let skipped = rest.len() - rest.trim_start().len();
+    Ok((number, rest.trim_start(), start + end + skipped))
+}
+
+// "1.5"をscale倍した整数。端数は切り捨てる
+fn scale_decimal(number: &str, scale: u128) -> Option<u128> {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let integer: u128 = if integer.is_empty() { 0 } else { integer.parse().ok()? };
+    // 小数部は18桁まで見る（10^18×1024^6でもu128に収まる）
+    let fraction = &fraction[..fraction.len().min(18)];
+    let numerator: u128 = if fraction.is_empty() { 0 } else { fraction.parse().ok()? };
+    let fraction = numerator * scale / 10u128.pow(fraction.len() as u32);
+    integer.checked_mul(scale)?.checked_add(fraction)
+}
+
+// 文字列の前後の空白を除き、空ならエラー。除いた先頭のバイト位置も返す
+fn trimmed(text: &str) -> Result<(&str, usize), ParseError> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    Ok((trimmed, text.len() - text.trim_start().len()))
+}
+
+/// バイト数。既定はIEC（1024倍）の単位、"{:#}"はSI（1000倍）の単位で表す
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+const IEC_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const SI_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+impl fmt::Display for ByteSize {
+    /// 1単位未満は"512 B"、それ以上は小数点以下1桁（"{:.2}"で変更）
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let (base, units) = if dest.alternate() { (1000.0, &SI_UNITS) } else { (1024.0, &IEC_UNITS) };
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= base && unit + 1 < units.len() {
+            value /= base;
+            unit += 1;
+        }
+        let text = if unit == 0 {
+            format!("{} B", self.0)
+        } else {
+            let precision = dest.precision().unwrap_or(1);
+            let mut mantissa = format!("{:.*}", precision, value);
+            // 丸めて1023.96 KiBが"1024.0 KiB"になるなら次の単位にする
+            if mantissa.parse::<f64>().unwrap_or(0.0) >= base && unit + 1 < units.len() {
+                value /= base;
+                unit += 1;
+                mantissa = format!("{:.*}", precision, value);
+            }
+            format!("{} {}", mantissa, units[unit])
+        };
+        pad(dest, &text)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ParseError;
+
+    /// "1536", "1.5 KiB", "2MB", "10 kb"。単位の大文字小文字は区別せず、"K"や"KB"は1000倍、"KiB"は1024倍
+    fn from_str(text: &str) -> Result<ByteSize, ParseError> {
+        let (text, start) = trimmed(text)?;
+        let (number, unit, unit_position) = split_number(text, start)?;
+        let lower = unit.to_lowercase();
+        let (prefix, binary) = match lower.strip_suffix("ib").or_else(|| lower.strip_suffix('i')) {
+            Some(prefix) => (prefix, true),
+            None => (lower.strip_suffix('b').unwrap_or(&lower), false),
+        };
+        let exponent = match (prefix, "kmgtpe".find(prefix)) {
+            ("", _) if !binary => 0,
+            (_, Some(i)) if prefix.len() == 1 => i as u32 + 1,
+            _ => return Err(ParseError::UnknownUnit { position: unit_position, unit: unit.to_string() }),
+        };
+        let scale = u128::from(if binary { 1024u64 } else { 1000 }).pow(exponent);
+        let bytes = scale_decimal(number, scale).ok_or(ParseError::OutOfRange)?;
+        if bytes > u128::from(u64::MAX) {
+            return Err(ParseError::OutOfRange);
+        }
+        Ok(ByteSize(bytes as u64))
+    }
+}
+
+/// 時間の長さ。既定は"2d 03h 02m 05s"、"{:#}"はISO 8601の"P2DT3H2M5S"
+///
+/// 秒未満は"{:.3}"で桁数を指定したときだけ表し、指定がなければ秒に四捨五入する。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let precision = dest.precision().unwrap_or(0).min(9);
+        let unit = 10u128.pow(9 - precision as u32);
+        // 指定の桁数に四捨五入したナノ秒
+        let nanos = (self.0.as_nanos() + unit / 2) / unit * unit;
+        let mut seconds = (nanos / NANOS_PER_SECOND) as u64;
+        let fraction = if precision == 0 {
+            String::new()
+        } else {
+            format!(".{:0width$}", (nanos % NANOS_PER_SECOND) / unit, width = precision)
+        };
+        let days = seconds / 86400;
+        seconds %= 86400;
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+        let mut text = String::new();
+        if dest.alternate() {
+            text.push('P');
+            if days > 0 {
+                text.push_str(&format!("{}D", days));
+            }
+            if hours > 0 || minutes > 0 || seconds > 0 || !fraction.is_empty() || days == 0 {
+                text.push('T');
+                if hours > 0 {
+                    text.push_str(&format!("{}H", hours));
+                }
+                if minutes > 0 {
+                    text.push_str(&format!("{}M", minutes));
+                }
+                if seconds > 0 || !fraction.is_empty() || (hours == 0 && minutes == 0) {
+                    text.push_str(&format!("{}{}S", seconds, fraction));
+                }
+            }
+        } else {
+            // 最上位の単位は0を詰めず、それより下は2桁にする
+            let parts = [(days, "d"), (hours, "h"), (minutes, "m")];
+            for &(value, suffix) in &parts {
+                if !text.is_empty() {
+                    text.push_str(&format!("{:02}{} ", value, suffix));
+                } else if value > 0 {
+                    text.push_str(&format!("{}{} ", value, suffix));
+                }
+            }
+            if text.is_empty() {
+                text.push_str(&format!("{}{}s", seconds, fraction));
+            } else {
+                text.push_str(&format!("{:02}{}s", seconds, fraction));
+            }
+        }
+        pad(dest, &text)
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = ParseError;
+
+    /// "3h 02m 05s", "1h30m", "1.5s", "250ms"のような数値と単位（d, h, m, s, ms, us/µs, ns）の並び、
+    /// またはISO 8601の"PT3H2M5S"や"P1W"。ISO 8601の年と月は長さが決まらないので受け付けない
+    fn from_str(text: &str) -> Result<HumanDuration, ParseError> {
+        let (text, start) = trimmed(text)?;
+        let nanos = if let Some(iso) = text.strip_prefix('P') {
+            parse_iso_duration(iso, start + 1)?
+        } else {
+            parse_human_duration(text, start)?
+        };
+        let seconds = nanos / NANOS_PER_SECOND;
+        if seconds > u128::from(u64::MAX) {
+            return Err(ParseError::OutOfRange);
+        }
+        Ok(HumanDuration(Duration::new(seconds as u64, (nanos % NANOS_PER_SECOND) as u32)))
+    }
+}
+
+fn parse_human_duration(mut text: &str, mut position: usize) -> Result<u128, ParseError> {
+    let mut total: u128 = 0;
+    while !text.is_empty() {
+        let (number, rest, unit_position) = split_number(text, position)?;
+        let end = rest.find(|ch: char| ch.is_ascii_digit() || ch == '.' || ch.is_whitespace()).unwrap_or(rest.len());
+        let unit = &rest[..end];
+        let scale = match unit {
+            "d" => 86400 * NANOS_PER_SECOND,
+            "h" => 3600 * NANOS_PER_SECOND,
+            "m" | "min" => 60 * NANOS_PER_SECOND,
+            "s" => NANOS_PER_SECOND,
+            "ms" => 1_000_000,
+            "us" | "µs" | "μs" => 1000,
+            "ns" => 1,
+            _ => return Err(ParseError::UnknownUnit { position: unit_position, unit: unit.to_string() }),
+        };
+        let nanos = scale_decimal(number, scale).ok_or(ParseError::OutOfRange)?;
+        total = total.checked_add(nanos).ok_or(ParseError::OutOfRange)?;
+        let rest = &rest[end..];
+        position = unit_position + end + (rest.len() - rest.trim_start().len());
+        text = rest.trim_start();
+    }
+    Ok(total)
+}
+
+fn parse_iso_duration(mut text: &str, mut position: usize) -> Result<u128, ParseError> {
+    let mut total: u128 = 0;
+    let mut in_time = false;
+    // "PT"や"P1DT"のように、Tの後に時間の要素がないのは誤り
+    let mut time_components = 0;
+    if text.is_empty() {
+        return Err(ParseError::InvalidNumber { position });
+    }
+    while !text.is_empty() {
+        if !in_time && text.starts_with('T') {
+            in_time = true;
+            text = &text[1..];
+            position += 1;
+            continue;
+        }
+        let end = text.find(|ch: char| !(ch.is_ascii_digit() || ch == '.' || ch == ',')).unwrap_or(text.len());
+        // 小数点には','も使える
+        let number = text[..end].replace(',', ".");
+        let unit = text[end..].chars().next();
+        if !number.bytes().any(|b| b.is_ascii_digit()) || number.matches('.').count() > 1 {
+            return Err(ParseError::InvalidNumber { position });
+        }
+        let scale = match (in_time, unit) {
+            (false, Some('W')) => 7 * 86400 * NANOS_PER_SECOND,
+            (false, Some('D')) => 86400 * NANOS_PER_SECOND,
+            (true, Some('H')) => 3600 * NANOS_PER_SECOND,
+            (true, Some('M')) => 60 * NANOS_PER_SECOND,
+            (true, Some('S')) => NANOS_PER_SECOND,
+            (_, unit) => {
+                let unit = unit.map_or(String::new(), |ch| ch.to_string());
+                return Err(ParseError::UnknownUnit { position: position + end, unit });
+            }
+        };
+        let nanos = scale_decimal(&number, scale).ok_or(ParseError::OutOfRange)?;
+        total = total.checked_add(nanos).ok_or(ParseError::OutOfRange)?;
+        if in_time {
+            time_components += 1;
+        }
+        text = &text[end + 1..];
+        position += end + 1;
+    }
+    if in_time && time_components == 0 {
+        return Err(ParseError::InvalidNumber { position });
+    }
+    Ok(total)
+}
+
+/// SI接頭辞（y〜Y）を付けた数値。仮数部は1以上1000未満になる
+///
+/// 既定は有効数字3桁で末尾の0を省き（"1.23k", "4.5µ"）、"{:.2}"なら仮数部の小数点以下の桁数を固定する。
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Si(pub f64);
+
+const SI_PREFIXES: [&str; 17] = ["y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y"];
+const SI_SMALLEST: i32 = -24;
+
+impl Si {
+    fn mantissa(value: f64, exponent: i32, precision: Option<usize>) -> String {
+        let mantissa = value / 10f64.powi(exponent);
+        match precision {
+            Some(precision) => format!("{:.*}", precision, mantissa),
+            None => {
+                let decimals = (2 - mantissa.abs().log10().floor() as i32).max(0) as usize;
+                let text = format!("{:.*}", decimals, mantissa);
+                if text.contains('.') {
+                    text.trim_end_matches('0').trim_end_matches('.').to_string()
+                } else {
+                    text
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Si {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.0;
+        // -0は"-0"のままにし、NaNには'+'を付けない（stdと同じ）
+        let sign = if dest.sign_plus() && !value.is_sign_negative() && !value.is_nan() { "+" } else { "" };
+        if value == 0.0 || !value.is_finite() {
+            let text = match dest.precision() {
+                Some(precision) => format!("{}{:.*}", sign, precision, value),
+                None => format!("{}{}", sign, value),
+            };
+            return pad(dest, &text);
+        }
+        let largest = SI_SMALLEST + 3 * (SI_PREFIXES.len() as i32 - 1);
+        let mut exponent = ((value.abs().log10() / 3.0).floor() as i32 * 3).clamp(SI_SMALLEST, largest);
+        // log10の誤差と、丸めで1000になる場合（999.7→"1000"）を直す
+        if value.abs() / 10f64.powi(exponent) < 1.0 && exponent > SI_SMALLEST {
+            exponent -= 3;
+        }
+        let mut mantissa = Si::mantissa(value, exponent, dest.precision());
+        if mantissa.trim_start_matches('-').parse::<f64>().unwrap_or(0.0) >= 1000.0 && exponent < largest {
+            exponent += 3;
+            mantissa = Si::mantissa(value, exponent, dest.precision());
+        }
+        let prefix = SI_PREFIXES[((exponent - SI_SMALLEST) / 3) as usize];
+        pad(dest, &format!("{}{}{}", sign, mantissa, prefix))
+    }
+}
+
+impl FromStr for Si {
+    type Err = ParseError;
+
+    /// "1.23k", "-4.5 µ", "4.5u"（µの代わり）、"100"。指数表記の"1e3"は'E'（エクサ）と紛らわしいので受け付けない
+    fn from_str(text: &str) -> Result<Si, ParseError> {
+        let (text, start) = trimmed(text)?;
+        let (negative, unsigned, start) = match text.as_bytes()[0] {
+            b'-' => (true, &text[1..], start + 1),
+            b'+' => (false, &text[1..], start + 1),
+            _ => (false, text, start),
+        };
+        let (number, prefix, prefix_position) = split_number(unsigned, start)?;
+        let exponent = match prefix {
+            "u" | "μ" => -6,
+            _ => match SI_PREFIXES.iter().position(|&p| p == prefix) {
+                Some(i) => SI_SMALLEST + 3 * i as i32,
+                None => return Err(ParseError::UnknownUnit { position: prefix_position, unit: prefix.to_string() }),
+            },
+        };
+        // 10の累乗を掛けると誤差が出るので、指数表記の文字列として解析する
+        let value: f64 = format!("{}{}e{}", if negative { "-" } else { "" }, number, exponent)
+            .parse()
+            .map_err(|_| ParseError::InvalidNumber { position: start })?;
+        Ok(Si(value))
+    }
+}
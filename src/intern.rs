@@ -0,0 +1,225 @@
+// 文字列のインターン
+//
+// main.rsのRcの例では、cloneしたRcは"{:p}"のアドレスが同じで、同じ内容を別に作った"mazurka"は違っていた。
+// StringInternerは等しい文字列を1つの共有されたRc<str>（スレッド間で使うSyncStringInternerではArc<str>）にまとめ、
+// シンボルの比較をポインタの比較（O(1)）で済ませる。シンボルには登録順の番号が付き、番号からも引ける。
+// NFCで正規化してから登録すれば、"th\u{e9}"と"the\u{301}"は同じシンボルになる。
+
+use std::borrow::{Borrow, Cow};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// シンボルが保持する共有文字列（Rc<str>とArc<str>）
+pub trait SharedStr: Clone + Deref<Target = str> + Borrow<str> + Eq + Hash + for<'a> From<&'a str> {}
+
+impl SharedStr for Rc<str> {}
+
+impl SharedStr for Arc<str> {}
+
+/// インターンした文字列。同じインターナーの等しい文字列は同じ領域を指す
+///
+/// 比較とハッシュは文字列の内容ではなくアドレスで行う。
+#[derive(Clone)]
+pub struct Interned<P> {
+    text: P,
+    id: usize,
+}
+
+/// StringInternerのシンボル
+pub type Symbol = Interned<Rc<str>>;
+
+/// SyncStringInternerのシンボル。スレッド間で送れる
+pub type SyncSymbol = Interned<Arc<str>>;
+
+impl<P: SharedStr> Interned<P> {
+    /// 登録順の番号（0から）
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// 共有している文字列そのもの
+    pub fn shared(&self) -> &P {
+        &self.text
+    }
+}
+
+impl<P: SharedStr> PartialEq for Interned<P> {
+    fn eq(&self, other: &Interned<P>) -> bool {
+        std::ptr::eq(self.text.as_ptr(), other.text.as_ptr())
+    }
+}
+
+impl<P: SharedStr> Eq for Interned<P> {}
+
+impl<P: SharedStr> Hash for Interned<P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.text.as_ptr() as usize).hash(state);
+    }
+}
+
+impl<P: SharedStr> Deref for Interned<P> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl<P: SharedStr> AsRef<str> for Interned<P> {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl<P: SharedStr> fmt::Display for Interned<P> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        dest.pad(&self.text)
+    }
+}
+
+impl<P: SharedStr> fmt::Debug for Interned<P> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        write!(dest, "#{} {:?}", self.id, &*self.text)
+    }
+}
+
+/// "{:p}"は文字列の領域のアドレス
+impl<P: SharedStr> fmt::Pointer for Interned<P> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.text.as_ptr(), dest)
+    }
+}
+
+// 文字列からシンボルへの表と、番号順のシンボル
+struct Table<P> {
+    nfc: bool,
+    ids: HashMap<P, usize>,
+    symbols: Vec<P>,
+}
+
+impl<P: SharedStr> Table<P> {
+    fn new(nfc: bool) -> Table<P> {
+        Table { nfc, ids: HashMap::new(), symbols: Vec::new() }
+    }
+
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.nfc && !unicode_normalization::is_nfc(text) {
+            Cow::Owned(text.nfc().collect())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    fn get(&self, text: &str) -> Option<Interned<P>> {
+        let text = self.normalize(text);
+        self.ids.get(&*text).map(|&id| Interned { text: self.symbols[id].clone(), id })
+    }
+
+    fn intern(&mut self, text: &str) -> Interned<P> {
+        if let Some(symbol) = self.get(text) {
+            return symbol;
+        }
+        let shared = P::from(&*self.normalize(text));
+        let id = self.symbols.len();
+        self.ids.insert(shared.clone(), id);
+        self.symbols.push(shared.clone());
+        Interned { text: shared, id }
+    }
+
+    fn resolve(&self, id: usize) -> Option<Interned<P>> {
+        self.symbols.get(id).map(|text| Interned { text: text.clone(), id })
+    }
+}
+
+/// 1つのスレッドで使うインターナー。&selfのまま登録できる
+pub struct StringInterner {
+    table: RefCell<Table<Rc<str>>>,
+}
+
+/// スレッド間で共有できるインターナー（Arc<SyncStringInterner>として使う）
+pub struct SyncStringInterner {
+    table: Mutex<Table<Arc<str>>>,
+}
+
+macro_rules! interner {
+    ($interner:ident, $symbol:ident, $borrow:ident, $borrow_mut:ident) => {
+        impl $interner {
+            /// 文字列をそのまま登録する
+            pub fn new() -> $interner {
+                $interner::with_nfc(false)
+            }
+
+            /// nfcがtrueなら、NFCに正規化してから登録・検索する
+            pub fn with_nfc(nfc: bool) -> $interner {
+                $interner { table: Table::new(nfc).into() }
+            }
+
+            /// 等しい文字列が登録済みならそのシンボルを、なければ新しいシンボルを返す
+            pub fn intern(&self, text: &str) -> $symbol {
+                self.$borrow_mut().intern(text)
+            }
+
+            /// 登録せずに探す
+            pub fn get(&self, text: &str) -> Option<$symbol> {
+                self.$borrow().get(text)
+            }
+
+            /// 番号から引く
+            pub fn resolve(&self, id: usize) -> Option<$symbol> {
+                self.$borrow().resolve(id)
+            }
+
+            pub fn len(&self) -> usize {
+                self.$borrow().symbols.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+        }
+
+        impl Default for $interner {
+            fn default() -> $interner {
+                $interner::new()
+            }
+        }
+
+        impl fmt::Debug for $interner {
+            fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+                let table = self.$borrow();
+                dest.debug_list().entries(table.symbols.iter().map(|text| &**text)).finish()
+            }
+        }
+    };
+}
+
+impl StringInterner {
+    fn table(&self) -> std::cell::Ref<'_, Table<Rc<str>>> {
+        self.table.borrow()
+    }
+
+    fn table_mut(&self) -> std::cell::RefMut<'_, Table<Rc<str>>> {
+        self.table.borrow_mut()
+    }
+}
+
+impl SyncStringInterner {
+    // 登録の途中でpanicしても表は壊れないので、poisonは無視する
+    fn table(&self) -> std::sync::MutexGuard<'_, Table<Arc<str>>> {
+        self.table.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+interner!(StringInterner, Symbol, table, table_mut);
+interner!(SyncStringInterner, SyncSymbol, table, table);
@@ -0,0 +1,303 @@
+// かな変換: ひらがな↔カタカナ、半角↔全角、ローマ字
+//
+// "うどん"から"ウドン"や半角の"ｳﾄﾞﾝ"を作り、"udon"のようなローマ字入力をかなに戻して検索に使う。
+
+use std::collections::HashMap;
+
+const HIRAGANA_TO_KATAKANA: u32 = 0x60;
+
+/// ひらがなをカタカナにする（ゝゞはヽヾに）。それ以外の文字はそのまま
+pub fn hiragana_to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{3041}'..='\u{3096}' | '\u{309d}'..='\u{309e}' => shift(ch, HIRAGANA_TO_KATAKANA as i32),
+            _ => ch,
+        })
+        .collect()
+}
+
+/// カタカナをひらがなにする（ヽヾはゝゞに）。対応するひらがなのないヷ〜ヺ等はそのまま
+pub fn katakana_to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{30a1}'..='\u{30f6}' | '\u{30fd}'..='\u{30fe}' => shift(ch, -(HIRAGANA_TO_KATAKANA as i32)),
+            _ => ch,
+        })
+        .collect()
+}
+
+fn shift(ch: char, offset: i32) -> char {
+    std::char::from_u32((ch as i32 + offset) as u32).unwrap_or(ch)
+}
+
+// U+FF61〜U+FF9Fの半角カナに対応する全角文字
+const HALF_WIDTH_KANA: [char; 63] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン', '゛', '゜',
+];
+
+const HALF_DAKUTEN: char = '\u{ff9e}';
+const HALF_HANDAKUTEN: char = '\u{ff9f}';
+
+// 濁点を付けられるカタカナと、付けた文字
+fn with_dakuten(ch: char) -> Option<char> {
+    match ch {
+        // 濁音は清音の次の符号位置にある
+        'カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ' | 'タ' | 'チ' | 'ツ' | 'テ' | 'ト' | 'ハ'
+        | 'ヒ' | 'フ' | 'ヘ' | 'ホ' => Some(shift(ch, 1)),
+        'ウ' => Some('ヴ'),
+        'ワ' => Some('ヷ'),
+        'ヲ' => Some('ヺ'),
+        _ => None,
+    }
+}
+
+fn with_handakuten(ch: char) -> Option<char> {
+    match ch {
+        'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' => Some(shift(ch, 2)),
+        _ => None,
+    }
+}
+
+/// 半角英数記号と半角カナを全角にする
+///
+/// 半角カナの濁点・半濁点は直前の文字と合成する（"ｳﾄﾞﾝ"→"ウドン"）。
+pub fn to_full_width(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() * 3);
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            ' ' => result.push('\u{3000}'),
+            '!'..='~' => result.push(shift(ch, 0xfee0)),
+            '\u{ff61}'..='\u{ff9f}' => {
+                let full = HALF_WIDTH_KANA[(ch as u32 - 0xff61) as usize];
+                let composed = match chars.peek() {
+                    Some(&HALF_DAKUTEN) => with_dakuten(full),
+                    Some(&HALF_HANDAKUTEN) => with_handakuten(full),
+                    _ => None,
+                };
+                match composed {
+                    Some(composed) => {
+                        chars.next();
+                        result.push(composed);
+                    }
+                    None => result.push(full),
+                }
+            }
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+/// 全角英数記号とカタカナを半角にする
+///
+/// 濁音・半濁音は清音と濁点・半濁点の2文字に分ける（"ウドン"→"ｳﾄﾞﾝ"）。半角のないひらがな等はそのまま。
+pub fn to_half_width(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\u{3000}' => result.push(' '),
+            '\u{ff01}'..='\u{ff5e}' => result.push(shift(ch, -0xfee0)),
+            _ => {
+                if let Some(i) = HALF_WIDTH_KANA.iter().position(|&full| full == ch) {
+                    result.push(shift('\u{ff61}', i as i32));
+                } else if let Some(i) = HALF_WIDTH_KANA.iter().position(|&full| with_dakuten(full) == Some(ch)) {
+                    result.push(shift('\u{ff61}', i as i32));
+                    result.push(HALF_DAKUTEN);
+                } else if let Some(i) = HALF_WIDTH_KANA.iter().position(|&full| with_handakuten(full) == Some(ch)) {
+                    result.push(shift('\u{ff61}', i as i32));
+                    result.push(HALF_HANDAKUTEN);
+                } else {
+                    result.push(ch);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// ローマ字の綴り方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Romanization {
+    /// ヘボン式（し=shi, ち=chi, つ=tsu, ふ=fu, じ=ji）
+    Hepburn,
+    /// 訓令式（し=si, ち=ti, つ=tu, ふ=hu, じ=zi）
+    Kunrei,
+}
+
+// (かな, ヘボン式, 訓令式)。逆変換で綴りが重なる場合は先に書いたものを優先する
+const ROMAJI: &[(&str, &str, &str)] = &[
+    ("きゃ", "kya", "kya"), ("きゅ", "kyu", "kyu"), ("きょ", "kyo", "kyo"),
+    ("しゃ", "sha", "sya"), ("しゅ", "shu", "syu"), ("しょ", "sho", "syo"), ("しぇ", "she", "sye"),
+    ("ちゃ", "cha", "tya"), ("ちゅ", "chu", "tyu"), ("ちょ", "cho", "tyo"), ("ちぇ", "che", "tye"),
+    ("にゃ", "nya", "nya"), ("にゅ", "nyu", "nyu"), ("にょ", "nyo", "nyo"),
+    ("ひゃ", "hya", "hya"), ("ひゅ", "hyu", "hyu"), ("ひょ", "hyo", "hyo"),
+    ("みゃ", "mya", "mya"), ("みゅ", "myu", "myu"), ("みょ", "myo", "myo"),
+    ("りゃ", "rya", "rya"), ("りゅ", "ryu", "ryu"), ("りょ", "ryo", "ryo"),
+    ("ぎゃ", "gya", "gya"), ("ぎゅ", "gyu", "gyu"), ("ぎょ", "gyo", "gyo"),
+    ("じゃ", "ja", "zya"), ("じゅ", "ju", "zyu"), ("じょ", "jo", "zyo"), ("じぇ", "je", "zye"),
+    ("ぢゃ", "ja", "zya"), ("ぢゅ", "ju", "zyu"), ("ぢょ", "jo", "zyo"),
+    ("びゃ", "bya", "bya"), ("びゅ", "byu", "byu"), ("びょ", "byo", "byo"),
+    ("ぴゃ", "pya", "pya"), ("ぴゅ", "pyu", "pyu"), ("ぴょ", "pyo", "pyo"),
+    ("ふぁ", "fa", "fa"), ("ふぃ", "fi", "fi"), ("ふぇ", "fe", "fe"), ("ふぉ", "fo", "fo"),
+    ("ゔぁ", "va", "va"), ("ゔぃ", "vi", "vi"), ("ゔぇ", "ve", "ve"), ("ゔぉ", "vo", "vo"),
+    ("あ", "a", "a"), ("い", "i", "i"), ("う", "u", "u"), ("え", "e", "e"), ("お", "o", "o"),
+    ("か", "ka", "ka"), ("き", "ki", "ki"), ("く", "ku", "ku"), ("け", "ke", "ke"), ("こ", "ko", "ko"),
+    ("さ", "sa", "sa"), ("し", "shi", "si"), ("す", "su", "su"), ("せ", "se", "se"), ("そ", "so", "so"),
+    ("た", "ta", "ta"), ("ち", "chi", "ti"), ("つ", "tsu", "tu"), ("て", "te", "te"), ("と", "to", "to"),
+    ("な", "na", "na"), ("に", "ni", "ni"), ("ぬ", "nu", "nu"), ("ね", "ne", "ne"), ("の", "no", "no"),
+    ("は", "ha", "ha"), ("ひ", "hi", "hi"), ("ふ", "fu", "hu"), ("へ", "he", "he"), ("ほ", "ho", "ho"),
+    ("ま", "ma", "ma"), ("み", "mi", "mi"), ("む", "mu", "mu"), ("め", "me", "me"), ("も", "mo", "mo"),
+    ("や", "ya", "ya"), ("ゆ", "yu", "yu"), ("よ", "yo", "yo"),
+    ("ら", "ra", "ra"), ("り", "ri", "ri"), ("る", "ru", "ru"), ("れ", "re", "re"), ("ろ", "ro", "ro"),
+    ("わ", "wa", "wa"), ("ゐ", "i", "i"), ("ゑ", "e", "e"), ("を", "o", "o"), ("ん", "n", "n"),
+    ("が", "ga", "ga"), ("ぎ", "gi", "gi"), ("ぐ", "gu", "gu"), ("げ", "ge", "ge"), ("ご", "go", "go"),
+    ("ざ", "za", "za"), ("じ", "ji", "zi"), ("ず", "zu", "zu"), ("ぜ", "ze", "ze"), ("ぞ", "zo", "zo"),
+    ("だ", "da", "da"), ("ぢ", "ji", "zi"), ("づ", "zu", "zu"), ("で", "de", "de"), ("ど", "do", "do"),
+    ("ば", "ba", "ba"), ("び", "bi", "bi"), ("ぶ", "bu", "bu"), ("べ", "be", "be"), ("ぼ", "bo", "bo"),
+    ("ぱ", "pa", "pa"), ("ぴ", "pi", "pi"), ("ぷ", "pu", "pu"), ("ぺ", "pe", "pe"), ("ぽ", "po", "po"),
+    ("ゔ", "vu", "vu"),
+    ("ぁ", "xa", "xa"), ("ぃ", "xi", "xi"), ("ぅ", "xu", "xu"), ("ぇ", "xe", "xe"), ("ぉ", "xo", "xo"),
+    ("ゃ", "xya", "xya"), ("ゅ", "xyu", "xyu"), ("ょ", "xyo", "xyo"), ("ゎ", "xwa", "xwa"),
+    ("っ", "xtsu", "xtu"),
+];
+
+// ローマ字入力でだけ使う綴り
+const ROMAJI_INPUT_ONLY: &[(&str, &str)] = &[
+    ("wo", "を"), ("di", "ぢ"), ("du", "づ"), ("cya", "ちゃ"), ("cyu", "ちゅ"), ("cyo", "ちょ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"), ("la", "ぁ"), ("li", "ぃ"), ("lu", "ぅ"),
+    ("le", "ぇ"), ("lo", "ぉ"), ("lya", "ゃ"), ("lyu", "ゅ"), ("lyo", "ょ"), ("ltu", "っ"),
+    ("ltsu", "っ"), ("n'", "ん"), ("-", "ー"),
+];
+
+/// ひらがな・カタカナをローマ字にする
+///
+/// 促音は次の子音を重ね（ヘボン式の"っち"は"tchi"）、母音やyの前の"ん"は"n'"にする。
+/// 長音符"ー"は直前の母音を繰り返す（"ラーメン"→"raamen"）。かな以外の文字はそのまま。
+pub fn to_romaji(text: &str, system: Romanization) -> String {
+    let hiragana = katakana_to_hiragana(text);
+    let mut result = String::with_capacity(hiragana.len());
+    let mut sokuon = false;
+    let mut after_n = false;
+    let mut rest = hiragana.as_str();
+
+    while let Some(ch) = rest.chars().next() {
+        let two = rest.char_indices().nth(2).map_or(rest.len(), |(i, _)| i);
+        let found = ROMAJI
+            .iter()
+            .find(|&&(kana, _, _)| kana == &rest[..two])
+            .or_else(|| ROMAJI.iter().find(|&&(kana, _, _)| kana == &rest[..ch.len_utf8()]));
+
+        match found {
+            Some(&("っ", _, _)) => {
+                if sokuon {
+                    result.push_str(romaji_of("っ", system));
+                }
+                sokuon = true;
+                rest = &rest["っ".len()..];
+                continue;
+            }
+            Some(&(kana, hepburn, kunrei)) => {
+                let romaji = if system == Romanization::Hepburn { hepburn } else { kunrei };
+                if sokuon {
+                    if romaji.starts_with("ch") {
+                        result.push('t');
+                    } else if let Some(consonant) = romaji.chars().next().filter(|c| !"aiueon".contains(*c)) {
+                        result.push(consonant);
+                    } else {
+                        result.push_str(romaji_of("っ", system));
+                    }
+                }
+                if after_n && romaji.starts_with(['a', 'i', 'u', 'e', 'o', 'y']) {
+                    // "きんゆう"を"kinyuu"と書くと"きにゅう"と区別できない
+                    result.push('\'');
+                }
+                result.push_str(romaji);
+                after_n = kana == "ん";
+                rest = &rest[kana.len()..];
+                sokuon = false;
+                continue;
+            }
+            None => {
+                if sokuon {
+                    result.push_str(romaji_of("っ", system));
+                }
+                if ch == 'ー' {
+                    if let Some(vowel) = result.chars().next_back().filter(|c| "aiueo".contains(*c)) {
+                        result.push(vowel);
+                    } else {
+                        result.push('-');
+                    }
+                } else {
+                    result.push(ch);
+                }
+                rest = &rest[ch.len_utf8()..];
+                sokuon = false;
+                after_n = false;
+            }
+        }
+    }
+    if sokuon {
+        result.push_str(romaji_of("っ", system));
+    }
+    result
+}
+
+fn romaji_of(kana: &str, system: Romanization) -> &'static str {
+    ROMAJI
+        .iter()
+        .find(|&&(k, _, _)| k == kana)
+        .map_or("", |&(_, hepburn, kunrei)| if system == Romanization::Hepburn { hepburn } else { kunrei })
+}
+
+/// ローマ字をひらがなにする（ヘボン式・訓令式のどちらの綴りも受け付ける）
+///
+/// 子音の重なりは促音"っ"に、子音の前や末尾の"n"は"ん"にする。かなにできない文字はそのまま残す。
+pub fn from_romaji(text: &str) -> String {
+    let mut table: HashMap<&str, &str> = HashMap::new();
+    for &(kana, hepburn, kunrei) in ROMAJI {
+        table.entry(hepburn).or_insert(kana);
+        table.entry(kunrei).or_insert(kana);
+    }
+    for &(romaji, kana) in ROMAJI_INPUT_ONLY {
+        table.entry(romaji).or_insert(kana);
+    }
+
+    let input = text.to_lowercase();
+    let bytes = input.as_bytes();
+    let mut result = String::with_capacity(input.len() * 3);
+    let mut i = 0;
+    while i < input.len() {
+        if let Some(len) = (1..=4).rev().find(|&len| {
+            i + len <= input.len() && input.is_char_boundary(i + len) && table.contains_key(&input[i..i + len])
+        }) {
+            let kana = table[&input[i..i + len]];
+            let mut len = len;
+            if &input[i..i + len] == "n"
+                && bytes.get(i + 1) == Some(&b'n')
+                && !matches!(bytes.get(i + 2), Some(b'a' | b'i' | b'u' | b'e' | b'o' | b'y'))
+            {
+                // "nn"は"ん"1文字。"konnichiha"のように次が母音なら最初の"n"だけが"ん"
+                len = 2;
+            }
+            result.push_str(kana);
+            i += len;
+            continue;
+        }
+        let b = bytes[i];
+        // 子音の重なりと、ヘボン式の"tch"は促音
+        let doubled = b.is_ascii_lowercase() && !b"aiueon".contains(&b) && bytes.get(i + 1) == Some(&b);
+        if doubled || (b == b't' && input[i + 1..].starts_with("ch")) {
+            result.push('っ');
+            i += 1;
+        } else {
+            let ch = input[i..].chars().next().expect("char boundary");
+            result.push(ch);
+            i += ch.len_utf8();
+        }
+    }
+    result
+}
@@ -0,0 +1,26 @@
+// main.rsの学習コードから切り出した、再利用できる文字列・テキスト処理
+pub mod pattern;
+pub mod comment;
+pub mod replace;
+pub mod regex_replace;
+pub mod passwd;
+pub mod hosts;
+pub mod csv;
+pub mod segment;
+pub mod morph;
+pub mod kana;
+pub mod width;
+pub mod wrap;
+pub mod align;
+pub mod table;
+pub mod format_spec;
+pub mod template;
+pub mod number;
+pub mod humanize;
+pub mod float;
+pub mod notation;
+pub mod complex;
+pub mod pretty;
+pub mod serialize;
+pub mod intern;
+pub mod normalized;
@@ -704,4 +704,1091 @@ fn main() {
             "1 Difficulty"
         );
     }
+    {
+        use rust_string_and_text_study::pattern::{CharSet, Script, GeneralCategory, LiteralSet, PatternExt};
+
+        // CharSetは事前計算したビットマップで判定するため、&[char]の線形探索より速い
+        // as_fn()のクロージャはstd::strのパターンとしてそのまま渡せる
+        let blanks = CharSet::from_chars(&[' ', '\t']);
+        let code = "\t    function noodle() { ";
+        assert_eq!(code.trim_start_matches(blanks.as_fn()),
+                   "function noodle() { ");
+        assert_eq!(code.trim_matches_by(&CharSet::whitespace()),
+                   "function noodle() {");
+
+        let hashes = CharSet::from("#").union(&CharSet::whitespace());
+        assert_eq!("## Elephants".trim_start_matches_by(&hashes), "Elephants");
+        assert!(CharSet::new().with_range('a', 'z').negated().contains('Z'));
+        // 否定された集合との和も作れる（「小文字以外」または「x」）
+        let not_lower = CharSet::new().with_range('a', 'z').with_range('α', 'ω').negated();
+        let either = not_lower.union(&CharSet::from("x"));
+        assert!(either.contains('x') && either.contains('Z') && either.contains('う'));
+        assert!(!either.contains('y') && !either.contains('β'));
+        assert!(either.contains('\u{10ffff}') && either.contains('\u{e000}'));
+        let not_late = CharSet::new().with_range('m', 'z').negated().union(&CharSet::new().with_range('a', 'z').negated());
+        assert!(not_late.contains('a') && !not_late.contains('m'));
+
+        // Unicodeの用字や一般カテゴリもパターンとして使える
+        assert_eq!("うどん定食".find_by(&Script::Han), Some(9));
+        assert_eq!(Script::of('ン'), Some(Script::Katakana));
+        assert_eq!("それは、うどん。ではない！"
+                   .split_by(&GeneralCategory::Punctuation).collect::<Vec<_>>(),
+                   vec!["それは", "うどん", "ではない", ""]);
+        assert_eq!("\"Borrow\" and \"BorrowMut\"".replace_by(&GeneralCategory::Punctuation, ""),
+                   "Borrow and BorrowMut");
+        assert_eq!(GeneralCategory::of('`'), GeneralCategory::Symbol); // バッククォートは句読点ではなく記号（Sk）
+
+        // 文字列リテラルの集合は同じ位置で一致するもののうち最長のものを採用する
+        let fears = LiteralSet::new(vec!["fear", "fear is"]);
+        assert_eq!("The only thing we have to fear is fear itself"
+                   .replace_by(&fears, "spin"),
+                   "The only thing we have to spin spin itself");
+        assert_eq!("<!-- x -->".trim_matches_by(&LiteralSet::new(vec!["<!--", "-->", " "])),
+                   "x");
+    }
+    {
+        use rust_string_and_text_study::comment::{CommentSyntax, CommentKind, CommentError};
+
+        // trim_start_matches()による手作りのコメント除去は、文字列リテラル中の'#'も消してしまう
+        let shell = CommentSyntax::shell();
+        assert_eq!(shell.strip("# ignore this line\n\
+                                echo \"#1 fan\" 'it''s' # greeting\n\
+                                echo a#b \\# not a comment\n").unwrap(),
+                   "\n\
+                    echo \"#1 fan\" 'it''s'\n\
+                    echo a#b \\# not a comment\n");
+
+        // ブロックコメントを除去しても改行は残るため、行番号がずれない
+        let c = CommentSyntax::c();
+        let source = "int x = 1; /* the\n answer */ int y = 2; // \"ok\"\nchar *s = \"/* no */\";\n";
+        let stripped = c.strip(source).unwrap();
+        assert_eq!(stripped, "int x = 1; \n int y = 2;\nchar *s = \"/* no */\";\n");
+        assert_eq!(stripped.lines().count(), source.lines().count());
+
+        let comments = c.comments(source).unwrap();
+        assert_eq!(comments.iter().map(|c| (c.kind, c.text, c.line)).collect::<Vec<_>>(),
+                   vec![(CommentKind::Block, " the\n answer ", 1),
+                        (CommentKind::Line, " \"ok\"", 2)]);
+
+        // SQLは引用符を二重にしてエスケープする、INIは行頭のみコメントになる
+        assert_eq!(CommentSyntax::sql().strip("SELECT 'a -- b''s' -- why\n").unwrap(),
+                   "SELECT 'a -- b''s'\n");
+        assert_eq!(CommentSyntax::ini().strip("; user settings\nname = a;b\n").unwrap(),
+                   "\nname = a;b\n");
+
+        // 閉じていない文字列リテラルやコメントは位置付きのエラーになる
+        assert_eq!(c.strip("x;\n  /* open"),
+                   Err(CommentError::UnterminatedComment { line: 2, column: 3 }));
+        assert_eq!(shell.strip("echo 'oops").unwrap_err().to_string(),
+                   "1:6: unterminated string literal");
+    }
+    {
+        use std::borrow::Cow;
+        use rust_string_and_text_study::replace::{Replacer, MatchKind};
+
+        // replace()を連鎖させると、先の置換結果が後の置換にマッチしてしまう
+        let speech = "The only thing we have to fear is spin itself";
+        assert_eq!(speech.replace("fear", "spin").replace("spin", "fear"),
+                   "The only thing we have to fear is fear itself");
+
+        // Replacerは元の文字列を1回だけ走査して、すべてのパターンを同時に置換する
+        let swap = Replacer::new(vec![("fear", "spin"), ("spin", "fear")]);
+        assert_eq!(swap.replace(speech),
+                   "The only thing we have to spin is fear itself");
+
+        // 同じ位置で複数のパターンが一致した場合、既定では最長のものを採用する
+        let entities: Replacer = vec![("<", "&lt;"), ("<=", "&le;"), ("&", "&amp;")].into_iter().collect();
+        assert_eq!(entities.replace("a <= b & c < d"), "a &le; b &amp; c &lt; d");
+        let first = Replacer::new(vec![("<", "&lt;"), ("<=", "&le;")]).match_kind(MatchKind::LeftmostFirst);
+        assert_eq!(first.replace("a <= b"), "a &lt;= b");
+
+        // str::replacen()と同様に置換回数を制限できる
+        let esses = Replacer::new(vec![("s", "S"), ("i", "I")]);
+        assert_eq!(esses.replacen("This is a soup", 3), "ThIS Is a soup");
+
+        // 一致しなければ元の文字列を借用したままのCow::Borrowedが返る
+        match swap.replace("nothing to see here") {
+            Cow::Borrowed(s) => assert_eq!(s, "nothing to see here"),
+            Cow::Owned(_) => panic!("should not allocate"),
+        }
+    }
+    {
+        extern crate regex;
+
+        use regex::Regex;
+        use rust_string_and_text_study::regex_replace::{RegexReplacer, Template, TemplateError, Edit, apply_edits};
+
+        let semver = Regex::new(r"(?P<major>\d+)\.(?P<minor>\d+)\.(?P<patch>\d+)(?P<pre>-[-.[:alnum:]]*)?").unwrap();
+        let section = "In the beginning, there was 1.0.0. \
+                       For a while, we used 1.0.1-beta, \
+                       but in the end, we settled on 1.2.4.";
+
+        // テンプレートは$1や${name}でキャプチャを参照し、「:upper」等で大文字・小文字を変換できる
+        let tagged = RegexReplacer::with_template(semver.clone(), "v$1.${minor}.${3}${pre:upper}").unwrap();
+        assert_eq!(tagged.replace_all(section),
+                   "In the beginning, there was v1.0.0. \
+                    For a while, we used v1.0.1-BETA, \
+                    but in the end, we settled on v1.2.4.");
+
+        // 存在しないグループや未知の変換はRegexReplacerを作る時点でエラーになる
+        assert_eq!(RegexReplacer::with_template(semver.clone(), "${build}").err().unwrap().to_string(),
+                   "no capture group named 'build'");
+        assert_eq!(Template::parse("$$1 ${1:reverse}"),
+                   Err(TemplateError::UnknownModifier { position: 4, modifier: "reverse".to_string() }));
+
+        // クロージャはCapturesから置換文字列を計算する
+        let bump_minor = RegexReplacer::with_fn(semver.clone(), |caps| {
+            let minor: u32 = caps["minor"].parse().unwrap();
+            format!("{}.{}.0", &caps["major"], minor + 1)
+        });
+
+        // ドライラン: 置換せずにバイト範囲と新しいテキストを列挙して確認してから適用する
+        let edits = bump_minor.edits(section);
+        assert_eq!(edits,
+                   vec![Edit { range: 28..33, replacement: "1.1.0".to_string() },
+                        Edit { range: 56..66, replacement: "1.1.0".to_string() },
+                        Edit { range: 98..103, replacement: "1.3.0".to_string() }]);
+        assert_eq!(&section[edits[1].range.clone()], "1.0.1-beta");
+        assert_eq!(apply_edits(section, &edits[2..]),
+                   "In the beginning, there was 1.0.0. \
+                    For a while, we used 1.0.1-beta, \
+                    but in the end, we settled on 1.3.0.");
+        assert_eq!(bump_minor.limit(1).replace_all("0.2.5 and 0.2.6"), "0.3.0 and 0.2.6");
+
+        // Templateはregexクレートのreplace_all()にもそのまま渡せる
+        let template = Template::parse("${1}_${2}").unwrap();
+        assert_eq!(semver.replace_all("regex = 0.2.5", &template), "regex = 0_2");
+    }
+    {
+        use rust_string_and_text_study::passwd::{Passwd, Group, PasswdFile, GroupFile, ShadowFile, ParseErrorKind};
+
+        // split(':')と同じく末尾の空フィールドも数え、uidやgidは数値として取り出す
+        let jimb: Passwd = "jimb:x:1000:1000:Jim Blandy:/home/jimb:".parse().unwrap();
+        assert_eq!((jimb.uid, jimb.gid, jimb.gecos.as_str(), jimb.shell.as_str()),
+                   (1000, 1000, "Jim Blandy", ""));
+        assert_eq!(jimb.to_string(), "jimb:x:1000:1000:Jim Blandy:/home/jimb:");
+
+        let passwd = "# local users\n\
+                      root:x:0:0:root:/root:/bin/bash\n\
+                      jimb:x:01000:1000:Jim Blandy:/home/jimb:\n";
+        let mut users = PasswdFile::parse(passwd).unwrap();
+        assert_eq!(users.records().map(|user| user.uid).collect::<Vec<_>>(), vec![0, 1000]);
+
+        // 何も変更しなければ、"01000"のような表記も含めて元のテキストのまま書き戻される
+        assert_eq!(users.to_string(), passwd);
+
+        // 変更したレコードだけがフィールドから組み立て直される
+        for user in users.records_mut().filter(|user| user.name == "jimb") {
+            user.shell = "/bin/zsh".to_string();
+        }
+        assert_eq!(users.to_string(),
+                   "# local users\n\
+                    root:x:0:0:root:/root:/bin/bash\n\
+                    jimb:x:1000:1000:Jim Blandy:/home/jimb:/bin/zsh\n");
+
+        let mut groups = GroupFile::parse("wheel:x:10:root,jimb\nusers:x:100:\n").unwrap();
+        assert_eq!(groups.records().next().unwrap().members, vec!["root", "jimb"]);
+        groups.push(Group { name: "staff".to_string(), password: "x".to_string(), gid: 50, members: vec![] });
+        assert_eq!(groups.remove_where(|group| group.gid == 100), 1);
+        assert_eq!(groups.to_string(), "wheel:x:10:root,jimb\nstaff:x:50:\n");
+
+        // /etc/shadowの空欄の日数はNoneになる
+        let shadow = ShadowFile::parse("jimb:$6$salt$hash:19000:0:99999:7:::\n").unwrap();
+        let entry = shadow.records().next().unwrap();
+        assert_eq!((entry.last_change, entry.max_age, entry.inactive_period), (Some(19000), Some(99999), None));
+
+        // エラーには行番号が付く
+        let error = PasswdFile::parse("root:x:0:0:root:/root:/bin/bash\njimb:x:jimb:1000::/home/jimb:\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.kind, ParseErrorKind::InvalidNumber { field: "uid", value: "jimb".to_string() });
+        assert_eq!("jimb:1000:Jim Blandy:".parse::<Passwd>().unwrap_err().to_string(),
+                   "line 1: expected 7 fields, found 4");
+    }
+    {
+        use std::net::IpAddr;
+        use rust_string_and_text_study::hosts::{HostsFile, HostsErrorKind};
+
+        // split_terminator('\n')で行に分け、先頭の語をIpAddrとしてパースする
+        let text = "# static table lookup for hostnames\n\
+                    127.0.0.1       localhost\n\
+                    127.0.0.1       www.reddit.com  # blocked\n\
+                    \n\
+                    fe80::3ea9:f4ff:fe34:7a50   builder build-01\n";
+        let mut hosts = HostsFile::parse(text).unwrap();
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(hosts.lookup("WWW.REDDIT.COM"), vec![localhost]);
+        assert_eq!(hosts.lookup("build-01").iter().map(|addr| addr.to_string()).collect::<Vec<_>>(),
+                   vec!["fe80::3ea9:f4ff:fe34:7a50"]);
+        assert_eq!(hosts.names_of(localhost), vec!["localhost", "www.reddit.com"]);
+        assert_eq!(hosts.entries().nth(1).unwrap().comment, Some(" blocked".to_string()));
+
+        // 変更しなければコメントや空白の揃え方も含めて元のまま書き戻される
+        assert_eq!(hosts.to_string(), text);
+
+        // 正式なホスト名を取り除くと最初の別名が繰り上がる
+        assert_eq!(hosts.remove("builder"), 1);
+        assert_eq!(hosts.remove("www.reddit.com"), 1);
+        hosts.add("192.168.0.1".parse().unwrap(), "router", &["gateway.lan"]).unwrap();
+        assert!(hosts.add(localhost, "bad_name", &[]).is_err());
+        assert_eq!(hosts.to_string(),
+                   "# static table lookup for hostnames\n\
+                    127.0.0.1       localhost\n\
+                    \n\
+                    fe80::3ea9:f4ff:fe34:7a50\tbuild-01\n\
+                    192.168.0.1\trouter gateway.lan\n");
+
+        // 不正なアドレスやホスト名は行と桁（バイト位置）付きで報告される
+        let error = HostsFile::parse("127.0.0.1 localhost\n127.0.0.256 oops\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.kind, HostsErrorKind::InvalidAddress("127.0.0.256".to_string()));
+        assert_eq!(HostsFile::parse("::1  localhost  -bad-\n").unwrap_err().to_string(),
+                   "1:17: invalid hostname \"-bad-\"");
+
+        // CRLFのファイルは行末の'\r'をホスト名に含めず、書き戻すときもCRLFのまま
+        let crlf = "127.0.0.1 localhost\r\n# lan\r\nfe80::1%lo0 gateway\r\n";
+        let mut hosts = HostsFile::parse(crlf).unwrap();
+        assert_eq!(hosts.to_string(), crlf);
+        let gateway = hosts.entries().nth(1).unwrap();
+        assert_eq!((gateway.address.to_string(), gateway.scope.as_deref()), ("fe80::1".to_string(), Some("lo0")));
+        assert_eq!(hosts.lookup("gateway").len(), 1);
+        hosts.remove("localhost");
+        hosts.add(localhost, "loopback", &[]).unwrap();
+        assert_eq!(hosts.to_string(), "# lan\r\nfe80::1%lo0 gateway\r\n127.0.0.1\tloopback\r\n");
+        assert!(HostsFile::parse("127.0.0.1%lo0 localhost\n").is_err());
+        let error = HostsFile::parse("127.0.0.1   # loopback\n").unwrap_err();
+        assert_eq!((error.column, error.kind), (10, HostsErrorKind::MissingHostname));
+    }
+    {
+        use std::borrow::Cow;
+        use std::io::Cursor;
+        use rust_string_and_text_study::csv::{Reader, Writer, Dialect, CsvError, parse_fields};
+
+        // split(',')では引用符で囲まれた','で壊れる
+        let line = r#"Maple Turmeric Latte,3,"3.25, with tax""#;
+        assert_eq!(line.split(',').count(), 4);
+        assert_eq!(parse_fields(line, &Dialect::csv(), 1).unwrap(),
+                   vec!["Maple Turmeric Latte", "3", "3.25, with tax"]);
+
+        // 先頭のBOM、CRLF、引用符中の改行と二重化された引用符を扱える
+        let data = "\u{feff}name,comment\r\n\
+                    jimb,\"said \"\"hi\"\"\nthen left\"\r\n\
+                    orendorff,\r\n";
+        let mut reader = Reader::new(Cursor::new(data), Dialect::csv());
+        {
+            let header = reader.read_record().unwrap().unwrap();
+            assert_eq!(header, vec!["name", "comment"]);
+        }
+        {
+            let record = reader.read_record().unwrap().unwrap();
+            assert_eq!(record[1], "said \"hi\"\nthen left");
+            // 引用符を解く必要のないフィールドは内部バッファを借用する
+            assert!(matches!(record[0], Cow::Borrowed(_)));
+            assert!(matches!(record[1], Cow::Owned(_)));
+        }
+        assert_eq!(reader.read_record().unwrap().unwrap(), vec!["orendorff", ""]);
+        assert!(reader.read_record().is_none());
+
+        // TSVや任意の区切り文字、バックスラッシュエスケープにも対応する
+        let records: Vec<Vec<String>> = Reader::new(Cursor::new("a\tb\n'c\\'d'\te\n"),
+                                                    Dialect::tsv().quote(Some('\'')).escape(Some('\\')))
+            .records()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, vec![vec!["a", "b"], vec!["c'd", "e"]]);
+
+        // 閉じていない引用符は、そのレコードが始まった行番号付きのエラーになる
+        let mut broken = Reader::new(Cursor::new("ok,1\n\"oops,2\n3,4\n"), Dialect::csv()).records();
+        assert!(broken.next().unwrap().is_ok());
+        match broken.next().unwrap() {
+            Err(CsvError::UnterminatedQuote { line }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // 引用符で始まらないフィールドの途中の'"'は文字として扱い、次の行を取り込まない
+        let heights: Vec<Vec<String>> = Reader::new(Cursor::new("height,name\n5'11\",bob\n6'0\",alice\n"), Dialect::csv())
+            .records()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(heights, vec![vec!["height", "name"], vec!["5'11\"", "bob"], vec!["6'0\"", "alice"]]);
+
+        // Writerは必要なときだけ引用符で囲む
+        let mut writer = Writer::new(Vec::new(), Dialect::csv().terminator("\n"));
+        writer.write_record(["description", "price"]).unwrap();
+        writer.write_record(["Latte, large", "3.25"]).unwrap();
+        writer.write_record(["say \"hi\"", ""]).unwrap();
+        writer.write_record([""]).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(),
+                   "description,price\n\
+                    \"Latte, large\",3.25\n\
+                    \"say \"\"hi\"\"\",\n\
+                    \"\"\n");
+    }
+    {
+        use rust_string_and_text_study::segment::{words, tokens, sentences, WordKind};
+
+        // split_whitespace()は句読点を語に含めてしまう
+        let haystack = "One fine day, in the middle of the night";
+        assert_eq!(haystack.split_whitespace().nth(2), Some("day,"));
+
+        // UAX #29の単語境界で区切ると、句読点や空白が別の区間になり種類とバイトオフセットが付く
+        let segments: Vec<_> = words(haystack).collect();
+        assert_eq!(segments[4].text, "day");
+        assert_eq!((segments[5].start, segments[5].text, segments[5].kind), (12, ",", WordKind::Punctuation));
+        assert_eq!(segments.iter().map(|s| s.text).collect::<String>(), haystack); // 連結すれば元に戻る
+
+        assert_eq!(tokens("can't stop 3.14 e.g. night,").map(|t| (t.text, t.kind)).collect::<Vec<_>>(),
+                   vec![("can't", WordKind::Word), ("stop", WordKind::Word), ("3.14", WordKind::Number),
+                        ("e.g", WordKind::Word), ("night", WordKind::Word)]);
+
+        // 空白のない日本語も区切れる（ひらがなと漢字は1文字ずつ、カタカナの連続は1語）
+        assert_eq!(tokens("うどんを食べる").map(|t| t.text).collect::<Vec<_>>(),
+                   vec!["う", "ど", "ん", "を", "食", "べ", "る"]);
+        assert_eq!(tokens("カタカナと漢字").map(|t| t.text).collect::<Vec<_>>(),
+                   vec!["カタカナ", "と", "漢", "字"]);
+
+        // 文境界は全角の句読点も扱う
+        assert_eq!(sentences("Smith left! 今日は晴れ。明日は雨？ Ok.").map(|s| s.trimmed()).collect::<Vec<_>>(),
+                   vec!["Smith left!", "今日は晴れ。", "明日は雨？", "Ok."]);
+        assert_eq!(sentences("Smith left! 今日は晴れ。").nth(1).unwrap().start, 12);
+    }
+    {
+        use rust_string_and_text_study::morph::{Dictionary, Tokenizer};
+
+        // MeCab/IPADIC形式の辞書: 表層形,左文脈ID,右文脈ID,コスト,品詞,細分類1〜3,活用型,活用形,原形,読み,発音
+        let lexicon = "うどん,1,1,3000,名詞,一般,*,*,*,*,うどん,ウドン,ウドン\n\
+                       どん,1,1,4000,名詞,一般,*,*,*,*,どん,ドン,ドン\n\
+                       を,2,2,1000,助詞,格助詞,一般,*,*,*,を,ヲ,ヲ\n\
+                       食べる,3,3,2000,動詞,自立,*,*,一段,基本形,食べる,タベル,タベル\n\
+                       食べ,4,4,2500,動詞,自立,*,*,一段,連用形,食べる,タベ,タベ\n\
+                       た,5,5,1000,助動詞,*,*,*,特殊・タ,基本形,た,タ,タ\n\
+                       ああ,6,6,2000,感動詞,*,*,*,*,*,ああ,アア,アー\n";
+        // 連接コスト: 1行目は文脈IDの数、以降は「前の語の右文脈ID 次の語の左文脈ID コスト」
+        let matrix = "7 7\n\
+                      0 1 -100\n1 2 -500\n2 3 -200\n2 4 -200\n4 5 -800\n3 0 -300\n5 0 -300\n\
+                      1 1 800\n3 5 3000\n";
+        let dictionary = Dictionary::from_parts(lexicon, matrix, None, None).unwrap();
+        let tokenizer = Tokenizer::new(dictionary);
+
+        // 表層形、品詞、原形、読みを取り出せる
+        let tokens = tokenizer.tokenize("うどんを食べた");
+        assert_eq!(tokens.iter().map(|t| t.surface).collect::<Vec<_>>(),
+                   vec!["うどん", "を", "食べ", "た"]);
+        assert_eq!(tokens[2].part_of_speech(), vec!["動詞", "自立"]);
+        assert_eq!(tokens[2].base_form(), "食べる");
+        assert_eq!(tokens.iter().filter_map(|t| t.reading()).collect::<String>(), "ウドンヲタベタ");
+        assert_eq!((tokens[3].start, tokens[3].end()), (18, 21));
+
+        // 辞書にない語は文字種ごとにまとめて未知語にする（カタカナの連続は1語）
+        let tokens = tokenizer.tokenize("ああ ラーメンを食べる");
+        assert_eq!(tokens.iter().map(|t| (t.surface, t.unknown)).collect::<Vec<_>>(),
+                   vec![("ああ", false), ("ラーメン", true), ("を", false), ("食べる", false)]);
+        assert_eq!(tokens[1].part_of_speech(), vec!["名詞", "一般"]);
+        assert_eq!(tokens[1].reading(), None);
+        assert_eq!(tokens[1].start, 7); // 空白は区切りとして扱い、オフセットは元のテキスト上の位置
+
+        // 文脈IDが連接表の範囲外なら辞書の読み込み時にエラーになる
+        assert!(Dictionary::from_parts("錆,9,9,100,名詞\n", "7 7\n", None, None).is_err());
+        // unk.defにDEFAULTの行がなければ、未知語を落とさないよう読み込みを失敗させる
+        let unk_def = "KANJI,0,0,5000,名詞,一般,*,*,*,*,*\n";
+        assert_eq!(Dictionary::from_parts(lexicon, matrix, None, Some(unk_def)).err().map(|e| e.to_string()),
+                   Some("unk.def:0: missing unknown-word entry for DEFAULT".to_string()));
+        let unk_def = "DEFAULT,0,0,5000,名詞,一般,*,*,*,*,*\n";
+        let fallback = Tokenizer::new(Dictionary::from_parts(lexicon, matrix, None, Some(unk_def)).unwrap());
+        let tokens = fallback.tokenize("錆びた");
+        assert_eq!(tokens.iter().map(|t| t.surface).collect::<String>(), "錆びた");
+    }
+    {
+        use rust_string_and_text_study::kana::{self, Romanization};
+
+        // ひらがな↔カタカナ、全角↔半角（濁点は合成・分解する）
+        assert_eq!(kana::hiragana_to_katakana("うどん"), "ウドン");
+        assert_eq!(kana::katakana_to_hiragana("ウドン・ソバ"), "うどん・そば");
+        assert_eq!(kana::to_half_width("ウドン１杯"), "ｳﾄﾞﾝ1杯");
+        assert_eq!(kana::to_full_width("ｳﾄﾞﾝ ﾊﾟﾝ ｳﾞｧｲｵﾘﾝ"), "ウドン\u{3000}パン\u{3000}ヴァイオリン");
+        assert_eq!(kana::to_full_width(&kana::to_half_width("ガッコウ")), "ガッコウ");
+
+        // ヘボン式と訓令式
+        assert_eq!(kana::to_romaji("しんぶん", Romanization::Hepburn), "shinbun");
+        assert_eq!(kana::to_romaji("ちかてつ", Romanization::Hepburn), "chikatetsu");
+        assert_eq!(kana::to_romaji("ちかてつ", Romanization::Kunrei), "tikatetu");
+        assert_eq!(kana::to_romaji("きっぷ まっちゃ", Romanization::Hepburn), "kippu matcha");
+        assert_eq!(kana::to_romaji("じゃがいも", Romanization::Kunrei), "zyagaimo");
+        assert_eq!(kana::to_romaji("きんえん", Romanization::Hepburn), "kin'en");
+        assert_eq!(kana::to_romaji("ラーメン", Romanization::Hepburn), "raamen");
+
+        // ローマ字入力をかなに戻す（どちらの綴りでもよい）
+        assert_eq!(kana::from_romaji("udon"), "うどん");
+        assert_eq!(kana::from_romaji("Konnichiha"), "こんにちは");
+        assert_eq!(kana::from_romaji("tikatetu"), kana::from_romaji("chikatetsu"));
+        assert_eq!(kana::from_romaji("kitte matcha"), "きって まっちゃ");
+        assert_eq!(kana::from_romaji("kin'en"), "きんえん");
+        assert_eq!(kana::hiragana_to_katakana(&kana::from_romaji("ra-men")), "ラーメン");
+    }
+    {
+        use rust_string_and_text_study::wrap::{self, WrapAlgorithm, Wrapper};
+
+        // writeln!で組み立てた手紙を16桁で折り返す（元の改行は残る）
+        let letter = "Whose rutabagas these are I think I know\nHis house is in the village throgh;\n";
+        assert_eq!(wrap::wrap(letter, 16),
+                   vec!["Whose rutabagas", "these are I", "think I know",
+                        "His house is in", "the village", "throgh;"]);
+        // fillは段落内の改行をつなげて詰め直す
+        assert_eq!(wrap::fill(letter, 30),
+                   "Whose rutabagas these are I\nthink I know His house is in\nthe village throgh;");
+
+        // 最適化では行末の余白が均される
+        let text = "aaa bb cc ddddd";
+        assert_eq!(Wrapper::new(6).wrap(text), vec!["aaa bb", "cc", "ddddd"]);
+        assert_eq!(Wrapper::new(6).algorithm(WrapAlgorithm::OptimalFit).wrap(text),
+                   vec!["aaa", "bb cc", "ddddd"]);
+
+        // 日本語は2桁ずつ数え、禁則処理で"」"を行頭に置かない
+        let text = "吾輩は猫である。名前はまだ無い。「どこで生れたか」とんと見当がつかぬ。";
+        assert_eq!(wrap::wrap(text, 16),
+                   vec!["吾輩は猫である。", "名前はまだ無い。", "「どこで生れた", "か」とんと見当が", "つかぬ。"]);
+        assert_eq!(Wrapper::new(16).kinsoku(false).wrap(text)[3], "」とんと見当がつ");
+
+        // ぶら下げインデント
+        let wrapper = Wrapper::new(20).initial_indent("- ").subsequent_indent("  ");
+        assert_eq!(wrapper.fill("Rust strings are UTF-8 うどん and more"),
+                   "- Rust strings are\n  UTF-8 うどん and\n  more");
+        // 2行目以降の方が狭くても、最適化で行数が増えることはない
+        let text = "aaaa bbbb cccc dddd eeee ffff";
+        let hanging = Wrapper::new(20).subsequent_indent("          ");
+        assert_eq!(hanging.wrap(text), vec!["aaaa bbbb cccc dddd", "          eeee ffff"]);
+        assert_eq!(hanging.clone().algorithm(WrapAlgorithm::OptimalFit).wrap(text), hanging.wrap(text));
+    }
+    {
+        use rust_string_and_text_study::align::{Align, Padded};
+
+        // "{:6}"は文字数で数えるので、端末では"うどん"の後ろが3桁余分に空く
+        assert_eq!(format!("[{:6}]", "うどん"), "[うどん   ]");
+        assert_eq!(format!("[{}]", Padded::new("うどん", 6)), "[うどん]");
+        assert_eq!(format!("[{}]", Padded::new("うどん", 8).right()), "[  うどん]");
+        // 結合文字は幅0なので"Élan"（E + U+0301）も4桁
+        assert_eq!(format!("[{}]", Padded::new("E\u{301}lan", 6).center().fill('*')), "[*E\u{301}lan*]");
+        assert_eq!(format!("[{}]", Padded::new(42, 5).align(Align::from_char('^').unwrap())), "[ 42  ]");
+
+        // 全角の詰め物は2桁ずつ、余った1桁は空白
+        assert_eq!(format!("[{}]", Padded::new("ab", 7).fill('・')), "[ab・・ ]");
+
+        // 切り詰めも桁数で数え、書記素クラスタの途中では切らない
+        assert_eq!(Padded::new("きつねうどん", 8).truncate(8).to_string(), "きつね… "); // "…"は1桁
+        assert_eq!(Padded::new("the\u{301}atre", 5).truncate(5).ellipsis("..").to_string(), "the\u{301}..");
+        assert_eq!(Padded::new("short", 8).truncate(8).to_string(), "short   ");
+
+        // 混在した表も揃う
+        let rows = [("うどん", 380), ("E\u{301}lan", 1200)];
+        let lines: Vec<String> = rows.iter()
+            .map(|&(name, price)| format!("{}|{}", Padded::new(name, 8), Padded::new(price, 6).right()))
+            .collect();
+        assert_eq!(lines, vec!["うどん  |   380", "E\u{301}lan    |  1200"]);
+    }
+    {
+        use rust_string_and_text_study::width::{AmbiguousWidth, DisplayWidth};
+
+        // charの分類と同じように、文字ごとの桁数を問い合わせる
+        assert_eq!('a'.display_width(), 1);
+        assert_eq!('う'.display_width(), 2);
+        assert_eq!('Ａ'.display_width(), 2); // 全角英字
+        assert_eq!('ｱ'.display_width(), 1); // 半角カナ
+        assert_eq!('\u{301}'.display_width(), 0); // 結合文字
+        assert_eq!('\u{200b}'.display_width(), 0); // ゼロ幅空白
+
+        // "○"や"Α"はAmbiguous: 欧米の端末では1桁、日本語の端末では2桁
+        assert_eq!("○×".display_width(), 2);
+        assert_eq!("○×".display_width_with(AmbiguousWidth::Wide), 4);
+        assert_eq!("abc".display_width_with(AmbiguousWidth::Wide), 3);
+
+        // 文字列は書記素クラスタごとに数える
+        assert_eq!("うどん".display_width(), 6);
+        assert_eq!("the\u{301}".display_width(), 3);
+        assert_eq!("🍜".display_width(), 2);
+        assert_eq!("\u{2764}\u{fe0f}".display_width(), 2); // ❤️ 異体字セレクタで絵文字表示
+        assert_eq!("\u{231a}\u{fe0e}".display_width(), 1); // ⌚︎ テキスト表示
+        assert_eq!("🇯🇵".display_width(), 2); // 国旗は地域指示記号2つで1つ
+        assert_eq!("👍🏽".display_width(), 2); // 肌の色の修飾
+        assert_eq!("👨\u{200d}👩\u{200d}👧".display_width(), 2); // ZWJで連結した家族
+
+        // Paddedも同じ数え方で、Ambiguousの扱いを選べる
+        use rust_string_and_text_study::align::Padded;
+        assert_eq!(format!("[{}]", Padded::new("○×", 4)), "[○×  ]");
+        assert_eq!(format!("[{}]", Padded::new("○×", 4).ambiguous_width(AmbiguousWidth::Wide)), "[○×]");
+        assert_eq!(format!("[{}]", Padded::new("🍜", 3)), "[🍜 ]");
+    }
+    {
+        use rust_string_and_text_study::table::{Cell, Column, Table, TableStyle};
+
+        // "{description:.<25}{quantity:2} @{price:5.2}"の幅を、全行から自動で決める
+        let mut table = Table::new()
+            .column(Column::new("description").fill('.'))
+            .column(Column::new("qty"))
+            .column(Column::new("price").precision(2));
+        table.add_row(vec![Cell::from("Maple Turmeric Latte"), Cell::from(3), Cell::from(3.25)]);
+        table.add_row(vec![Cell::from("きつねうどん|大"), Cell::from(12), Cell::from(4.5)]);
+
+        // 数値の列は右寄せ、全角文字は2桁で数える
+        assert_eq!(table.to_string(),
+                   "description           qty  price\n\
+                    --------------------  ---  -----\n\
+                    Maple Turmeric Latte    3   3.25\n\
+                    きつねうどん|大.....   12   4.50\n");
+        assert_eq!(table.render(TableStyle::Markdown),
+                   "| description          | qty | price |\n\
+                    | :------------------- | --: | ----: |\n\
+                    | Maple Turmeric Latte |   3 |  3.25 |\n\
+                    | きつねうどん\\|大.... |  12 |  4.50 |\n");
+        assert_eq!(table.render(TableStyle::Csv),
+                   "description,qty,price\r\n\
+                    Maple Turmeric Latte,3,3.25\r\n\
+                    きつねうどん|大,12,4.50\r\n");
+        assert_eq!(table.render(TableStyle::Box),
+                   "┌──────────────────────┬─────┬───────┐\n\
+                    │ description          │ qty │ price │\n\
+                    ├──────────────────────┼─────┼───────┤\n\
+                    │ Maple Turmeric Latte │   3 │  3.25 │\n\
+                    │ きつねうどん|大..... │  12 │  4.50 │\n\
+                    └──────────────────────┴─────┴───────┘\n");
+
+        // 64ビットの符号なし整数も折り返さずに表示する
+        let mut counters = Table::new().column(Column::new("counter")).column(Column::new("value"));
+        counters.add_row(vec![Cell::from("max"), Cell::from(u64::MAX)]);
+        counters.add_row(vec![Cell::from("min"), Cell::from(i64::MIN)]);
+        assert_eq!(counters.render(TableStyle::Csv), "counter,value\r\nmax,18446744073709551615\r\nmin,-9223372036854775808\r\n");
+    }
+    {
+        use rust_string_and_text_study::format_spec::{Args, FormatError, FormatString};
+
+        // 設定ファイルから読んだ書式を実行時に解釈する
+        let format = FormatString::parse("{lsb:02x} {msb:02x} {insn}").unwrap();
+        let args = Args::new().named("lsb", 0x0a).named("msb", 0xff).named("insn", "mov");
+        assert_eq!(format.render(&args).unwrap(), "0a ff mov");
+
+        // format!と同じ結果になる
+        let format = FormatString::parse("{description:.<25}{quantity:2} @{price:5.2}").unwrap();
+        let args = Args::new().named("price", 3.25).named("quantity", 3).named("description", "Maple Turmeric Latte");
+        assert_eq!(format.render(&args).unwrap(), "Maple Turmeric Latte..... 3 @ 3.25");
+
+        let format = FormatString::parse("{mode} {2} {} {}").unwrap();
+        let args = Args::new().arg("people").arg("eater").arg("purple").named("mode", "flying");
+        assert_eq!(format.render(&args).unwrap(), "flying purple people eater");
+
+        let format = FormatString::parse("{:>1$}|{2:+08.2}|{3:#010b}|{{x}}|{4:e}|{:.*}").unwrap();
+        let args = Args::new().arg("bookends").arg(11).arg(-1.23456).arg(5).arg(1234.5);
+        assert_eq!(format.render(&args).unwrap(),
+                   format!("{:>1$}|{2:+08.2}|{3:#010b}|{{x}}|{4:e}|{:.*}", "bookends", 11, -1.23456, 5, 1234.5));
+        let format = FormatString::parse("{0:+#012x}|{0:^+12}|{0:=^12}").unwrap();
+        assert_eq!(format.render(&Args::new().arg(1234)).unwrap(), "+0x0000004d2|   +1234    |====1234====");
+        // 負の整数の16進・2進は元の型の幅の2の補数になり、NaNには'+'を付けない
+        let format = FormatString::parse("{0:x}|{1:#b}|{2:x?}|{3:X?}|{4:+}|{4:+?}|{5:?}").unwrap();
+        let args = Args::new().arg(-1i8).arg(-2i16).arg(-1i32).arg(-255i16).arg(f64::NAN).arg(-7i64);
+        assert_eq!(format.render(&args).unwrap(),
+                   format!("{0:x}|{1:#b}|{2:x?}|{3:X?}|{4:+}|{4:+?}|{5:?}", -1i8, -2i16, -1i32, -255i16, f64::NAN, -7i64));
+        // 2^53を超える整数の指数表記も桁を落とさない
+        let format = FormatString::parse("{0:e}|{0:.3E}|{1:e}|{2:e}").unwrap();
+        let args = Args::new().arg(u64::MAX).arg(i64::MIN).arg(9007199254740993u64);
+        assert_eq!(format.render(&args).unwrap(),
+                   format!("{0:e}|{0:.3E}|{1:e}|{2:e}", u64::MAX, i64::MIN, 9007199254740993u64));
+
+        // 書式の誤りは位置付きで報告する
+        assert_eq!(FormatString::parse("id={"), Err(FormatError::UnmatchedBrace { position: 3 }));
+        assert_eq!(FormatString::parse("{:q}").unwrap_err().to_string(), "unknown format type \"q\" at byte 2");
+        let format = FormatString::parse("{name} is {age:x}").unwrap();
+        assert_eq!(format.render(&Args::new().named("age", 42)).unwrap_err().to_string(),
+                   "missing argument 'name' for placeholder at byte 0");
+        assert_eq!(format.render(&Args::new().named("name", "Ann").named("age", "old")).unwrap_err().to_string(),
+                   "cannot format string with LowerHex at byte 10");
+    }
+    {
+        use rust_string_and_text_study::template::{Context, Template, TemplateSet};
+
+        // 一度解析しておけば、letterと同じようにStringへ何度でも書き出せる
+        let template = Template::parse("Dear {{ name | trim }},\n\
+                                        \n\
+                                        {% for item in items %}\n\
+                                        \x20 {{ loop.index }}. {{ item.name | pad(10) }}{{ item.qty }}\n\
+                                        {% endfor %}\n\
+                                        {% if vip %}\n\
+                                        Thanks, VIP!\n\
+                                        {% elif not items %}\n\
+                                        Nothing ordered.\n\
+                                        {% else %}\n\
+                                        Thanks.\n\
+                                        {% endif %}\n").unwrap();
+        let items = vec![Context::new().set("name", "うどん").set("qty", 2),
+                         Context::new().set("name", "soba").set("qty", 1)];
+        let context = Context::new().set("name", " Ann ").set("vip", false).set("items", items);
+        let mut letter = String::new();
+        template.render(&context, &mut letter).unwrap();
+        // タグだけの行は出力に残らない。padは表示幅で揃える
+        assert_eq!(letter, "Dear Ann,\n\n  1. うどん    2\n  2. soba      1\nThanks.\n");
+
+        // 名前が.htmlならHTML、.shならシェル向けに自動でエスケープする
+        let mut set = TemplateSet::new();
+        set.add("row.html", "<li>{{ item }}</li>").unwrap();
+        set.add("list.html", "<ul>{% for item in items %}{% include \"row.html\" %}{% endfor %}</ul>{{ footer | raw }}")
+            .unwrap();
+        set.add("search.sh", "grep {{ pattern }} {{ file }}").unwrap();
+        let context = Context::new().set("items", vec!["a<b", "\"q\""]).set("footer", "<hr>")
+                                    .set("pattern", "it's").set("file", "log.txt");
+        let mut html = String::new();
+        set.render("list.html", &context, &mut html).unwrap();
+        assert_eq!(html, "<ul><li>a&lt;b</li><li>&quot;q&quot;</li></ul><hr>");
+        let mut command = String::new();
+        set.render("search.sh", &context, &mut command).unwrap();
+        assert_eq!(command, "grep 'it'\\''s' log.txt");
+
+        // 構文やフィルタ名の誤りは解析時に、未定義の変数は描画時にエラーになる
+        assert_eq!(Template::parse("Hi\n  {{ name | shout }}").unwrap_err().to_string(),
+                   "2:3: unknown filter \"shout\"");
+        // joinはリストに最初に適用するフィルタで、途中に置くと解析時のエラーになる
+        let tags = Context::new().set("tags", vec!["rust", "text"]);
+        assert_eq!(Template::parse("{{ tags | join(\", \") | upper }}").unwrap().render_to_string(&tags).unwrap(),
+                   "RUST, TEXT");
+        assert_eq!(Template::parse("{{ tags | upper | join(\", \") }}").unwrap_err().to_string(),
+                   "1:1: filter \"join\" must come first");
+        // notは条件にだけ書ける
+        assert_eq!(Template::parse("ok\n {{ not flag }}").unwrap_err().to_string(), "2:2: invalid expression \"not flag\"");
+        assert!(Template::parse("{% for x in not xs %}{% endfor %}").is_err());
+        assert_eq!(Template::parse("{% for x in xs %}").unwrap_err().to_string(), "1:1: missing {% endfor %}");
+        assert_eq!(Template::parse("{{ missing }}").unwrap().render_to_string(&Context::new()).unwrap_err().to_string(),
+                   "line 1: undefined variable 'missing'");
+    }
+
+    // 桁区切り・小数点の記号・丸め・通貨と百分率
+    {
+        use rust_string_and_text_study::number::{Grouping, NumberFormat, Precision, Style};
+
+        let us = NumberFormat::locale("en-US").unwrap();
+        assert_eq!(us.format(1234567), "1,234,567");
+        assert_eq!(us.format(-1234.5678), "-1,234.5678");
+        assert_eq!(format!("[{:>12}]", us.apply(1234567)), "[   1,234,567]");
+        assert_eq!(format!("[{:*<12.2}]", us.apply(1234567)), "[1,234,567***]"); // 精度で切り詰めない
+
+        let de = NumberFormat::locale("de-DE").unwrap().precision(Precision::Fixed(2));
+        assert_eq!(de.format(1234567.891), "1.234.567,89");
+        let euro = de.clone().style(Style::CurrencySuffix(" €".to_string()));
+        assert_eq!(euro.format(1234.5), "1.234,50 €");
+        assert_eq!(NumberFormat::locale("fr-FR").unwrap().format(1234567), "1\u{202f}234\u{202f}567");
+
+        let india = NumberFormat::locale("en-IN").unwrap();
+        assert_eq!(india.format(1234567), "12,34,567");
+        assert_eq!(india.format(123456789u64), "12,34,56,789");
+
+        let man = NumberFormat::new().grouping(Grouping::Myriad);
+        assert_eq!(man.format(12345678), "1234万5678");
+        assert_eq!(man.format(100050000), "1億0005万");
+        assert_eq!(man.format(300000000000u64), "3000億");
+
+        let dollar = NumberFormat::new().precision(Precision::Fixed(2)).style(Style::CurrencyPrefix("$".to_string()));
+        assert_eq!(dollar.format(-1234.5), "-$1,234.50");
+        assert_eq!(dollar.format(-0.001), "$0.00");
+
+        let percent = NumberFormat::new().precision(Precision::Fixed(1)).style(Style::Percent);
+        assert_eq!(percent.format(0.1234), "12.3%");
+        assert_eq!(NumberFormat::locale("de-DE").unwrap().style(Style::Percent).format(5), "500\u{a0}%");
+        assert_eq!(NumberFormat::new().grouping(Grouping::None).style(Style::Percent).format(i128::MIN),
+                   format!("{}00%", i128::MIN));
+
+        let sig = NumberFormat::new().precision(Precision::Significant(3));
+        assert_eq!(sig.format(1234.5678), "1,230");
+        assert_eq!(sig.format(0.00012345), "0.000123");
+        assert_eq!(sig.format(9996), "10,000");
+        assert_eq!(NumberFormat::new().plus_sign(true).format(42), "+42");
+        assert_eq!(NumberFormat::new().grouping(Grouping::None).format(1234567), "1234567");
+    }
+
+    // バイト数・時間・SI接頭辞の表記と読み戻し
+    {
+        use rust_string_and_text_study::humanize::{ByteSize, HumanDuration, ParseError, Si};
+        use std::time::Duration;
+
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(format!("{:#}", ByteSize(1536)), "1.5 kB");
+        assert_eq!(format!("{:.2}", ByteSize(5 * 1024 * 1024 * 1024 + 1)), "5.00 GiB");
+        assert_eq!(format!("[{:>10}]", ByteSize(1048575)), "[   1.0 MiB]");
+        assert_eq!("1.5 KiB".parse(), Ok(ByteSize(1536)));
+        assert_eq!("2MB".parse(), Ok(ByteSize(2_000_000)));
+        assert_eq!(" 10 kb ".parse(), Ok(ByteSize(10_000)));
+        assert_eq!("16 EiB".parse::<ByteSize>(), Err(ParseError::OutOfRange));
+        assert_eq!("3 XB".parse::<ByteSize>().unwrap_err().to_string(), "unknown unit 'XB' at byte 2");
+
+        let duration = HumanDuration(Duration::from_secs(3 * 3600 + 2 * 60 + 5));
+        assert_eq!(duration.to_string(), "3h 02m 05s");
+        assert_eq!(format!("{:#}", duration), "PT3H2M5S");
+        assert_eq!(HumanDuration(Duration::from_secs(2 * 86400 + 5)).to_string(), "2d 00h 00m 05s");
+        assert_eq!(format!("{:#}", HumanDuration(Duration::from_secs(86400))), "P1D");
+        assert_eq!(format!("{:.3}", HumanDuration(Duration::from_millis(65250))), "1m 05.250s");
+        assert_eq!(format!("{:#.1}", HumanDuration(Duration::from_millis(1250))), "PT1.3S");
+        assert_eq!(format!("{:#}", HumanDuration(Duration::from_secs(0))), "PT0S");
+        assert_eq!("3h 02m 05s".parse(), Ok(duration));
+        assert_eq!("PT3H2M5S".parse(), Ok(duration));
+        assert_eq!("1h30m".parse(), Ok(HumanDuration(Duration::from_secs(5400))));
+        assert_eq!("1.5s 250ms".parse(), Ok(HumanDuration(Duration::from_millis(1750))));
+        assert_eq!("P1W".parse(), Ok(HumanDuration(Duration::from_secs(7 * 86400))));
+        assert_eq!("PT0,5S".parse(), Ok(HumanDuration(Duration::from_millis(500))));
+        assert_eq!("P1Y".parse::<HumanDuration>().unwrap_err().to_string(), "unknown unit 'Y' at byte 2");
+        assert_eq!("PT".parse::<HumanDuration>(), Err(ParseError::InvalidNumber { position: 2 }));
+        assert!("P1DT".parse::<HumanDuration>().is_err());
+        assert_eq!("3x".parse::<HumanDuration>().unwrap_err().to_string(), "unknown unit 'x' at byte 1");
+
+        assert_eq!(Si(1234.0).to_string(), "1.23k");
+        assert_eq!(Si(4.5e-6).to_string(), "4.5µ");
+        assert_eq!(Si(-0.001).to_string(), "-1m");
+        assert_eq!(format!("{:+}|{:+.2}|{:+}|{:+}", Si(-0.0), Si(-0.0), Si(0.0), Si(f64::NAN)), "-0|-0.00|+0|NaN");
+        assert_eq!(Si(999.7).to_string(), "1k");
+        assert_eq!(Si(12.0).to_string(), "12");
+        assert_eq!(format!("{:8.2}", Si(11186.0)), "  11.19k");
+        assert_eq!(format!("{:<+8.1}", Si(2.2e9)), "+2.2G   ");
+        assert_eq!("1.23k".parse(), Ok(Si(1230.0)));
+        assert_eq!("4.5u".parse(), Ok(Si(4.5e-6)));
+        assert_eq!("-4.5 µ".parse(), Ok(Si(-4.5e-6)));
+        assert_eq!("1.5E".parse(), Ok(Si(1.5e18)));
+        assert_eq!("k".parse::<Si>().unwrap_err().to_string(), "invalid number at byte 0");
+    }
+
+    // 読み戻せる最短の表記・正確な10進展開・厳格な解析
+    {
+        use rust_string_and_text_study::float::{parse_strict, shortest_digits, Digits, Exact, FloatGrammar, Shortest};
+
+        // "{:.6}"は読み戻すと元の値にならないことがある
+        let third = 1.0 / 3.0;
+        assert_ne!(format!("{:.6}", third).parse::<f64>().unwrap(), third);
+        assert_eq!(Shortest(third).to_string(), "0.3333333333333333");
+        assert_eq!(Shortest(third).to_string().parse::<f64>().unwrap(), third);
+        assert_eq!(Shortest(0.1 + 0.2).to_string(), "0.30000000000000004");
+        assert_eq!(Shortest(1e21).to_string(), "1e21");
+        assert_eq!(Shortest(123456.0).to_string(), "123456");
+        assert_eq!(Shortest(-1.5e-8).to_string(), "-1.5e-8");
+        assert_eq!(format!("{:e}", Shortest(1234.5)), "1.2345e3");
+        assert_eq!(format!("[{:>8}]", Shortest(0.25)), "[    0.25]");
+        assert_eq!(shortest_digits(5e-324), Some(Digits { negative: false, digits: "5".to_string(), exponent: -324 }));
+        assert_eq!(shortest_digits(f64::NAN), None);
+
+        assert_eq!(Exact(0.1).to_string(), "0.1000000000000000055511151231257827021181583404541015625");
+        assert_eq!(Exact(1e23).to_string(), "99999999999999991611392");
+        assert_eq!(Exact(-0.5).to_string(), "-0.5");
+        assert_eq!(Exact(2.0).to_string(), "2");
+
+        assert_eq!(parse_strict("-12.5e3"), Ok(-12500.0));
+        assert_eq!(parse_strict("inf").unwrap_err().to_string(), "unexpected 'i' at byte 0");
+        assert_eq!(parse_strict("NaN").unwrap_err().to_string(), "unexpected 'N' at byte 0");
+        assert_eq!(parse_strict(".5").unwrap_err().to_string(), "expected a digit at byte 0");
+        assert_eq!(parse_strict("5.").unwrap_err().to_string(), "expected a digit at byte 2");
+        assert_eq!(parse_strict("+1").unwrap_err().to_string(), "unexpected '+' at byte 0");
+        assert_eq!(parse_strict("1e").unwrap_err().to_string(), "expected a digit at byte 2");
+        assert_eq!(parse_strict("1.5 ").unwrap_err().to_string(), "unexpected ' ' at byte 3");
+        assert_eq!(parse_strict("1e400").unwrap_err().to_string(), "value out of range");
+        assert_eq!(parse_strict("").unwrap_err().to_string(), "empty string");
+
+        let rust = FloatGrammar::rust();
+        for text in &["+.5", "5.", "inf", "-Infinity", "NaN", "1e400", "1_0"] {
+            assert_eq!(rust.parse(text).is_ok(), text.parse::<f64>().is_ok(), "{}", text);
+        }
+        let config = FloatGrammar::strict().underscores(true).exponent(false);
+        assert_eq!(config.parse("1_000.25"), Ok(1000.25));
+        assert_eq!(config.parse("1__0").unwrap_err().to_string(), "unexpected '_' at byte 1");
+        assert_eq!(config.parse("1e3").unwrap_err().to_string(), "unexpected 'e' at byte 1");
+    }
+
+    // 工学表記と指数を固定した科学表記
+    {
+        use rust_string_and_text_study::notation::{Engineering, ExponentStyle, FixedExponent};
+
+        let input = 1234.5678;
+        assert_eq!(format!("{:.3e}", input), "1.235e3");
+        assert_eq!(format!("{}", Engineering::new(input)), "1.2345678e3");
+        assert_eq!(format!("{:.3}", Engineering::new(input)), "1.235e3");
+        assert_eq!(format!("{:.2}", Engineering::new(0.0000123456)), "12.35e-6");
+        assert_eq!(format!("{:.1}", Engineering::new(999.96)), "1.0e3");
+        assert_eq!(format!("{:.3}", Engineering::new(-47e3)), "-47.000e3");
+        assert_eq!(format!("{:.2}", Engineering::new(0.0)), "0.00e0");
+        assert_eq!(format!("{:+.3}", Engineering::new(input).style(ExponentStyle::Printf)), "+1.235e+03");
+        assert_eq!(format!("{:.3}", Engineering::new(0.00047).style(ExponentStyle::TimesTen)), "470.000×10⁻⁶");
+        assert_eq!(format!("{:.1}", Engineering::new(2.5e10).style(ExponentStyle::UpperE)), "25.0E9");
+        assert_eq!(format!("[{:>12.3}]", Engineering::new(input)), "[     1.235e3]");
+        assert_eq!(format!("[{:<12.3}]", Engineering::new(input)), "[1.235e3     ]");
+        assert_eq!(format!("{:012.3}", Engineering::new(-input)), "-00001.235e3");
+        assert_eq!(format!("{}", Engineering::new(f64::INFINITY)), "inf");
+
+        // 列の値を同じ指数で揃える
+        let column: Vec<String> = [12.5, 1234.5678, 0.75]
+            .iter()
+            .map(|&value| format!("{:>10.3}", FixedExponent::new(value, 3)))
+            .collect();
+        assert_eq!(column, vec!["   0.012e3", "   1.235e3", "   0.001e3"]);
+        assert_eq!(format!("{:.2}", FixedExponent::new(0.0005, 3)), "0.00e3");
+        assert_eq!(format!("{}", FixedExponent::new(0.015, -3)), "15e-3");
+        // 0.125は2進数でちょうど表せるので、"{:.2}"と同じく偶数に丸める
+        assert_eq!(format!("{:.2}", FixedExponent::new(0.125, 0)), format!("{:.2}e0", 0.125));
+        assert_eq!(format!("{:.1}", FixedExponent::new(-2.5e-6, -6).style(ExponentStyle::Printf)), "-2.5e-06");
+    }
+
+    // 書式の指定に従い、読み戻せる複素数型
+    {
+        use rust_string_and_text_study::complex::{Complex, ParseComplexError};
+
+        let one_twenty = Complex::new(-0.5, 0.866);
+        assert_eq!(format!("{}", one_twenty), "-0.5 + 0.866i");
+        assert_eq!(format!("{}", Complex::new(-0.5, -0.866)), "-0.5 - 0.866i");
+        assert_eq!(format!("{:.2}", one_twenty), "-0.50 + 0.87i");
+        assert_eq!(format!("{:+}", Complex::new(1.0, 2.0)), "+1 + 2i");
+        assert_eq!(format!("{:+}|{:+}", Complex::new(-0.0, 1.0), Complex::new(f64::NAN, 1.0)), "-0 + 1i|NaN + 1i");
+        assert_eq!(format!("[{:>12}]", Complex::new(1.0, -2.0)), "[      1 - 2i]");
+        assert_eq!(format!("[{:*<10.1}]", Complex::new(1.0f32, 0.5)), "[1.0 + 0.5i]");
+        let ninety = Complex::new(0.0, 2.0);
+        assert_eq!(format!("{:#}", ninety), "2 ⊿ 90°");
+        assert_eq!(format!("{:#.1}", Complex::new(-1.0, -1.0)), "1.4 ⊿ -135.0°");
+
+        assert_eq!("-0.5 + 0.866i".parse(), Ok(one_twenty));
+        assert_eq!("-0.5-0.866i".parse(), Ok(Complex::new(-0.5, -0.866)));
+        assert_eq!("3".parse(), Ok(Complex::new(3.0, 0.0)));
+        assert_eq!("-2i".parse(), Ok(Complex::new(0.0, -2.0)));
+        assert_eq!("1 - i".parse(), Ok(Complex::new(1.0, -1.0)));
+        assert_eq!("1e-3+2.5e2j".parse(), Ok(Complex::new(0.001, 250.0)));
+        let polar: Complex<f64> = "2 ⊿ 90°".parse().unwrap();
+        assert_eq!(format!("{:.3}", polar), "0.000 + 2.000i");
+        assert_eq!(format!("{:#}", "1∠3.141592653589793".parse::<Complex<f64>>().unwrap()), "1 ⊿ 180°");
+        assert_eq!("1 + xi".parse::<Complex<f64>>(), Err(ParseComplexError::InvalidNumber { position: 4 }));
+        assert_eq!("2 ⊿ °".parse::<Complex<f64>>().unwrap_err().to_string(), "invalid number at byte 6");
+        assert_eq!(" ".parse::<Complex<f64>>(), Err(ParseComplexError::Empty));
+
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+        assert_eq!(a + b, Complex::new(4.0, 1.0));
+        assert_eq!(a - b, Complex::new(-2.0, 3.0));
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        assert_eq!((a * b) / b, a);
+        assert_eq!(-a * 2.0, Complex::new(-2.0, -4.0));
+        assert_eq!(a.conj(), Complex::new(1.0, -2.0));
+        assert_eq!(Complex::new(3.0, 4.0).abs(), 5.0);
+        // 整数の成分でも加減乗算はできる
+        assert_eq!(Complex::new(1, 2) * Complex::new(3, -1), Complex::new(5, 5));
+    }
+
+    // 字下げ・行幅・キーの整列・上限・色を指定できる整形表示
+    {
+        use rust_string_and_text_study::pretty::PrettyPrinter;
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert("Portland", (42.5237606, -122.6819273));
+        map.insert("Taipei",   (25.0375167,  121.5637));
+
+        // "{:#?}"と違い、1行に収まるタプルは改行しない。HashMapの順序に依らずキーの順に並ぶ
+        let printer = PrettyPrinter::new().indent(2).width(44).sort_keys(true);
+        assert_eq!(printer.format(&map),
+                   "{\n  \"Portland\": (42.5237606, -122.6819273),\n  \"Taipei\": (25.0375167, 121.5637),\n}");
+        assert_eq!(printer.clone().width(80).format(&map),
+                   "{\"Portland\": (42.5237606, -122.6819273), \"Taipei\": (25.0375167, 121.5637)}");
+
+        #[derive(Debug)]
+        struct Station { name: &'static str, location: (f64, f64), tags: Vec<&'static str> }
+        let station = Station { name: "Taipei Main", location: (25.0478, 121.5170), tags: vec!["rail", "metro"] };
+        assert_eq!(PrettyPrinter::new().width(40).format(&station),
+                   "Station {\n    name: \"Taipei Main\",\n    location: (25.0478, 121.517),\n    tags: [\"rail\", \"metro\"],\n}");
+        assert_eq!(PrettyPrinter::new().max_depth(1).format(&station),
+                   "Station { name: \"Taipei Main\", location: (…), tags: […] }");
+        assert_eq!(PrettyPrinter::new().max_items(3).format(&(1..=10).collect::<Vec<_>>()), "[1, 2, 3, … 7 more]");
+
+        let mut numbers = HashMap::new();
+        numbers.insert(10, 'a');
+        numbers.insert(9, '\'');
+        numbers.insert(100, '}');
+        assert_eq!(PrettyPrinter::new().sort_keys(true).format(&numbers), "{9: '\\'', 10: 'a', 100: '}'}");
+
+        // 色を付けても行幅の計算はエスケープシーケンスを数えない
+        assert_eq!(PrettyPrinter::new().colors(true).format(&Some((1, "one"))),
+                   "\x1b[33mSome\x1b[0m((\x1b[36m1\x1b[0m, \x1b[32m\"one\"\x1b[0m))");
+        assert_eq!(PrettyPrinter::new().colors(true).width(15).format(&Some((1, "one"))),
+                   "\x1b[33mSome\x1b[0m(\n    (\x1b[36m1\x1b[0m, \x1b[32m\"one\"\x1b[0m),\n)");
+        // 読めない形はそのまま
+        assert_eq!(PrettyPrinter::new().format_debug("not (balanced"), "not (balanced");
+    }
+
+    // 同じ値をJSON・YAML・TOMLで書き出す
+    {
+        use rust_string_and_text_study::complex::Complex;
+        use rust_string_and_text_study::serialize::{FloatFormat, KeyOrder, TextOptions, ToText, TomlError};
+        use rust_string_and_text_study::to_text;
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert("Portland", (42.5237606, -122.6819273));
+        map.insert("Taipei",   (25.0375167,  121.5637));
+
+        // キーを整列するので、HashMapの走査順に依らず同じ出力になる
+        let compact = TextOptions::new().indent(None);
+        assert_eq!(compact.json(&map), r#"{"Portland":[42.5237606,-122.6819273],"Taipei":[25.0375167,121.5637]}"#);
+        assert_eq!(map.to_yaml(),
+                   "Portland:\n  - 42.5237606\n  - -122.6819273\nTaipei:\n  - 25.0375167\n  - 121.5637\n");
+        assert_eq!(map.to_toml().unwrap(), "Portland = [42.5237606, -122.6819273]\nTaipei = [25.0375167, 121.5637]\n");
+        assert_eq!(compact.clone().key_order(KeyOrder::Iteration).json(&map).len(), compact.json(&map).len());
+
+        // deriveは全フィールドを宣言の順に書く
+        #[derive(ToText)]
+        struct Station { name: String, location: (f64, f64), tags: Vec<&'static str>, opened: Option<u32> }
+        let stations = vec![
+            Station { name: "Taipei Main".to_string(), location: (25.0478, 121.517),
+                      tags: vec!["rail", "yes"], opened: Some(1891) },
+            Station { name: "Tamsui: \"淡水\"\n".to_string(), location: (25.1677, 121.4453),
+                      tags: vec![], opened: None },
+        ];
+        assert_eq!(compact.json(&stations),
+                   concat!(r#"[{"name":"Taipei Main","location":[25.0478,121.517],"tags":["rail","yes"],"opened":1891},"#,
+                           r#"{"name":"Tamsui: \"淡水\"\n","location":[25.1677,121.4453],"tags":[],"opened":null}]"#));
+        // "yes"のように別の型に読まれる文字列と、": "や改行を含む文字列は引用符で囲む
+        assert_eq!(stations.to_yaml(),
+                   "- name: Taipei Main\n  location:\n    - 25.0478\n    - 121.517\n\
+                    \x20 tags:\n    - rail\n    - \"yes\"\n  opened: 1891\n\
+                    - name: \"Tamsui: \\\"淡水\\\"\\n\"\n  location:\n    - 25.1677\n    - 121.4453\n\
+                    \x20 tags: []\n  opened: null\n");
+
+        let mut line = HashMap::new();
+        line.insert("stations", stations);
+        // TOMLにnullはないので、Noneのフィールドは書かない
+        assert_eq!(line.to_toml().unwrap(),
+                   "[[stations]]\nname = \"Taipei Main\"\nlocation = [25.0478, 121.517]\n\
+                    tags = [\"rail\", \"yes\"]\nopened = 1891\n\n\
+                    [[stations]]\nname = \"Tamsui: \\\"淡水\\\"\\n\"\nlocation = [25.1677, 121.4453]\ntags = []\n");
+        assert_eq!(vec![1, 2].to_toml(), Err(TomlError::NotTable));
+        let mut nulls = HashMap::new();
+        nulls.insert("values", vec![Some(1), None]);
+        assert_eq!(nulls.to_toml().unwrap_err().to_string(), "key 'values': null is not representable in TOML");
+
+        // 浮動小数点数は最短表記か桁数固定。整数に見える値には".0"を付ける
+        let floats = vec![1.0, 0.1, 1e21, f64::NAN];
+        assert_eq!(compact.json(&floats), "[1.0,0.1,1e21,null]");
+        assert_eq!(TextOptions::new().float_format(FloatFormat::Fixed(2)).yaml(&floats),
+                   "- 1.00\n- 0.10\n- 1000000000000000000000.00\n- .nan\n");
+        assert_eq!(compact.json(&vec![0.1f32, 16777216.0f32]), "[0.1,16777216.0]");
+        assert_eq!(compact.json(&Complex::new(-0.5, 0.866)), r#"{"re":-0.5,"im":0.866}"#);
+
+        // 字下げを広げても、リストの要素の中のブロックは"- "の後の桁に揃える
+        let wide = TextOptions::new().indent(Some(4));
+        assert_eq!(wide.yaml(&vec![vec![1, 2], vec![3]]), "- - 1\n  - 2\n- - 3\n");
+        assert_eq!(wide.yaml(&line["stations"][..1]),
+                   "- name: Taipei Main\n  location:\n      - 25.0478\n      - 121.517\n\
+                    \x20 tags:\n      - rail\n      - \"yes\"\n  opened: 1891\n");
+        assert_eq!(wide.yaml(&map),
+                   "Portland:\n    - 42.5237606\n    - -122.6819273\nTaipei:\n    - 25.0375167\n    - 121.5637\n");
+        assert_eq!(compact.json("tab\tand \u{1}"), r#""tab\tand \u0001""#);
+
+        // 型引数やタプル構造体にもderiveでき、to_text!は並べたフィールドだけを書く
+        #[derive(ToText)]
+        struct Tagged<'a, T: Clone = u8> where T: PartialEq { label: &'a str, values: Vec<T> }
+        #[derive(Clone, PartialEq, ToText)]
+        struct Point(i32, i32);
+        struct Secret { user: &'static str, password: &'static str }
+        to_text!(Secret { user });
+        let tagged = Tagged { label: "p", values: vec![Point(1, 2)] };
+        assert_eq!(compact.json(&tagged), r#"{"label":"p","values":[[1,2]]}"#);
+        assert_eq!(compact.json(&Secret { user: "jimb", password: "hunter2" }), r#"{"user":"jimb"}"#);
+    }
+
+    // 等しい文字列を1つのRc<str>にまとめるインターン
+    {
+        use rust_string_and_text_study::intern::{StringInterner, SyncStringInterner};
+        use std::collections::HashSet;
+        use std::rc::Rc;
+        use std::sync::Arc;
+        use std::thread;
+
+        let interner = StringInterner::new();
+        let original = interner.intern("mazurka");
+        let cloned = original.clone();
+        // 別に作った"mazurka"も登録済みの領域を指す
+        let impostor = interner.intern(&String::from("mazurka"));
+        assert_eq!(format!("{:p}", original), format!("{:p}", cloned));
+        assert_eq!(format!("{:p}", original), format!("{:p}", impostor));
+        assert_eq!(original, impostor);
+        assert!(Rc::ptr_eq(original.shared(), impostor.shared()));
+        assert_eq!(Rc::strong_count(original.shared()), 5);
+
+        let polka = interner.intern("polka");
+        assert_ne!(original, polka);
+        assert_eq!((original.id(), polka.id()), (0, 1));
+        assert_eq!(interner.resolve(1), Some(polka.clone()));
+        assert_eq!(interner.resolve(2), None);
+        assert_eq!(interner.get("waltz"), None);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(format!("{}|{:>8}|{:?}", original, polka, polka), "mazurka|   polka|#1 \"polka\"");
+        assert_eq!(original.to_uppercase(), "MAZURKA");
+        let unique: HashSet<_> = ["polka", "mazurka", "polka"].iter().map(|text| interner.intern(text)).collect();
+        assert_eq!(unique.len(), 2);
+
+        // 別のインターナーのシンボルは内容が同じでも別物
+        assert_ne!(StringInterner::new().intern("mazurka"), original);
+
+        // NFCで正規化すれば合成済みの"é"と"e"+結合アクセントが同じシンボルになる
+        let plain = StringInterner::new();
+        assert_ne!(plain.intern("th\u{e9}"), plain.intern("the\u{301}"));
+        let nfc = StringInterner::with_nfc(true);
+        let composed = nfc.intern("th\u{e9}");
+        assert_eq!(nfc.intern("the\u{301}"), composed);
+        assert_eq!(nfc.get("the\u{301}").map(|symbol| symbol.id()), Some(0));
+        assert_eq!(composed.as_str(), "th\u{e9}");
+
+        // スレッド間で共有するインターナーはArc<str>のシンボルを返す
+        let shared = Arc::new(SyncStringInterner::with_nfc(true));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || shared.intern(if i % 2 == 0 { "caf\u{e9}" } else { "cafe\u{301}" }))
+            })
+            .collect();
+        let symbols: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        assert!(symbols.iter().all(|symbol| *symbol == symbols[0]));
+        assert_eq!(shared.len(), 1);
+        assert_eq!(format!("{:?}", shared), "[\"caf\u{e9}\"]");
+    }
+
+    // 正規化した形で比べるので、"th\u{e9}"で登録したキーを"the\u{301}"で引ける
+    {
+        use rust_string_and_text_study::normalized::{NfkcCasefold, NormalizedStr, NormalizedString};
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::{BTreeMap, HashMap};
+        use std::hash::{Hash, Hasher};
+
+        fn hash<T: ?Sized + Hash>(t: &T) -> u64 {
+            let mut s = DefaultHasher::new();
+            t.hash(&mut s);
+            s.finish()
+        }
+
+        let composed: &NormalizedStr = NormalizedStr::new("th\u{e9}");
+        let decomposed: &NormalizedStr = NormalizedStr::new("the\u{301}");
+        assert!(composed == decomposed);
+        assert_eq!(hash(composed), hash(decomposed));
+        assert_eq!(hash(composed), hash("th\u{e9}"));
+        assert_eq!(decomposed.normalized(), "th\u{e9}");
+        assert_eq!(decomposed.as_str(), "the\u{301}");
+
+        let mut users: HashMap<NormalizedString, u32> = HashMap::new();
+        users.insert(NormalizedString::from("the\u{301}"), 1000);
+        assert_eq!(users.get(NormalizedStr::new("th\u{e9}")), Some(&1000));
+        *users.entry("th\u{e9}".into()).or_insert(0) += 1;
+        assert_eq!(users.len(), 1);
+        // キーは最初に登録した元の文字列のまま表示する
+        let (key, count) = users.iter().next().unwrap();
+        assert_eq!((key.as_str(), key.normalized(), *count), ("the\u{301}", "th\u{e9}", 1001));
+        assert_eq!(format!("[{:>5}] {:?}", key, key), "[ the\u{301}] \"the\\u{301}\"");
+
+        // NFKCと大文字小文字の畳み込みでは全角・合字・大文字も同じキーになる
+        let mut files: BTreeMap<NormalizedString<NfkcCasefold>, &str> = BTreeMap::new();
+        files.insert("Ｒｕｓｔ".into(), "fullwidth");
+        files.insert("\u{fb01}le".into(), "ligature");
+        files.insert("Zebra".into(), "upper");
+        assert_eq!(files.get(NormalizedStr::new("RUST")), Some(&"fullwidth"));
+        assert_eq!(files.get(NormalizedStr::new("FILE")), Some(&"ligature"));
+        assert!(NormalizedStr::<NfkcCasefold>::new("Caf\u{e9}") == NormalizedStr::new("CAFE\u{301}"));
+        // 順序は正規化した形で決まる（"Zebra"は"zebra"として最後）
+        let order: Vec<&str> = files.keys().map(|key| key.as_str()).collect();
+        assert_eq!(order, vec!["\u{fb01}le", "Ｒｕｓｔ", "Zebra"]);
+
+        let owned = NormalizedStr::<NfkcCasefold>::new("ＡＢＣ").to_owned();
+        assert_eq!(owned.normalized(), "abc");
+        assert_eq!(owned.into_string(), "ＡＢＣ");
+    }
 }
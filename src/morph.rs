@@ -0,0 +1,535 @@
+// 辞書を使った日本語の形態素解析
+//
+// MeCab/IPADIC形式の辞書（語彙の*.csv、連接コストのmatrix.def、文字種のchar.def、未知語のunk.def）を読み、
+// ラティスを組み立ててViterbiアルゴリズムでコスト最小の分割を選ぶ。
+// 配布物のIPADICはEUC-JPなので、UTF-8に変換したもの（mecab-ipadic-utf8等）を使うこと。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::csv::{parse_fields, Dialect};
+use crate::pattern::Script;
+
+#[derive(Clone, Debug)]
+struct Entry {
+    left_id: usize,
+    right_id: usize,
+    cost: i32,
+    // 品詞,品詞細分類1,品詞細分類2,品詞細分類3,活用型,活用形,原形,読み,発音
+    features: Vec<String>,
+}
+
+/// char.defの文字種
+#[derive(Clone, Debug)]
+struct Category {
+    name: String,
+    // 既知語があっても未知語処理を行うか
+    invoke: bool,
+    // 同じ文字種の連続をまとめるか
+    group: bool,
+    // 1〜length文字の未知語候補も作る
+    length: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dictionary {
+    entries: Vec<Entry>,
+    index: HashMap<String, Vec<usize>>,
+    max_chars: usize,
+    right_size: usize,
+    left_size: usize,
+    matrix: Vec<i32>,
+    categories: Vec<Category>,
+    // (範囲の先頭, 末尾, 文字種, 互換な文字種)。後の行ほど優先する
+    char_map: Vec<(char, char, usize, Vec<usize>)>,
+    unknown: Vec<Vec<Entry>>,
+}
+
+impl Dictionary {
+    /// 辞書ディレクトリ内の*.csv、matrix.def、char.def、unk.defを読み込む
+    ///
+    /// char.defとunk.defはなくてもよく、その場合は組み込みの文字種と未知語の既定値を使う。
+    /// unk.defを使う場合は、DEFAULTの未知語の行が必要。
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Dictionary, DictionaryError> {
+        let dir = dir.as_ref();
+        let mut lexicon = String::new();
+        let mut csv_files: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "csv"))
+            .collect();
+        csv_files.sort();
+        for path in csv_files {
+            lexicon.push_str(&fs::read_to_string(path)?);
+            if !lexicon.ends_with('\n') {
+                lexicon.push('\n');
+            }
+        }
+        let matrix = fs::read_to_string(dir.join("matrix.def"))?;
+        let optional = |name: &str| -> Result<Option<String>, DictionaryError> {
+            match fs::read_to_string(dir.join(name)) {
+                Ok(text) => Ok(Some(text)),
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        };
+        let char_def = optional("char.def")?;
+        let unk_def = optional("unk.def")?;
+        Dictionary::from_parts(&lexicon, &matrix, char_def.as_deref(), unk_def.as_deref())
+    }
+
+    /// メモリ上の各ファイルの内容から辞書を組み立てる
+    pub fn from_parts(
+        lexicon: &str,
+        matrix: &str,
+        char_def: Option<&str>,
+        unk_def: Option<&str>,
+    ) -> Result<Dictionary, DictionaryError> {
+        let mut dictionary = Dictionary {
+            entries: Vec::new(),
+            index: HashMap::new(),
+            max_chars: 0,
+            right_size: 0,
+            left_size: 0,
+            matrix: Vec::new(),
+            categories: Vec::new(),
+            char_map: Vec::new(),
+            unknown: Vec::new(),
+        };
+
+        for (surface, entry) in parse_entries("lexicon", lexicon)? {
+            dictionary.max_chars = dictionary.max_chars.max(surface.chars().count());
+            dictionary.index.entry(surface).or_default().push(dictionary.entries.len());
+            dictionary.entries.push(entry);
+        }
+
+        dictionary.parse_matrix(matrix)?;
+        match char_def {
+            Some(text) => dictionary.parse_char_def(text)?,
+            None => dictionary.builtin_char_def(),
+        }
+        dictionary.unknown = vec![Vec::new(); dictionary.categories.len()];
+        match unk_def {
+            Some(text) => {
+                for (name, entry) in parse_entries("unk.def", text)? {
+                    if let Some(category) = dictionary.category_named(&name) {
+                        dictionary.unknown[category].push(entry);
+                    }
+                }
+                // 未知語の規則がない文字種はDEFAULTで代用するので、DEFAULTがないと入力が消えてしまう
+                let default = dictionary.category_named("DEFAULT").expect("DEFAULT category");
+                if dictionary.unknown[default].is_empty() {
+                    return Err(DictionaryError::Format {
+                        file: "unk.def",
+                        line: 0,
+                        message: "missing unknown-word entry for DEFAULT".to_string(),
+                    });
+                }
+            }
+            None => dictionary.builtin_unknown(),
+        }
+
+        for entry in dictionary.entries.iter().chain(dictionary.unknown.iter().flatten()) {
+            if entry.right_id >= dictionary.right_size || entry.left_id >= dictionary.left_size {
+                return Err(DictionaryError::Format {
+                    file: "lexicon",
+                    line: 0,
+                    message: format!("context id out of matrix.def range: {:?}", entry.features),
+                });
+            }
+        }
+        Ok(dictionary)
+    }
+
+    fn parse_matrix(&mut self, text: &str) -> Result<(), DictionaryError> {
+        let error = |line: usize, message: &str| DictionaryError::Format {
+            file: "matrix.def",
+            line,
+            message: message.to_string(),
+        };
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or_else(|| error(1, "missing header"))?;
+        let sizes: Vec<usize> = header.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+        if sizes.len() != 2 {
+            return Err(error(1, "header must be \"<right ids> <left ids>\""));
+        }
+        self.right_size = sizes[0];
+        self.left_size = sizes[1];
+        self.matrix = vec![0; self.right_size * self.left_size];
+        for (index, line) in lines {
+            let numbers: Vec<i64> = line.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+            match numbers[..] {
+                [right, left, cost]
+                    if (0..self.right_size as i64).contains(&right) && (0..self.left_size as i64).contains(&left) =>
+                {
+                    self.matrix[right as usize * self.left_size + left as usize] = cost as i32;
+                }
+                _ => return Err(error(index + 1, "expected \"<right id> <left id> <cost>\"")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_char_def(&mut self, text: &str) -> Result<(), DictionaryError> {
+        let mut mappings = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields[0].starts_with("0x") {
+                mappings.push((index + 1, fields));
+                continue;
+            }
+            let flags: Vec<usize> = fields[1..].iter().filter_map(|n| n.parse().ok()).collect();
+            if flags.len() != 3 {
+                return Err(DictionaryError::Format {
+                    file: "char.def",
+                    line: index + 1,
+                    message: "expected \"<category> <invoke> <group> <length>\"".to_string(),
+                });
+            }
+            self.categories.push(Category {
+                name: fields[0].to_string(),
+                invoke: flags[0] != 0,
+                group: flags[1] != 0,
+                length: flags[2],
+            });
+        }
+        if self.category_named("DEFAULT").is_none() {
+            self.categories.insert(0, Category { name: "DEFAULT".to_string(), invoke: false, group: true, length: 0 });
+        }
+
+        for (line, fields) in mappings {
+            let error = |message: &str| DictionaryError::Format { file: "char.def", line, message: message.to_string() };
+            let mut bounds = fields[0].split("..").map(|code| {
+                u32::from_str_radix(code.trim_start_matches("0x"), 16).ok().and_then(std::char::from_u32)
+            });
+            let lo = bounds.next().flatten().ok_or_else(|| error("invalid code point"))?;
+            let hi = match bounds.next() {
+                Some(hi) => hi.ok_or_else(|| error("invalid code point"))?,
+                None => lo,
+            };
+            let mut categories = Vec::new();
+            for name in &fields[1..] {
+                categories.push(self.category_named(name).ok_or_else(|| error("undefined category"))?);
+            }
+            if categories.is_empty() {
+                return Err(error("missing category"));
+            }
+            let primary = categories[0];
+            self.char_map.push((lo, hi, primary, categories));
+        }
+        Ok(())
+    }
+
+    // IPADICのchar.defを簡略化した組み込みの文字種
+    fn builtin_char_def(&mut self) {
+        let definitions = [
+            ("DEFAULT", false, true, 0),
+            ("SPACE", false, true, 0),
+            ("KANJI", false, false, 2),
+            ("SYMBOL", true, true, 0),
+            ("NUMERIC", true, true, 0),
+            ("ALPHA", true, true, 0),
+            ("HIRAGANA", false, true, 2),
+            ("KATAKANA", true, true, 2),
+        ];
+        for &(name, invoke, group, length) in &definitions {
+            self.categories.push(Category { name: name.to_string(), invoke, group, length });
+        }
+    }
+
+    fn builtin_unknown(&mut self) {
+        for category in 0..self.categories.len() {
+            let features = match self.categories[category].name.as_str() {
+                "NUMERIC" => "名詞,数,*,*,*,*,*",
+                "SYMBOL" => "記号,一般,*,*,*,*,*",
+                "SPACE" => "記号,空白,*,*,*,*,*",
+                _ => "名詞,一般,*,*,*,*,*",
+            };
+            self.unknown[category].push(Entry {
+                left_id: 0,
+                right_id: 0,
+                cost: 10000,
+                features: features.split(',').map(String::from).collect(),
+            });
+        }
+    }
+
+    fn category_named(&self, name: &str) -> Option<usize> {
+        self.categories.iter().position(|category| category.name == name)
+    }
+
+    fn category_of(&self, ch: char) -> usize {
+        if let Some(&(_, _, primary, _)) = self.mapping_of(ch) {
+            return primary;
+        }
+        let name = if !self.char_map.is_empty() {
+            "DEFAULT"
+        } else if ch.is_whitespace() {
+            "SPACE"
+        } else if Script::Han.contains(ch) {
+            "KANJI"
+        } else if Script::Hiragana.contains(ch) {
+            "HIRAGANA"
+        } else if Script::Katakana.contains(ch) || ch == 'ー' {
+            "KATAKANA"
+        } else if ch.is_numeric() {
+            "NUMERIC"
+        } else if ch.is_alphabetic() {
+            "ALPHA"
+        } else {
+            "SYMBOL"
+        };
+        self.category_named(name).unwrap_or(0)
+    }
+
+    // chを文字種categoryの連続にまとめてよいか（char.defで複数の文字種を持つ文字は、そのどれとでもまとまる）
+    fn is_compatible(&self, ch: char, category: usize) -> bool {
+        match self.mapping_of(ch) {
+            Some((_, _, _, compatible)) => compatible.contains(&category),
+            None => self.category_of(ch) == category,
+        }
+    }
+
+    fn mapping_of(&self, ch: char) -> Option<&(char, char, usize, Vec<usize>)> {
+        self.char_map.iter().rev().find(|&&(lo, hi, _, _)| lo <= ch && ch <= hi)
+    }
+
+    fn connection_cost(&self, right_id: usize, left_id: usize) -> i64 {
+        i64::from(self.matrix[right_id * self.left_size + left_id])
+    }
+}
+
+fn parse_entries(file: &'static str, text: &str) -> Result<Vec<(String, Entry)>, DictionaryError> {
+    let dialect = Dialect::csv();
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |message: &str| DictionaryError::Format { file, line: index + 1, message: message.to_string() };
+        let fields = parse_fields(line, &dialect, index + 1).map_err(|e| error(&e.to_string()))?;
+        if fields.len() < 4 {
+            return Err(error("expected \"<surface>,<left id>,<right id>,<cost>,<features>...\""));
+        }
+        let number = |i: usize| fields[i].trim().parse::<i64>().map_err(|_| error("invalid number"));
+        let (left_id, right_id, cost) = (number(1)?, number(2)?, number(3)?);
+        if left_id < 0 || right_id < 0 {
+            return Err(error("negative context id"));
+        }
+        entries.push((
+            fields[0].to_string(),
+            Entry {
+                left_id: left_id as usize,
+                right_id: right_id as usize,
+                cost: cost as i32,
+                features: fields[4..].iter().map(|field| field.to_string()).collect(),
+            },
+        ));
+    }
+    Ok(entries)
+}
+
+/// 形態素（トークン）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub surface: &'a str,
+    /// 元テキスト上のバイト範囲の先頭
+    pub start: usize,
+    /// 辞書に載っていない語か
+    pub unknown: bool,
+    features: &'a [String],
+}
+
+impl<'a> Token<'a> {
+    pub fn end(&self) -> usize {
+        self.start + self.surface.len()
+    }
+
+    /// 辞書の素性（品詞,品詞細分類1,…,原形,読み,発音）
+    pub fn features(&self) -> &'a [String] {
+        self.features
+    }
+
+    /// 品詞と細分類（"*"を除く）
+    pub fn part_of_speech(&self) -> Vec<&'a str> {
+        self.features.iter().take(4).map(String::as_str).filter(|&f| f != "*").collect()
+    }
+
+    /// 原形（活用しない語や未知語は表層形）
+    pub fn base_form(&self) -> &'a str {
+        match self.features.get(6).map(String::as_str) {
+            Some(base) if base != "*" => base,
+            _ => self.surface,
+        }
+    }
+
+    /// 読み（カタカナ）。未知語など読みのない語はNone
+    pub fn reading(&self) -> Option<&'a str> {
+        self.features.get(7).map(String::as_str).filter(|&r| r != "*")
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Node<'d> {
+    start: usize,
+    end: usize,
+    entry: &'d Entry,
+    unknown: bool,
+    best: i64,
+    previous: Option<usize>,
+}
+
+/// ラティスとViterbiアルゴリズムによるトークナイザ
+pub struct Tokenizer {
+    dictionary: Dictionary,
+}
+
+impl Tokenizer {
+    pub fn new(dictionary: Dictionary) -> Tokenizer {
+        Tokenizer { dictionary }
+    }
+
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    /// テキストを形態素に分割する。空白は区切りとして扱い、トークンには含めない
+    pub fn tokenize<'a>(&'a self, text: &'a str) -> Vec<Token<'a>> {
+        let mut tokens = Vec::new();
+        let mut offset = 0;
+        for chunk in text.split(char::is_whitespace) {
+            if !chunk.is_empty() {
+                self.tokenize_chunk(chunk, offset, &mut tokens);
+            }
+            offset += chunk.len() + text[offset + chunk.len()..].chars().next().map_or(0, char::len_utf8);
+        }
+        tokens
+    }
+
+    fn tokenize_chunk<'a>(&'a self, text: &'a str, offset: usize, tokens: &mut Vec<Token<'a>>) {
+        let dictionary = &self.dictionary;
+        let mut nodes: Vec<Node> = Vec::new();
+        // ends_at[i]: バイト位置iで終わるノード
+        let mut ends_at: Vec<Vec<usize>> = vec![Vec::new(); text.len() + 1];
+
+        for (start, ch) in text.char_indices() {
+            if start > 0 && ends_at[start].is_empty() {
+                continue;
+            }
+            let mut candidates: Vec<(usize, &Entry, bool)> = Vec::new();
+
+            // 既知語: 先頭から辞書の最長の語の文字数までの接頭辞を引く
+            for (count, (i, c)) in text[start..].char_indices().enumerate() {
+                if count >= dictionary.max_chars {
+                    break;
+                }
+                let end = start + i + c.len_utf8();
+                if let Some(ids) = dictionary.index.get(&text[start..end]) {
+                    candidates.extend(ids.iter().map(|&id| (end, &dictionary.entries[id], false)));
+                }
+            }
+
+            // 未知語: 文字種ごとの規則で候補を作る
+            let category = dictionary.category_of(ch);
+            let rule = &dictionary.categories[category];
+            if rule.invoke || candidates.is_empty() {
+                let run: Vec<usize> = text[start..]
+                    .char_indices()
+                    .take_while(|&(_, c)| dictionary.is_compatible(c, category))
+                    .take(1024)
+                    .map(|(i, c)| start + i + c.len_utf8())
+                    .collect();
+                let mut ends = Vec::new();
+                if rule.group {
+                    ends.extend(run.last().cloned());
+                }
+                ends.extend(run.iter().take(rule.length).cloned());
+                if ends.is_empty() {
+                    ends.push(start + ch.len_utf8());
+                }
+                ends.sort_unstable();
+                ends.dedup();
+                // DEFAULTの未知語があることはfrom_partsで確かめてある
+                let unknown = if dictionary.unknown[category].is_empty() {
+                    &dictionary.unknown[dictionary.category_named("DEFAULT").unwrap_or(0)]
+                } else {
+                    &dictionary.unknown[category]
+                };
+                for &end in &ends {
+                    candidates.extend(unknown.iter().map(|entry| (end, entry, true)));
+                }
+            }
+
+            for (end, entry, unknown) in candidates {
+                let (best, previous) = if start == 0 {
+                    (dictionary.connection_cost(0, entry.left_id), None)
+                } else {
+                    ends_at[start]
+                        .iter()
+                        .map(|&p| (nodes[p].best + dictionary.connection_cost(nodes[p].entry.right_id, entry.left_id), Some(p)))
+                        .min_by_key(|&(cost, _)| cost)
+                        .expect("reachable position")
+                };
+                ends_at[end].push(nodes.len());
+                nodes.push(Node { start, end, entry, unknown, best: best + i64::from(entry.cost), previous });
+            }
+        }
+
+        // EOSへの連接コストを加えて最小の経路をたどる
+        let last = ends_at[text.len()]
+            .iter()
+            .cloned()
+            .min_by_key(|&n| nodes[n].best + dictionary.connection_cost(nodes[n].entry.right_id, 0));
+        let mut path = Vec::new();
+        let mut current = last;
+        while let Some(n) = current {
+            path.push(n);
+            current = nodes[n].previous;
+        }
+        tokens.extend(path.iter().rev().map(|&n| {
+            let node = &nodes[n];
+            Token {
+                surface: &text[node.start..node.end],
+                start: offset + node.start,
+                unknown: node.unknown,
+                features: &node.entry.features,
+            }
+        }));
+    }
+}
+
+#[derive(Debug)]
+pub enum DictionaryError {
+    Io(io::Error),
+    Format { file: &'static str, line: usize, message: String },
+}
+
+impl From<io::Error> for DictionaryError {
+    fn from(error: io::Error) -> DictionaryError {
+        DictionaryError::Io(error)
+    }
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DictionaryError::Io(ref error) => write!(dest, "{}", error),
+            DictionaryError::Format { file, line, ref message } => write!(dest, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl std::error::Error for DictionaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            DictionaryError::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,231 @@
+// 正規化した形で比べる文字列
+//
+// main.rsの例では"th\u{e9}"と"the\u{301}"は見た目が同じでも==にならず、ハッシュ値も違ったので、
+// 片方で登録したユーザーをもう片方で引けなかった。NormalizedString（所有）とNormalizedStr（借用）は
+// Hash・Eq・Ordを正規化した形（NFC、またはNFKCと大文字小文字の畳み込み）で行い、表示には元の文字列を使う。
+// NormalizedStringはBorrow<NormalizedStr>なので、HashMapやBTreeMapを&strから作ったNormalizedStrで引ける。
+
+use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use unicode_normalization::UnicodeNormalization;
+
+/// 比較に使う正規化
+pub trait Form {
+    /// 正規化済みならそのまま返す
+    fn normalize(text: &str) -> Cow<'_, str>;
+}
+
+/// NFC。合成済みの"é"と"e"+結合アクセントを同じとみなす
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Nfc;
+
+/// NFKCと大文字小文字の畳み込み。全角の"Ｒｕｓｔ"や合字の"ﬁ"も"rust"・"fi"と同じとみなす
+///
+/// 畳み込みはchar::to_lowercaseなので、Unicodeのcase foldingと違い"ß"は"ss"にならない。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NfkcCasefold;
+
+impl Form for Nfc {
+    fn normalize(text: &str) -> Cow<'_, str> {
+        if unicode_normalization::is_nfc(text) {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.nfc().collect())
+        }
+    }
+}
+
+impl Form for NfkcCasefold {
+    fn normalize(text: &str) -> Cow<'_, str> {
+        let lower = |ch: char| ch.to_lowercase().eq(std::iter::once(ch));
+        if unicode_normalization::is_nfkc(text) && text.chars().all(lower) {
+            return Cow::Borrowed(text);
+        }
+        // 小文字にすると正規形でなくなることがあるので、もう一度NFKCにする
+        let folded: String = text.nfkc().flat_map(char::to_lowercase).collect();
+        Cow::Owned(folded.nfkc().collect())
+    }
+}
+
+/// 正規化した形で比べる借用文字列。比べるたびに正規化する
+#[repr(transparent)]
+pub struct NormalizedStr<F = Nfc> {
+    form: PhantomData<F>,
+    text: str,
+}
+
+impl<F: Form> NormalizedStr<F> {
+    pub fn new(text: &str) -> &NormalizedStr<F> {
+        // SAFETY: repr(transparent)でstrと同じ表現なので、参照を読み替えられる
+        unsafe { &*(text as *const str as *const NormalizedStr<F>) }
+    }
+
+    /// 元の文字列
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// 比較に使う正規化した形
+    pub fn normalized(&self) -> Cow<'_, str> {
+        F::normalize(&self.text)
+    }
+}
+
+impl<F: Form> PartialEq for NormalizedStr<F> {
+    fn eq(&self, other: &NormalizedStr<F>) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl<F: Form> Eq for NormalizedStr<F> {}
+
+/// 正規化した形のコードポイント順
+impl<F: Form> Ord for NormalizedStr<F> {
+    fn cmp(&self, other: &NormalizedStr<F>) -> Ordering {
+        self.normalized().cmp(&other.normalized())
+    }
+}
+
+impl<F: Form> PartialOrd for NormalizedStr<F> {
+    fn partial_cmp(&self, other: &NormalizedStr<F>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 正規化した形のstrと同じハッシュ値
+impl<F: Form> Hash for NormalizedStr<F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl<F: Form> AsRef<str> for NormalizedStr<F> {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl<F: Form> fmt::Display for NormalizedStr<F> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        dest.pad(&self.text)
+    }
+}
+
+impl<F: Form> fmt::Debug for NormalizedStr<F> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.text, dest)
+    }
+}
+
+impl<F: Form> ToOwned for NormalizedStr<F> {
+    type Owned = NormalizedString<F>;
+
+    fn to_owned(&self) -> NormalizedString<F> {
+        NormalizedString::from(&self.text)
+    }
+}
+
+/// 正規化した形で比べる所有文字列。正規化した形を作ったときに保持しておく
+#[derive(Clone)]
+pub struct NormalizedString<F = Nfc> {
+    text: String,
+    // 元の文字列が正規化済みならNone
+    key: Option<String>,
+    form: PhantomData<F>,
+}
+
+impl<F: Form> NormalizedString<F> {
+    /// 元の文字列
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// 比較に使う正規化した形
+    pub fn normalized(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.text)
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+}
+
+impl<F: Form> From<String> for NormalizedString<F> {
+    fn from(text: String) -> NormalizedString<F> {
+        let key = match F::normalize(&text) {
+            Cow::Owned(key) => Some(key),
+            Cow::Borrowed(_) => None,
+        };
+        NormalizedString { text, key, form: PhantomData }
+    }
+}
+
+impl<'a, F: Form> From<&'a str> for NormalizedString<F> {
+    fn from(text: &'a str) -> NormalizedString<F> {
+        NormalizedString::from(text.to_string())
+    }
+}
+
+impl<F: Form> Deref for NormalizedString<F> {
+    type Target = NormalizedStr<F>;
+
+    fn deref(&self) -> &NormalizedStr<F> {
+        NormalizedStr::new(&self.text)
+    }
+}
+
+impl<F: Form> Borrow<NormalizedStr<F>> for NormalizedString<F> {
+    fn borrow(&self) -> &NormalizedStr<F> {
+        self
+    }
+}
+
+impl<F: Form> PartialEq for NormalizedString<F> {
+    fn eq(&self, other: &NormalizedString<F>) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl<F: Form> Eq for NormalizedString<F> {}
+
+impl<F: Form> Ord for NormalizedString<F> {
+    fn cmp(&self, other: &NormalizedString<F>) -> Ordering {
+        self.normalized().cmp(other.normalized())
+    }
+}
+
+impl<F: Form> PartialOrd for NormalizedString<F> {
+    fn partial_cmp(&self, other: &NormalizedString<F>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Borrowで引けるよう、NormalizedStrと同じく正規化した形のstrのハッシュ値
+impl<F: Form> Hash for NormalizedString<F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl<F: Form> AsRef<str> for NormalizedString<F> {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl<F: Form> fmt::Display for NormalizedString<F> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        dest.pad(&self.text)
+    }
+}
+
+impl<F: Form> fmt::Debug for NormalizedString<F> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.text, dest)
+    }
+}
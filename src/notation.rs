@@ -0,0 +1,215 @@
+// 工学表記と指数を固定した科学表記
+//
+// "{:.3e}"は仮数部が1以上10未満になるよう指数を選ぶ。Engineeringは指数を3の倍数（1.235e3, 12.35e-6）に、
+// FixedExponentは列の全ての値で同じ指数（0.012e3, 1.235e3）にする。
+// 指数はe3・E3・e+03・×10³の書き方を選べ、"{:+}"・幅・寄せ方・"{:012}"・"{:.3}"はFormatterの指定に従う。
+// "{:.3}"の丸めはstdと同じく、2進数の値を正確に10進に直して最も近い値（同じ近さなら偶数）にする。
+
+use std::fmt;
+
+use crate::align::{Align, Padded};
+use crate::float::{shortest_digits, Exact};
+
+/// 指数の書き方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExponentStyle {
+    /// "1.5e3", "1.5e-3"（"{:e}"と同じ）
+    #[default]
+    LowerE,
+    /// "1.5E3"（"{:E}"と同じ）
+    UpperE,
+    /// "1.5e+03"（Cのprintfと同じく符号と2桁以上）
+    Printf,
+    /// "1.5×10³"
+    TimesTen,
+}
+
+impl ExponentStyle {
+    fn write(self, exponent: i32) -> String {
+        match self {
+            ExponentStyle::LowerE => format!("e{}", exponent),
+            ExponentStyle::UpperE => format!("E{}", exponent),
+            ExponentStyle::Printf => format!("e{}{:02}", if exponent < 0 { '-' } else { '+' }, exponent.abs()),
+            ExponentStyle::TimesTen => {
+                const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+                let digits: String = exponent.abs().to_string().bytes().map(|b| SUPERSCRIPTS[(b - b'0') as usize]).collect();
+                format!("×10{}{}", if exponent < 0 { "⁻" } else { "" }, digits)
+            }
+        }
+    }
+}
+
+/// 指数を3の倍数にした表記。"{:.3}"は仮数部の小数点以下の桁数で、指定がなければ読み戻せる最短の桁にする
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Engineering {
+    value: f64,
+    style: ExponentStyle,
+}
+
+impl Engineering {
+    pub fn new(value: f64) -> Engineering {
+        Engineering { value, style: ExponentStyle::default() }
+    }
+
+    pub fn style(mut self, style: ExponentStyle) -> Engineering {
+        self.style = style;
+        self
+    }
+}
+
+impl fmt::Display for Engineering {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        if !self.value.is_finite() {
+            return write_number(dest, self.value, &self.value.abs().to_string());
+        }
+        let (digits, point) = decimal_digits(self.value, dest.precision());
+        // 先頭の桁の位が10^(point-1)
+        let mut exponent = if digits.is_empty() { 0 } else { (point - 1).div_euclid(3) * 3 };
+        let mut mantissa = Mantissa::new(&digits, point - exponent, dest.precision());
+        // 丸めで999.96が"1000.0"になったら指数を上げる
+        if mantissa.integer_len() > 3 {
+            exponent += 3;
+            mantissa = Mantissa::new(&digits, point - exponent, dest.precision());
+        }
+        let text = format!("{}{}", mantissa.text, self.style.write(exponent));
+        write_number(dest, self.value, &text)
+    }
+}
+
+/// 指数を固定した表記。"{:.3}"は仮数部の小数点以下の桁数で、指定がなければ読み戻せる最短の桁にする
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedExponent {
+    value: f64,
+    exponent: i32,
+    style: ExponentStyle,
+}
+
+impl FixedExponent {
+    pub fn new(value: f64, exponent: i32) -> FixedExponent {
+        FixedExponent { value, exponent, style: ExponentStyle::default() }
+    }
+
+    pub fn style(mut self, style: ExponentStyle) -> FixedExponent {
+        self.style = style;
+        self
+    }
+}
+
+impl fmt::Display for FixedExponent {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        if !self.value.is_finite() {
+            return write_number(dest, self.value, &self.value.abs().to_string());
+        }
+        let (digits, point) = decimal_digits(self.value, dest.precision());
+        let mantissa = Mantissa::new(&digits, point - self.exponent, dest.precision());
+        let text = format!("{}{}", mantissa.text, self.style.write(self.exponent));
+        write_number(dest, self.value, &text)
+    }
+}
+
+// 絶対値の10進の桁と小数点の位置（値は0.digits×10^point）。先頭に0はなく、0は空の桁になる
+// 精度の指定があれば丸めるために正確な展開を、なければ読み戻せる最短の桁を使う
+fn decimal_digits(value: f64, precision: Option<usize>) -> (String, i32) {
+    let value = value.abs();
+    if precision.is_none() {
+        return match shortest_digits(value) {
+            Some(ref shortest) if shortest.digits != "0" => (shortest.digits.clone(), shortest.exponent + 1),
+            _ => (String::new(), 0),
+        };
+    }
+    let exact = Exact(value).to_string();
+    let (integer, fraction) = exact.split_once('.').unwrap_or((&exact, ""));
+    let all = format!("{}{}", integer, fraction);
+    let significant = all.trim_start_matches('0');
+    let point = integer.len() as i32 - (all.len() - significant.len()) as i32;
+    (significant.trim_end_matches('0').to_string(), if significant.is_empty() { 0 } else { point })
+}
+
+// 0.digits×10^pointを小数点以下places桁（Noneなら桁を落とさない）で書いた仮数部
+struct Mantissa {
+    text: String,
+}
+
+impl Mantissa {
+    fn new(digits: &str, point: i32, places: Option<usize>) -> Mantissa {
+        let places = places.unwrap_or_else(|| (digits.len() as i32 - point).max(0) as usize);
+        // 整数部と小数部をつないだ桁のうち、残すのは先頭からpoint + places桁
+        let keep = point + places as i32;
+        let mut kept: Vec<u8> = (0..keep.max(0)).map(|i| *digits.as_bytes().get(i as usize).unwrap_or(&b'0')).collect();
+        let rest = if keep < 0 {
+            ""
+        } else {
+            digits.get(keep as usize..).unwrap_or("")
+        };
+        // 捨てる桁がちょうど半分なら偶数に丸める
+        let round_up = match rest.as_bytes().first() {
+            Some(&first) if first > b'5' => true,
+            Some(&b'5') => rest.len() > 1 || kept.last().is_some_and(|last| (last - b'0') % 2 == 1),
+            _ => false,
+        };
+        let mut point = point;
+        if round_up {
+            let mut i = kept.len();
+            loop {
+                if i == 0 {
+                    kept.insert(0, b'1');
+                    point += 1;
+                    break;
+                }
+                i -= 1;
+                if kept[i] == b'9' {
+                    kept[i] = b'0';
+                } else {
+                    kept[i] += 1;
+                    break;
+                }
+            }
+        }
+
+        let kept = String::from_utf8(kept).expect("ASCII digits");
+        let (integer, fraction) = if point <= 0 {
+            let zeros = ((-point) as usize).min(places);
+            let mut fraction = "0".repeat(zeros);
+            fraction.push_str(&kept);
+            ("0".to_string(), fraction)
+        } else {
+            let split = (point as usize).min(kept.len());
+            (kept[..split].to_string(), kept[split..].to_string())
+        };
+        let fraction: String = fraction.chars().chain(std::iter::repeat('0')).take(places).collect();
+        let text = if places == 0 {
+            integer
+        } else {
+            format!("{}.{}", integer, fraction)
+        };
+        Mantissa { text }
+    }
+
+    fn integer_len(&self) -> usize {
+        self.text.find('.').unwrap_or(self.text.len())
+    }
+}
+
+// 符号を付け、幅・寄せ方・'0'詰めを適用する。精度は仮数部の桁数に使ったので切り詰めには使わない
+fn write_number(dest: &mut fmt::Formatter, value: f64, body: &str) -> fmt::Result {
+    let sign = if value.is_sign_negative() && !value.is_nan() {
+        "-"
+    } else if dest.sign_plus() {
+        "+"
+    } else {
+        ""
+    };
+    let width = dest.width().unwrap_or(0);
+    let length = sign.chars().count() + body.chars().count();
+    if dest.sign_aware_zero_pad() && value.is_finite() {
+        let zeros = "0".repeat(width.saturating_sub(length));
+        return write!(dest, "{}{}{}", sign, zeros, body);
+    }
+    let align = match dest.align() {
+        Some(fmt::Alignment::Left) => Align::Left,
+        Some(fmt::Alignment::Center) => Align::Center,
+        _ => Align::Right,
+    };
+    let fill = dest.fill();
+    write!(dest, "{}", Padded::new(format!("{}{}", sign, body), width).align(align).fill(fill))
+}
@@ -0,0 +1,320 @@
+// ロケールに合わせた数値の書式
+//
+// "{:+012}"や"{:.2}"は桁区切りを入れない。NumberFormatは桁区切り（1,234,567 / 1.234.567 / インド式の
+// 12,34,567 / 万・億・兆の単位）と小数点の記号、小数点以下の桁数または有効桁数での丸め、
+// 通貨と百分率の表記を指定でき、format!の中ではDisplayのラッパーとして使う。
+// 丸めはstdの"{:.2}"と同じく、2進数の値を正確に10進に直したうえで最も近い値にする。
+
+use std::fmt;
+
+use crate::align::{Align, Padded};
+
+/// 整数部の桁区切り
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grouping {
+    None,
+    /// 3桁ごと（1,234,567）
+    Thousands,
+    /// 下3桁、以降2桁ごと（12,34,567）
+    Indian,
+    /// 4桁ごとに万・億・兆を付ける（1234万5678）。0000の位は省く
+    Myriad,
+}
+
+/// 丸め方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// 値を表すのに必要なだけ（"{}"と同じ）
+    Shortest,
+    /// 小数点以下の桁数（"{:.2}"と同じ）
+    Fixed(usize),
+    /// 有効桁数
+    Significant(usize),
+}
+
+/// 表記の種類
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Style {
+    Decimal,
+    /// 100倍して百分率の記号を付ける
+    Percent,
+    /// 数値の前に付ける通貨記号（"$1,234.50"）
+    CurrencyPrefix(String),
+    /// 数値の後ろに付ける通貨記号（"1.234,50 €"）
+    CurrencySuffix(String),
+}
+
+/// 書式化する数値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+macro_rules! number_from {
+    ($variant:ident, $target:ty: $($t:ty),*) => {
+        $(impl From<$t> for Number {
+            fn from(value: $t) -> Number {
+                Number::$variant(value as $target)
+            }
+        })*
+    };
+}
+
+number_from!(Int, i128: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+number_from!(Float, f64: f32, f64);
+
+/// 数値の書式
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberFormat {
+    grouping: Grouping,
+    group_separator: String,
+    decimal_separator: String,
+    precision: Precision,
+    style: Style,
+    percent_sign: String,
+    plus_sign: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat::new()
+    }
+}
+
+impl NumberFormat {
+    /// 3桁ごとに','、小数点は'.'（en-US）
+    pub fn new() -> NumberFormat {
+        NumberFormat {
+            grouping: Grouping::Thousands,
+            group_separator: ",".to_string(),
+            decimal_separator: ".".to_string(),
+            precision: Precision::Shortest,
+            style: Style::Decimal,
+            percent_sign: "%".to_string(),
+            plus_sign: false,
+        }
+    }
+
+    /// "en-US", "ja-JP", "de-DE", "fr-FR", "en-IN"の区切り記号。知らないロケールならNone
+    pub fn locale(tag: &str) -> Option<NumberFormat> {
+        let base = NumberFormat::new();
+        match tag {
+            "en-US" | "en-GB" | "ja-JP" | "zh-CN" => Some(base),
+            "de-DE" | "es-ES" | "it-IT" => {
+                Some(base.group_separator(".").decimal_separator(",").percent_sign("\u{a0}%"))
+            }
+            // 桁区切りは狭いノーブレークスペース
+            "fr-FR" => Some(base.group_separator("\u{202f}").decimal_separator(",").percent_sign("\u{a0}%")),
+            "en-IN" | "hi-IN" => Some(base.grouping(Grouping::Indian)),
+            _ => None,
+        }
+    }
+
+    pub fn grouping(mut self, grouping: Grouping) -> NumberFormat {
+        self.grouping = grouping;
+        self
+    }
+
+    pub fn group_separator(mut self, separator: &str) -> NumberFormat {
+        self.group_separator = separator.to_string();
+        self
+    }
+
+    pub fn decimal_separator(mut self, separator: &str) -> NumberFormat {
+        self.decimal_separator = separator.to_string();
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> NumberFormat {
+        self.precision = precision;
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> NumberFormat {
+        self.style = style;
+        self
+    }
+
+    /// 百分率の記号（ドイツ語やフランス語では"\u{a0}%"）
+    pub fn percent_sign(mut self, sign: &str) -> NumberFormat {
+        self.percent_sign = sign.to_string();
+        self
+    }
+
+    /// 正の数にも'+'を付ける（"{:+}"）
+    pub fn plus_sign(mut self, plus_sign: bool) -> NumberFormat {
+        self.plus_sign = plus_sign;
+        self
+    }
+
+    /// format!に渡せるラッパー。幅と寄せ方の指定（"{:>12}"）はそのまま効く
+    pub fn apply<N: Into<Number>>(&self, value: N) -> Formatted<'_> {
+        Formatted { format: self, value: value.into() }
+    }
+
+    pub fn format<N: Into<Number>>(&self, value: N) -> String {
+        self.apply(value).to_string()
+    }
+
+    // 符号と、10進の整数部・小数部
+    fn digits(&self, value: Number) -> (bool, String, String) {
+        let percent = self.style == Style::Percent;
+        let (negative, plain) = match value {
+            Number::Int(n) => {
+                let mut digits = n.unsigned_abs().to_string();
+                // 100倍は桁を2つ足して行う（i128::MAXでも桁あふれしない）
+                if percent && n != 0 {
+                    digits.push_str("00");
+                }
+                let plain = match self.precision {
+                    Precision::Shortest => digits,
+                    Precision::Fixed(0) => digits,
+                    Precision::Fixed(places) => format!("{}.{}", digits, "0".repeat(places)),
+                    Precision::Significant(count) => round_significant(&digits, count.max(1)),
+                };
+                (n < 0, plain)
+            }
+            Number::Float(f) => {
+                let f = if percent { f * 100.0 } else { f };
+                let plain = match self.precision {
+                    Precision::Shortest => format!("{}", f.abs()),
+                    Precision::Fixed(places) => format!("{:.*}", places, f.abs()),
+                    Precision::Significant(count) => expand_exponent(&format!("{:.*e}", count.max(1) - 1, f.abs())),
+                };
+                // -0.0や、丸めて0になった負の数に'-'は付けない
+                let zero = plain.bytes().all(|b| b == b'0' || b == b'.');
+                (f.is_sign_negative() && !zero && !f.is_nan(), plain)
+            }
+        };
+        match plain.find('.') {
+            Some(i) => (negative, plain[..i].to_string(), plain[i + 1..].to_string()),
+            None => (negative, plain, String::new()),
+        }
+    }
+
+    fn group(&self, integer: &str) -> String {
+        // inf・NaNは区切らない
+        if !integer.bytes().all(|b| b.is_ascii_digit()) {
+            return integer.to_string();
+        }
+        match self.grouping {
+            Grouping::None => integer.to_string(),
+            Grouping::Thousands => split_from_right(integer, 3, 3).join(&self.group_separator),
+            Grouping::Indian => split_from_right(integer, 3, 2).join(&self.group_separator),
+            Grouping::Myriad => myriad(integer),
+        }
+    }
+}
+
+// 右からfirst桁、以降rest桁ずつに分ける
+fn split_from_right(digits: &str, first: usize, rest: usize) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut end = digits.len();
+    let mut size = first;
+    while end > size {
+        groups.push(&digits[end - size..end]);
+        end -= size;
+        size = rest;
+    }
+    groups.push(&digits[..end]);
+    groups.reverse();
+    groups
+}
+
+const MYRIAD_UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+
+fn myriad(digits: &str) -> String {
+    let groups = split_from_right(digits, 4, 4);
+    if groups.len() > MYRIAD_UNITS.len() {
+        return digits.to_string();
+    }
+    let mut result = String::new();
+    for (i, group) in groups.iter().enumerate() {
+        let unit = MYRIAD_UNITS[groups.len() - 1 - i];
+        let trimmed = group.trim_start_matches('0');
+        if !trimmed.is_empty() {
+            // 先頭の位以外は4桁のまま（1億5万ではなく1億0005万）
+            result.push_str(if result.is_empty() { trimmed } else { group });
+            result.push_str(unit);
+        }
+    }
+    if result.is_empty() {
+        "0".to_string()
+    } else {
+        result
+    }
+}
+
+// "1.2345e3"のような指数表記を"1234.5"に直す
+fn expand_exponent(text: &str) -> String {
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().unwrap_or(0)),
+        None => return text.to_string(),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer, fraction);
+    let point = integer.len() as i32 + exponent;
+    if point <= 0 {
+        format!("0.{}{}", "0".repeat((-point) as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+    }
+}
+
+// 整数の10進表記を有効桁数で四捨五入する
+fn round_significant(digits: &str, count: usize) -> String {
+    if digits.len() <= count {
+        return digits.to_string();
+    }
+    let kept: u128 = digits[..count].parse().unwrap_or(0);
+    let round_up = digits.as_bytes()[count] >= b'5';
+    let kept = (kept + round_up as u128).to_string();
+    // 繰り上がりで桁が増えた場合（999→1000）も元の桁数に揃える
+    let zeros = digits.len() - count;
+    format!("{}{}", kept, "0".repeat(zeros))
+}
+
+/// NumberFormat::apply()が返すDisplayのラッパー
+///
+/// 幅・詰め物・寄せ方（既定は右寄せ）はFormatterの指定に従う。桁数はNumberFormatのprecisionで決まり、
+/// "{:.2}"で切り詰めることはない。
+#[derive(Clone, Copy, Debug)]
+pub struct Formatted<'a> {
+    format: &'a NumberFormat,
+    value: Number,
+}
+
+impl<'a> fmt::Display for Formatted<'a> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        let format = self.format;
+        let (negative, integer, fraction) = format.digits(self.value);
+        let mut number = format.group(&integer);
+        if !fraction.is_empty() {
+            number.push_str(&format.decimal_separator);
+            number.push_str(&fraction);
+        }
+        let sign = if negative {
+            "-"
+        } else if format.plus_sign {
+            "+"
+        } else {
+            ""
+        };
+        let text = match format.style {
+            Style::Decimal => format!("{}{}", sign, number),
+            Style::Percent => format!("{}{}{}", sign, number, format.percent_sign),
+            Style::CurrencyPrefix(ref symbol) => format!("{}{}{}", sign, symbol, number),
+            Style::CurrencySuffix(ref symbol) => format!("{}{}{}", sign, number, symbol),
+        };
+        let align = match dest.align() {
+            Some(fmt::Alignment::Left) => Align::Left,
+            Some(fmt::Alignment::Center) => Align::Center,
+            _ => Align::Right,
+        };
+        let (width, fill) = (dest.width().unwrap_or(0), dest.fill());
+        write!(dest, "{}", Padded::new(text, width).align(align).fill(fill))
+    }
+}
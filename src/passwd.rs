@@ -0,0 +1,299 @@
+// /etc/passwd, /etc/group, /etc/shadow形式のコロン区切りファイル
+//
+// "jimb:1000:Jim Blandy:".split(':')と同様に末尾の空フィールドも1フィールドとして数える。
+// 変更していないレコードは元の行をそのまま書き戻すため、読み込んで書き出すだけならファイルは変わらない。
+
+use std::fmt;
+use std::str::FromStr;
+
+/// コロン区切りの1レコード
+pub trait ColonRecord: Sized + PartialEq {
+    /// 1行のフィールド数
+    const FIELDS: usize;
+
+    fn from_fields(fields: &[&str]) -> Result<Self, ParseErrorKind>;
+
+    fn to_fields(&self) -> Vec<String>;
+}
+
+fn parse_record<T: ColonRecord>(line: &str) -> Result<T, ParseErrorKind> {
+    let fields: Vec<&str> = line.split(':').collect();
+    if fields.len() != T::FIELDS {
+        return Err(ParseErrorKind::FieldCount { expected: T::FIELDS, found: fields.len() });
+    }
+    T::from_fields(&fields)
+}
+
+fn write_record<T: ColonRecord>(record: &T, dest: &mut fmt::Formatter) -> fmt::Result {
+    write!(dest, "{}", record.to_fields().join(":"))
+}
+
+fn number<N: FromStr>(field: &'static str, value: &str) -> Result<N, ParseErrorKind> {
+    value.parse().map_err(|_| ParseErrorKind::InvalidNumber { field, value: value.to_string() })
+}
+
+// /etc/shadowの日数フィールドは空欄を許す
+fn optional_number<N: FromStr>(field: &'static str, value: &str) -> Result<Option<N>, ParseErrorKind> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        number(field, value).map(Some)
+    }
+}
+
+fn optional_to_string<N: ToString>(value: &Option<N>) -> String {
+    value.as_ref().map_or_else(String::new, N::to_string)
+}
+
+/// /etc/passwdの1行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Passwd {
+    pub name: String,
+    pub password: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub gecos: String,
+    pub home: String,
+    pub shell: String,
+}
+
+impl ColonRecord for Passwd {
+    const FIELDS: usize = 7;
+
+    fn from_fields(fields: &[&str]) -> Result<Passwd, ParseErrorKind> {
+        Ok(Passwd {
+            name: fields[0].to_string(),
+            password: fields[1].to_string(),
+            uid: number("uid", fields[2])?,
+            gid: number("gid", fields[3])?,
+            gecos: fields[4].to_string(),
+            home: fields[5].to_string(),
+            shell: fields[6].to_string(),
+        })
+    }
+
+    fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.password.clone(),
+            self.uid.to_string(),
+            self.gid.to_string(),
+            self.gecos.clone(),
+            self.home.clone(),
+            self.shell.clone(),
+        ]
+    }
+}
+
+/// /etc/groupの1行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub password: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+impl ColonRecord for Group {
+    const FIELDS: usize = 4;
+
+    fn from_fields(fields: &[&str]) -> Result<Group, ParseErrorKind> {
+        Ok(Group {
+            name: fields[0].to_string(),
+            password: fields[1].to_string(),
+            gid: number("gid", fields[2])?,
+            members: if fields[3].is_empty() {
+                Vec::new()
+            } else {
+                fields[3].split(',').map(String::from).collect()
+            },
+        })
+    }
+
+    fn to_fields(&self) -> Vec<String> {
+        vec![self.name.clone(), self.password.clone(), self.gid.to_string(), self.members.join(",")]
+    }
+}
+
+/// /etc/shadowの1行（日数は1970-01-01からの日数）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shadow {
+    pub name: String,
+    pub password: String,
+    pub last_change: Option<u32>,
+    pub min_age: Option<u32>,
+    pub max_age: Option<u32>,
+    pub warn_period: Option<u32>,
+    pub inactive_period: Option<u32>,
+    pub expire: Option<u32>,
+    pub reserved: String,
+}
+
+impl ColonRecord for Shadow {
+    const FIELDS: usize = 9;
+
+    fn from_fields(fields: &[&str]) -> Result<Shadow, ParseErrorKind> {
+        Ok(Shadow {
+            name: fields[0].to_string(),
+            password: fields[1].to_string(),
+            last_change: optional_number("last_change", fields[2])?,
+            min_age: optional_number("min_age", fields[3])?,
+            max_age: optional_number("max_age", fields[4])?,
+            warn_period: optional_number("warn_period", fields[5])?,
+            inactive_period: optional_number("inactive_period", fields[6])?,
+            expire: optional_number("expire", fields[7])?,
+            reserved: fields[8].to_string(),
+        })
+    }
+
+    fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.password.clone(),
+            optional_to_string(&self.last_change),
+            optional_to_string(&self.min_age),
+            optional_to_string(&self.max_age),
+            optional_to_string(&self.warn_period),
+            optional_to_string(&self.inactive_period),
+            optional_to_string(&self.expire),
+            self.reserved.clone(),
+        ]
+    }
+}
+
+macro_rules! impl_text_conversions {
+    ($($record:ty),*) => {
+        $(
+            impl FromStr for $record {
+                type Err = ParseError;
+
+                fn from_str(line: &str) -> Result<$record, ParseError> {
+                    parse_record(line).map_err(|kind| ParseError { line: 1, kind })
+                }
+            }
+
+            impl fmt::Display for $record {
+                fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+                    write_record(self, dest)
+                }
+            }
+        )*
+    }
+}
+
+impl_text_conversions!(Passwd, Group, Shadow);
+
+#[derive(Clone, Debug)]
+enum Line<T> {
+    Record { record: T, original: String },
+    // 空行と'#'で始まるコメント行
+    Other(String),
+}
+
+/// コロン区切りファイル全体。空行やコメント行も位置を保って保持する
+#[derive(Clone, Debug)]
+pub struct ColonFile<T> {
+    lines: Vec<Line<T>>,
+    trailing_newline: bool,
+}
+
+pub type PasswdFile = ColonFile<Passwd>;
+pub type GroupFile = ColonFile<Group>;
+pub type ShadowFile = ColonFile<Shadow>;
+
+impl<T: ColonRecord> ColonFile<T> {
+    pub fn parse(text: &str) -> Result<ColonFile<T>, ParseError> {
+        let mut lines = Vec::new();
+        for (index, line) in text.split_terminator('\n').enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                lines.push(Line::Other(line.to_string()));
+                continue;
+            }
+            let record = parse_record(line).map_err(|kind| ParseError { line: index + 1, kind })?;
+            lines.push(Line::Record { record, original: line.to_string() });
+        }
+        Ok(ColonFile { lines, trailing_newline: text.is_empty() || text.ends_with('\n') })
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &T> {
+        self.lines.iter().filter_map(|line| match *line {
+            Line::Record { ref record, .. } => Some(record),
+            Line::Other(_) => None,
+        })
+    }
+
+    pub fn records_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.lines.iter_mut().filter_map(|line| match *line {
+            Line::Record { ref mut record, .. } => Some(record),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// 末尾にレコードを追加する
+    pub fn push(&mut self, record: T) {
+        self.lines.push(Line::Record { record, original: String::new() });
+    }
+
+    /// 条件に合うレコードを削除し、削除した数を返す
+    pub fn remove_where<F: FnMut(&T) -> bool>(&mut self, mut condition: F) -> usize {
+        let before = self.lines.len();
+        self.lines.retain(|line| match *line {
+            Line::Record { ref record, .. } => !condition(record),
+            Line::Other(_) => true,
+        });
+        before - self.lines.len()
+    }
+}
+
+impl<T: ColonRecord> fmt::Display for ColonFile<T> {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                writeln!(dest)?;
+            }
+            match *line {
+                // 元の行を解析し直して同じ値なら、変更されていないので元の行をそのまま書く
+                Line::Record { ref record, ref original } => {
+                    if parse_record::<T>(original).as_ref() == Ok(record) {
+                        dest.write_str(original)?;
+                    } else {
+                        write_record(record, dest)?;
+                    }
+                }
+                Line::Other(ref text) => dest.write_str(text)?,
+            }
+        }
+        if self.trailing_newline && !self.lines.is_empty() {
+            writeln!(dest)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    FieldCount { expected: usize, found: usize },
+    InvalidNumber { field: &'static str, value: String },
+}
+
+/// 行番号（1始まり）付きの解析エラー
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::FieldCount { expected, found } => {
+                write!(dest, "line {}: expected {} fields, found {}", self.line, expected, found)
+            }
+            ParseErrorKind::InvalidNumber { field, ref value } => {
+                write!(dest, "line {}: invalid {} {:?}", self.line, field, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,671 @@
+// 再利用できるパターンオブジェクト
+//
+// std::str::Patternトレイトは安定化されておらず独自型を実装できないため、
+// str::find()等にはCharSet::as_fn()のクロージャを渡すか、PatternExtの*_by()メソッドを使う
+
+use std::collections::HashMap;
+
+/// 文字列中の一致箇所を探すパターン（オフセットはすべてバイト単位）
+pub trait TextPattern {
+    /// `haystack[from..]`で最初に一致した範囲を`(start, end)`で返す
+    fn find_in(&self, haystack: &str, from: usize) -> Option<(usize, usize)>;
+
+    /// 先頭で一致した場合、その長さを返す
+    fn match_prefix(&self, haystack: &str) -> Option<usize>;
+
+    /// 末尾で一致した場合、その長さを返す
+    fn match_suffix(&self, haystack: &str) -> Option<usize>;
+}
+
+/// 1文字ずつ判定するパターン
+pub trait CharPattern {
+    fn matches_char(&self, ch: char) -> bool;
+}
+
+impl CharPattern for char {
+    fn matches_char(&self, ch: char) -> bool {
+        *self == ch
+    }
+}
+
+impl<F: Fn(char) -> bool> CharPattern for F {
+    fn matches_char(&self, ch: char) -> bool {
+        self(ch)
+    }
+}
+
+impl<P: CharPattern> TextPattern for P {
+    fn find_in(&self, haystack: &str, from: usize) -> Option<(usize, usize)> {
+        haystack[from..]
+            .char_indices()
+            .find(|&(_, ch)| self.matches_char(ch))
+            .map(|(i, ch)| (from + i, from + i + ch.len_utf8()))
+    }
+
+    fn match_prefix(&self, haystack: &str) -> Option<usize> {
+        haystack.chars().next()
+            .filter(|&ch| self.matches_char(ch))
+            .map(char::len_utf8)
+    }
+
+    fn match_suffix(&self, haystack: &str) -> Option<usize> {
+        haystack.chars().next_back()
+            .filter(|&ch| self.matches_char(ch))
+            .map(char::len_utf8)
+    }
+}
+
+/// 事前計算済みの文字集合
+///
+/// U+0000〜U+00FFはビットマップ、それ以外はソート・結合済みの範囲テーブルを二分探索する。
+/// `&[' ', '\t'] as &[char]`のような線形探索と違い、要素数に関係なく判定できる。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CharSet {
+    latin1: [u64; 4],
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl CharSet {
+    pub fn new() -> CharSet {
+        CharSet::default()
+    }
+
+    /// char::is_whitespaceと同じ文字の集合
+    pub fn whitespace() -> CharSet {
+        let mut set = CharSet::new();
+        for &(lo, hi) in WHITE_SPACE {
+            set.insert_range(lo, hi);
+        }
+        set
+    }
+
+    pub fn from_chars(chars: &[char]) -> CharSet {
+        chars.iter().cloned().collect()
+    }
+
+    pub fn with_range(mut self, lo: char, hi: char) -> CharSet {
+        self.insert_range(lo, hi);
+        self
+    }
+
+    pub fn insert(&mut self, ch: char) {
+        self.insert_range(ch, ch);
+    }
+
+    /// 両端を含む範囲`lo..=hi`を追加する
+    pub fn insert_range(&mut self, lo: char, hi: char) {
+        if lo > hi {
+            return;
+        }
+        for code in (lo as u32)..=(hi as u32).min(0xff) {
+            self.latin1[(code / 64) as usize] |= 1 << (code % 64);
+        }
+        if (hi as u32) <= 0xff {
+            return;
+        }
+        let lo = if (lo as u32) <= 0xff { '\u{100}' } else { lo };
+        self.ranges.push((lo, hi));
+        self.normalize_ranges();
+    }
+
+    /// 他の集合の要素をすべて追加した集合を返す。否定された集合は補集合を展開してから合わせる
+    pub fn union(self, other: &CharSet) -> CharSet {
+        let mut set = self.expanded();
+        let other = other.clone().expanded();
+        for (dst, src) in set.latin1.iter_mut().zip(other.latin1.iter()) {
+            *dst |= *src;
+        }
+        set.ranges.extend_from_slice(&other.ranges);
+        set.normalize_ranges();
+        set
+    }
+
+    // 否定されていれば、同じ文字を含む否定されていない集合に書き直す
+    fn expanded(self) -> CharSet {
+        if !self.negated {
+            return self;
+        }
+        let mut ranges = Vec::new();
+        let mut next = Some('\u{100}');
+        for &(lo, hi) in &self.ranges {
+            if let Some(start) = next.filter(|&start| start < lo) {
+                ranges.push((start, step(lo, -1)));
+            }
+            next = if hi == char::MAX { None } else { Some(step(hi, 1)) };
+        }
+        if let Some(start) = next {
+            ranges.push((start, char::MAX));
+        }
+        CharSet { latin1: self.latin1.map(|bits| !bits), ranges, negated: false }
+    }
+
+    /// 補集合を返す
+    pub fn negated(mut self) -> CharSet {
+        self.negated = !self.negated;
+        self
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        let code = ch as u32;
+        let found = if code <= 0xff {
+            self.latin1[(code / 64) as usize] & (1 << (code % 64)) != 0
+        } else {
+            in_table(&self.ranges, ch)
+        };
+        found != self.negated
+    }
+
+    /// std::strのfind()やtrim_start_matches()にそのまま渡せるクロージャ
+    pub fn as_fn(&self) -> impl Fn(char) -> bool + '_ {
+        move |ch| self.contains(ch)
+    }
+
+    fn normalize_ranges(&mut self) {
+        self.ranges.sort();
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+        for &(lo, hi) in &self.ranges {
+            match merged.last_mut() {
+                Some(last) if lo as u32 <= last.1 as u32 + 1 => {
+                    if hi > last.1 {
+                        last.1 = hi;
+                    }
+                }
+                _ => merged.push((lo, hi)),
+            }
+        }
+        self.ranges = merged;
+    }
+}
+
+impl CharPattern for CharSet {
+    fn matches_char(&self, ch: char) -> bool {
+        self.contains(ch)
+    }
+}
+
+impl std::iter::FromIterator<char> for CharSet {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> CharSet {
+        let mut set = CharSet::new();
+        for ch in iter {
+            set.insert(ch);
+        }
+        set
+    }
+}
+
+impl<'a> From<&'a str> for CharSet {
+    fn from(chars: &'a str) -> CharSet {
+        chars.chars().collect()
+    }
+}
+
+// サロゲートを飛ばして前後の文字へ進む
+fn step(ch: char, delta: i32) -> char {
+    let mut code = (ch as u32).wrapping_add_signed(delta);
+    if (0xd800..=0xdfff).contains(&code) {
+        code = if delta > 0 { 0xe000 } else { 0xd7ff };
+    }
+    char::from_u32(code).expect("neighbor of a char in range")
+}
+
+pub(crate) fn in_table(table: &[(char, char)], ch: char) -> bool {
+    table
+        .binary_search_by(|&(lo, hi)| {
+            if hi < ch {
+                std::cmp::Ordering::Less
+            } else if lo > ch {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Unicodeの用字（Script）
+///
+/// 主要なブロックのみを収録しており、Scripts.txtの全範囲ではない。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Thai,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+}
+
+impl Script {
+    pub const ALL: [Script; 10] = [
+        Script::Latin,
+        Script::Greek,
+        Script::Cyrillic,
+        Script::Hebrew,
+        Script::Arabic,
+        Script::Thai,
+        Script::Hangul,
+        Script::Hiragana,
+        Script::Katakana,
+        Script::Han,
+    ];
+
+    /// 文字の用字を返す。収録していない用字や記号類はNone
+    pub fn of(ch: char) -> Option<Script> {
+        Script::ALL.iter().cloned().find(|script| script.contains(ch))
+    }
+
+    pub fn contains(self, ch: char) -> bool {
+        in_table(self.table(), ch)
+    }
+
+    fn table(self) -> &'static [(char, char)] {
+        match self {
+            Script::Latin => LATIN,
+            Script::Greek => GREEK,
+            Script::Cyrillic => CYRILLIC,
+            Script::Hebrew => HEBREW,
+            Script::Arabic => ARABIC,
+            Script::Thai => THAI,
+            Script::Hangul => HANGUL,
+            Script::Hiragana => HIRAGANA,
+            Script::Katakana => KATAKANA,
+            Script::Han => HAN,
+        }
+    }
+}
+
+impl CharPattern for Script {
+    fn matches_char(&self, ch: char) -> bool {
+        self.contains(ch)
+    }
+}
+
+/// Unicodeの一般カテゴリ（General_Category）の大分類
+///
+/// 記号・句読点・結合文字は主要なブロックのみ表で持ち、
+/// 文字・数字・制御文字はcharの分類メソッドで判定する。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GeneralCategory {
+    Letter,
+    Mark,
+    Number,
+    Punctuation,
+    Symbol,
+    Separator,
+    Other,
+}
+
+impl GeneralCategory {
+    pub fn of(ch: char) -> GeneralCategory {
+        if in_table(SEPARATOR, ch) {
+            GeneralCategory::Separator
+        } else if ch.is_control() {
+            GeneralCategory::Other
+        } else if in_table(PUNCTUATION, ch) {
+            GeneralCategory::Punctuation
+        } else if in_table(SYMBOL, ch) {
+            GeneralCategory::Symbol
+        } else if in_table(MARK, ch) {
+            GeneralCategory::Mark
+        } else if ch.is_numeric() {
+            GeneralCategory::Number
+        } else if ch.is_alphabetic() {
+            GeneralCategory::Letter
+        } else {
+            GeneralCategory::Other
+        }
+    }
+}
+
+impl CharPattern for GeneralCategory {
+    fn matches_char(&self, ch: char) -> bool {
+        GeneralCategory::of(ch) == *self
+    }
+}
+
+/// 文字列リテラルの集合
+///
+/// 複数のリテラルが同じ位置で一致する場合は最長のものを採用する（leftmost-longest）。
+#[derive(Clone, Debug, Default)]
+pub struct LiteralSet {
+    literals: Vec<String>,
+    // 先頭文字ごとに、長い順に並べたリテラルの添字
+    by_first: HashMap<char, Vec<usize>>,
+    by_last: HashMap<char, Vec<usize>>,
+}
+
+impl LiteralSet {
+    pub fn new<I, S>(literals: I) -> LiteralSet
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut set = LiteralSet::default();
+        for literal in literals {
+            set.insert(literal);
+        }
+        set
+    }
+
+    /// リテラルを追加し、その添字を返す。空文字列は何にも一致しないため無視してNoneを返す
+    pub fn insert<S: Into<String>>(&mut self, literal: S) -> Option<usize> {
+        let literal = literal.into();
+        let (first, last) = match (literal.chars().next(), literal.chars().next_back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return None,
+        };
+        if let Some(index) = self.literals.iter().position(|l| *l == literal) {
+            return Some(index);
+        }
+        let index = self.literals.len();
+        self.literals.push(literal);
+        for (key, map) in [(first, &mut self.by_first), (last, &mut self.by_last)].iter_mut() {
+            let literals = &self.literals;
+            let bucket = map.entry(*key).or_default();
+            bucket.push(index);
+            bucket.sort_by_key(|&i| std::cmp::Reverse(literals[i].len()));
+        }
+        Some(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.literals.get(index).map(String::as_str)
+    }
+
+    /// `haystack[at..]`の先頭で一致する最長のリテラルの添字を返す
+    pub fn longest_at(&self, haystack: &str, at: usize) -> Option<usize> {
+        self.matches_at(haystack, at).next()
+    }
+
+    /// `haystack[at..]`の先頭で一致するすべてのリテラルの添字を、長い順に返す
+    pub fn matches_at<'a>(&'a self, haystack: &'a str, at: usize) -> impl Iterator<Item = usize> + 'a {
+        let rest = &haystack[at..];
+        rest.chars().next()
+            .and_then(|first| self.by_first.get(&first))
+            .into_iter()
+            .flatten()
+            .cloned()
+            .filter(move |&i| rest.starts_with(self.literals[i].as_str()))
+    }
+
+    /// `haystack[from..]`で最初に見つかった一致を`(start, end, 添字)`で返す
+    pub fn find_literal(&self, haystack: &str, from: usize) -> Option<(usize, usize, usize)> {
+        haystack[from..].char_indices().find_map(|(i, _)| {
+            self.longest_at(haystack, from + i)
+                .map(|index| (from + i, from + i + self.literals[index].len(), index))
+        })
+    }
+}
+
+impl TextPattern for LiteralSet {
+    fn find_in(&self, haystack: &str, from: usize) -> Option<(usize, usize)> {
+        self.find_literal(haystack, from).map(|(start, end, _)| (start, end))
+    }
+
+    fn match_prefix(&self, haystack: &str) -> Option<usize> {
+        self.longest_at(haystack, 0).map(|i| self.literals[i].len())
+    }
+
+    fn match_suffix(&self, haystack: &str) -> Option<usize> {
+        let last = haystack.chars().next_back()?;
+        self.by_last.get(&last)?
+            .iter()
+            .map(|&i| &self.literals[i])
+            .find(|literal| haystack.ends_with(literal.as_str()))
+            .map(String::len)
+    }
+}
+
+/// TextPatternを受け取るstrの検索・分割・トリム・置換
+pub trait PatternExt {
+    fn find_by<P: TextPattern>(&self, pattern: &P) -> Option<usize>;
+    fn split_by<'a, P: TextPattern>(&'a self, pattern: &'a P) -> SplitBy<'a, P>;
+    fn trim_matches_by<P: TextPattern>(&self, pattern: &P) -> &str;
+    fn trim_start_matches_by<P: TextPattern>(&self, pattern: &P) -> &str;
+    fn trim_end_matches_by<P: TextPattern>(&self, pattern: &P) -> &str;
+    fn replace_by<P: TextPattern>(&self, pattern: &P, to: &str) -> String;
+}
+
+impl PatternExt for str {
+    fn find_by<P: TextPattern>(&self, pattern: &P) -> Option<usize> {
+        pattern.find_in(self, 0).map(|(start, _)| start)
+    }
+
+    fn split_by<'a, P: TextPattern>(&'a self, pattern: &'a P) -> SplitBy<'a, P> {
+        SplitBy { haystack: self, pattern, position: 0, finished: false }
+    }
+
+    fn trim_matches_by<P: TextPattern>(&self, pattern: &P) -> &str {
+        self.trim_start_matches_by(pattern).trim_end_matches_by(pattern)
+    }
+
+    fn trim_start_matches_by<P: TextPattern>(&self, pattern: &P) -> &str {
+        let mut rest = self;
+        while let Some(len) = pattern.match_prefix(rest).filter(|&len| len > 0) {
+            rest = &rest[len..];
+        }
+        rest
+    }
+
+    fn trim_end_matches_by<P: TextPattern>(&self, pattern: &P) -> &str {
+        let mut rest = self;
+        while let Some(len) = pattern.match_suffix(rest).filter(|&len| len > 0) {
+            rest = &rest[..rest.len() - len];
+        }
+        rest
+    }
+
+    fn replace_by<P: TextPattern>(&self, pattern: &P, to: &str) -> String {
+        let mut result = String::with_capacity(self.len());
+        let mut last = 0;
+        while let Some((start, end)) = pattern.find_in(self, last) {
+            result.push_str(&self[last..start]);
+            result.push_str(to);
+            // 空一致で無限ループしないよう、最低1文字は進める
+            if end == start {
+                match self[end..].chars().next() {
+                    Some(ch) => {
+                        result.push(ch);
+                        last = end + ch.len_utf8();
+                    }
+                    None => {
+                        last = end;
+                        break;
+                    }
+                }
+            } else {
+                last = end;
+            }
+        }
+        result.push_str(&self[last..]);
+        result
+    }
+}
+
+/// PatternExt::split_by()が返すイテレータ
+pub struct SplitBy<'a, P> {
+    haystack: &'a str,
+    pattern: &'a P,
+    position: usize,
+    finished: bool,
+}
+
+impl<'a, P: TextPattern> Iterator for SplitBy<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.finished {
+            return None;
+        }
+        match self.pattern.find_in(self.haystack, self.position) {
+            Some((start, end)) if end > start => {
+                let piece = &self.haystack[self.position..start];
+                self.position = end;
+                Some(piece)
+            }
+            _ => {
+                self.finished = true;
+                Some(&self.haystack[self.position..])
+            }
+        }
+    }
+}
+
+const WHITE_SPACE: &[(char, char)] = &[
+    ('\u{9}', '\u{d}'), (' ', ' '), ('\u{85}', '\u{85}'), ('\u{a0}', '\u{a0}'),
+    ('\u{1680}', '\u{1680}'), ('\u{2000}', '\u{200a}'), ('\u{2028}', '\u{2029}'),
+    ('\u{202f}', '\u{202f}'), ('\u{205f}', '\u{205f}'), ('\u{3000}', '\u{3000}'),
+];
+
+const SEPARATOR: &[(char, char)] = &[
+    (' ', ' '), ('\u{a0}', '\u{a0}'), ('\u{1680}', '\u{1680}'), ('\u{2000}', '\u{200a}'),
+    ('\u{2028}', '\u{2029}'), ('\u{202f}', '\u{202f}'), ('\u{205f}', '\u{205f}'),
+    ('\u{3000}', '\u{3000}'),
+];
+
+const PUNCTUATION: &[(char, char)] = &[
+    ('!', '#'), ('%', '*'), (',', '/'), (':', ';'), ('?', '@'), ('[', ']'), ('_', '_'),
+    ('{', '{'), ('}', '}'), ('\u{a1}', '\u{a1}'), ('\u{a7}', '\u{a7}'), ('\u{ab}', '\u{ab}'),
+    ('\u{b6}', '\u{b7}'), ('\u{bb}', '\u{bb}'), ('\u{bf}', '\u{bf}'), ('\u{37e}', '\u{37e}'),
+    ('\u{387}', '\u{387}'), ('\u{55a}', '\u{55f}'), ('\u{589}', '\u{58a}'),
+    ('\u{5be}', '\u{5be}'), ('\u{5c0}', '\u{5c0}'), ('\u{5c3}', '\u{5c3}'),
+    ('\u{5c6}', '\u{5c6}'), ('\u{5f3}', '\u{5f4}'), ('\u{609}', '\u{60a}'),
+    ('\u{60c}', '\u{60d}'), ('\u{61b}', '\u{61b}'), ('\u{61d}', '\u{61f}'),
+    ('\u{66a}', '\u{66d}'), ('\u{6d4}', '\u{6d4}'), ('\u{964}', '\u{965}'),
+    ('\u{e4f}', '\u{e4f}'), ('\u{e5a}', '\u{e5b}'), ('\u{2010}', '\u{2027}'),
+    ('\u{2030}', '\u{2043}'), ('\u{2045}', '\u{2051}'), ('\u{2053}', '\u{205e}'),
+    ('\u{207d}', '\u{207e}'), ('\u{208d}', '\u{208e}'), ('\u{2308}', '\u{230b}'),
+    ('\u{2329}', '\u{232a}'), ('\u{2768}', '\u{2775}'), ('\u{27c5}', '\u{27c6}'),
+    ('\u{27e6}', '\u{27ef}'), ('\u{2983}', '\u{2998}'), ('\u{29d8}', '\u{29db}'),
+    ('\u{29fc}', '\u{29fd}'), ('\u{2e00}', '\u{2e2e}'), ('\u{2e30}', '\u{2e4f}'),
+    ('\u{3001}', '\u{3003}'), ('\u{3008}', '\u{3011}'), ('\u{3014}', '\u{301f}'),
+    ('\u{3030}', '\u{3030}'), ('\u{303d}', '\u{303d}'), ('\u{30a0}', '\u{30a0}'),
+    ('\u{30fb}', '\u{30fb}'), ('\u{fe10}', '\u{fe19}'), ('\u{fe30}', '\u{fe52}'),
+    ('\u{fe54}', '\u{fe61}'), ('\u{fe63}', '\u{fe63}'), ('\u{fe68}', '\u{fe68}'),
+    ('\u{fe6a}', '\u{fe6b}'), ('\u{ff01}', '\u{ff03}'), ('\u{ff05}', '\u{ff0a}'),
+    ('\u{ff0c}', '\u{ff0f}'), ('\u{ff1a}', '\u{ff1b}'), ('\u{ff1f}', '\u{ff20}'),
+    ('\u{ff3b}', '\u{ff3d}'), ('\u{ff3f}', '\u{ff3f}'), ('\u{ff5b}', '\u{ff5b}'),
+    ('\u{ff5d}', '\u{ff5d}'), ('\u{ff5f}', '\u{ff65}'),
+];
+
+const SYMBOL: &[(char, char)] = &[
+    ('$', '$'), ('+', '+'), ('<', '>'), ('^', '^'), ('`', '`'), ('|', '|'), ('~', '~'),
+    ('\u{a2}', '\u{a6}'), ('\u{a8}', '\u{a9}'), ('\u{ac}', '\u{ac}'), ('\u{ae}', '\u{b1}'),
+    ('\u{b4}', '\u{b4}'), ('\u{b8}', '\u{b8}'), ('\u{d7}', '\u{d7}'), ('\u{f7}', '\u{f7}'),
+    ('\u{2c2}', '\u{2c5}'), ('\u{2d2}', '\u{2df}'), ('\u{2e5}', '\u{2eb}'),
+    ('\u{2ed}', '\u{2ed}'), ('\u{2ef}', '\u{2ff}'), ('\u{384}', '\u{385}'),
+    ('\u{3f6}', '\u{3f6}'), ('\u{2044}', '\u{2044}'), ('\u{2052}', '\u{2052}'),
+    ('\u{207a}', '\u{207c}'), ('\u{208a}', '\u{208c}'), ('\u{20a0}', '\u{20c0}'),
+    ('\u{2100}', '\u{2101}'), ('\u{2103}', '\u{2106}'), ('\u{2108}', '\u{2109}'),
+    ('\u{2114}', '\u{2114}'), ('\u{2116}', '\u{2118}'), ('\u{211e}', '\u{2123}'),
+    ('\u{2125}', '\u{2125}'), ('\u{2127}', '\u{2127}'), ('\u{2129}', '\u{2129}'),
+    ('\u{212e}', '\u{212e}'), ('\u{213a}', '\u{213b}'), ('\u{2140}', '\u{2144}'),
+    ('\u{214a}', '\u{214d}'), ('\u{214f}', '\u{214f}'), ('\u{218a}', '\u{218b}'),
+    ('\u{2190}', '\u{2307}'), ('\u{230c}', '\u{2328}'), ('\u{232b}', '\u{2426}'),
+    ('\u{2440}', '\u{244a}'), ('\u{249c}', '\u{24e9}'), ('\u{2500}', '\u{2767}'),
+    ('\u{2794}', '\u{27c4}'), ('\u{27c7}', '\u{27e5}'), ('\u{27f0}', '\u{2982}'),
+    ('\u{2999}', '\u{29d7}'), ('\u{29dc}', '\u{29fb}'), ('\u{29fe}', '\u{2b73}'),
+    ('\u{2b76}', '\u{2b95}'), ('\u{2b97}', '\u{2bff}'), ('\u{2e80}', '\u{2e99}'),
+    ('\u{2e9b}', '\u{2ef3}'), ('\u{2f00}', '\u{2fd5}'), ('\u{2ff0}', '\u{2fff}'),
+    ('\u{3004}', '\u{3004}'), ('\u{3012}', '\u{3013}'), ('\u{3020}', '\u{3020}'),
+    ('\u{3036}', '\u{3037}'), ('\u{303e}', '\u{303f}'), ('\u{309b}', '\u{309c}'),
+    ('\u{3190}', '\u{3191}'), ('\u{3196}', '\u{319f}'), ('\u{31c0}', '\u{31e3}'),
+    ('\u{3200}', '\u{321e}'), ('\u{322a}', '\u{3247}'), ('\u{3250}', '\u{3250}'),
+    ('\u{3260}', '\u{327f}'), ('\u{328a}', '\u{32b0}'), ('\u{32c0}', '\u{33ff}'),
+    ('\u{ff04}', '\u{ff04}'), ('\u{ff0b}', '\u{ff0b}'), ('\u{ff1c}', '\u{ff1e}'),
+    ('\u{ff3e}', '\u{ff3e}'), ('\u{ff40}', '\u{ff40}'), ('\u{ff5c}', '\u{ff5c}'),
+    ('\u{ff5e}', '\u{ff5e}'), ('\u{ffe0}', '\u{ffe6}'), ('\u{ffe8}', '\u{ffee}'),
+    ('\u{1f000}', '\u{1faff}'),
+];
+
+const MARK: &[(char, char)] = &[
+    ('\u{300}', '\u{36f}'), ('\u{483}', '\u{489}'), ('\u{591}', '\u{5bd}'),
+    ('\u{610}', '\u{61a}'), ('\u{64b}', '\u{65f}'), ('\u{900}', '\u{903}'),
+    ('\u{93a}', '\u{93c}'), ('\u{93e}', '\u{94f}'), ('\u{e31}', '\u{e31}'),
+    ('\u{e34}', '\u{e3a}'), ('\u{e47}', '\u{e4e}'), ('\u{1ab0}', '\u{1aff}'),
+    ('\u{1dc0}', '\u{1dff}'), ('\u{20d0}', '\u{20f0}'), ('\u{302a}', '\u{302f}'),
+    ('\u{3099}', '\u{309a}'), ('\u{fe00}', '\u{fe0f}'), ('\u{fe20}', '\u{fe2f}'),
+    ('\u{e0100}', '\u{e01ef}'),
+];
+
+const LATIN: &[(char, char)] = &[
+    ('A', 'Z'), ('a', 'z'), ('\u{aa}', '\u{aa}'), ('\u{ba}', '\u{ba}'), ('\u{c0}', '\u{d6}'),
+    ('\u{d8}', '\u{f6}'), ('\u{f8}', '\u{2b8}'), ('\u{2e0}', '\u{2e4}'),
+    ('\u{1d00}', '\u{1d25}'), ('\u{1d2c}', '\u{1d5c}'), ('\u{1d62}', '\u{1d65}'),
+    ('\u{1d6b}', '\u{1d77}'), ('\u{1d79}', '\u{1dbe}'), ('\u{1e00}', '\u{1eff}'),
+    ('\u{2071}', '\u{2071}'), ('\u{207f}', '\u{207f}'), ('\u{2090}', '\u{209c}'),
+    ('\u{212a}', '\u{212b}'), ('\u{2132}', '\u{2132}'), ('\u{214e}', '\u{214e}'),
+    ('\u{2160}', '\u{2188}'), ('\u{2c60}', '\u{2c7f}'), ('\u{a722}', '\u{a787}'),
+    ('\u{a78b}', '\u{a7ca}'), ('\u{a7f2}', '\u{a7ff}'), ('\u{ab30}', '\u{ab5a}'),
+    ('\u{ab5c}', '\u{ab64}'), ('\u{fb00}', '\u{fb06}'), ('\u{ff21}', '\u{ff3a}'),
+    ('\u{ff41}', '\u{ff5a}'),
+];
+
+const GREEK: &[(char, char)] = &[
+    ('\u{370}', '\u{373}'), ('\u{375}', '\u{377}'), ('\u{37a}', '\u{37d}'),
+    ('\u{37f}', '\u{37f}'), ('\u{384}', '\u{384}'), ('\u{386}', '\u{386}'),
+    ('\u{388}', '\u{38a}'), ('\u{38c}', '\u{38c}'), ('\u{38e}', '\u{3a1}'),
+    ('\u{3a3}', '\u{3e1}'), ('\u{3f0}', '\u{3ff}'), ('\u{1d26}', '\u{1d2a}'),
+    ('\u{1f00}', '\u{1ffe}'), ('\u{2126}', '\u{2126}'), ('\u{ab65}', '\u{ab65}'),
+];
+
+const CYRILLIC: &[(char, char)] = &[
+    ('\u{400}', '\u{484}'), ('\u{487}', '\u{52f}'), ('\u{1c80}', '\u{1c88}'),
+    ('\u{1d2b}', '\u{1d2b}'), ('\u{1d78}', '\u{1d78}'), ('\u{2de0}', '\u{2dff}'),
+    ('\u{a640}', '\u{a69f}'),
+];
+
+const HEBREW: &[(char, char)] = &[
+    ('\u{591}', '\u{5c7}'), ('\u{5d0}', '\u{5ea}'), ('\u{5ef}', '\u{5f4}'),
+    ('\u{fb1d}', '\u{fb4f}'),
+];
+
+const ARABIC: &[(char, char)] = &[
+    ('\u{600}', '\u{604}'), ('\u{606}', '\u{60b}'), ('\u{60d}', '\u{61a}'),
+    ('\u{61c}', '\u{61e}'), ('\u{620}', '\u{63f}'), ('\u{641}', '\u{64a}'),
+    ('\u{656}', '\u{66f}'), ('\u{671}', '\u{6dc}'), ('\u{6de}', '\u{6ff}'),
+    ('\u{750}', '\u{77f}'), ('\u{8a0}', '\u{8ff}'), ('\u{fb50}', '\u{fdff}'),
+    ('\u{fe70}', '\u{fefc}'),
+];
+
+const THAI: &[(char, char)] = &[('\u{e01}', '\u{e3a}'), ('\u{e40}', '\u{e5b}')];
+
+const HANGUL: &[(char, char)] = &[
+    ('\u{1100}', '\u{11ff}'), ('\u{302e}', '\u{302f}'), ('\u{3131}', '\u{318e}'),
+    ('\u{3200}', '\u{321e}'), ('\u{3260}', '\u{327e}'), ('\u{a960}', '\u{a97c}'),
+    ('\u{ac00}', '\u{d7a3}'), ('\u{d7b0}', '\u{d7c6}'), ('\u{d7cb}', '\u{d7fb}'),
+    ('\u{ffa0}', '\u{ffbe}'), ('\u{ffc2}', '\u{ffc7}'), ('\u{ffca}', '\u{ffcf}'),
+    ('\u{ffd2}', '\u{ffd7}'), ('\u{ffda}', '\u{ffdc}'),
+];
+
+const HIRAGANA: &[(char, char)] = &[
+    ('\u{3041}', '\u{3096}'), ('\u{309d}', '\u{309f}'), ('\u{1b001}', '\u{1b11f}'),
+    ('\u{1f200}', '\u{1f200}'),
+];
+
+const KATAKANA: &[(char, char)] = &[
+    ('\u{30a1}', '\u{30fa}'), ('\u{30fd}', '\u{30ff}'), ('\u{31f0}', '\u{31ff}'),
+    ('\u{32d0}', '\u{32fe}'), ('\u{3300}', '\u{3357}'), ('\u{ff66}', '\u{ff6f}'),
+    ('\u{ff71}', '\u{ff9d}'), ('\u{1b000}', '\u{1b000}'),
+];
+
+const HAN: &[(char, char)] = &[
+    ('\u{2e80}', '\u{2e99}'), ('\u{2e9b}', '\u{2ef3}'), ('\u{2f00}', '\u{2fd5}'),
+    ('\u{3005}', '\u{3005}'), ('\u{3007}', '\u{3007}'), ('\u{3021}', '\u{3029}'),
+    ('\u{3038}', '\u{303b}'), ('\u{3400}', '\u{4dbf}'), ('\u{4e00}', '\u{9fff}'),
+    ('\u{f900}', '\u{fa6d}'), ('\u{fa70}', '\u{fad9}'), ('\u{20000}', '\u{2a6df}'),
+    ('\u{2a700}', '\u{2ebe0}'), ('\u{2f800}', '\u{2fa1d}'), ('\u{30000}', '\u{3134a}'),
+];
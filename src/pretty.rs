@@ -0,0 +1,433 @@
+// {:#?}より調整のきく整形表示
+//
+// "{:#?}"は字下げが4桁に決まっていて、(42.5237606, -122.6819273)のような短いタプルも要素ごとに改行する。
+// PrettyPrinterは値の"{:?}"の出力を構造体・タプル・リスト・マップの木に読み直し、Wadlerの整形アルゴリズムと
+// 同じく「まとまりが1行に収まるなら1行に、収まらなければ要素ごとに改行」して書く。字下げと行幅、
+// マップのキーの整列、深さと要素数の上限、ANSIエスケープシーケンスによる色付けを指定できる。
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::width::str_width;
+
+// 値の木。"{:?}"の出力を読み直したもの
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Atom(String, Kind),
+    Seq(Seq),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Seq {
+    /// "Point { .. }"や"Some(..)"の型名
+    name: Option<String>,
+    shape: Shape,
+    items: Vec<Item>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    /// "( )"
+    Tuple,
+    /// "[ ]"
+    List,
+    /// "{ }"。型名があれば構造体、なければマップか集合
+    Braces,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Item {
+    Value(Node),
+    Entry(Node, Node),
+    /// 上限で省いた要素の数
+    More(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Str,
+    Number,
+    Keyword,
+    Ident,
+    Field,
+    Punct,
+}
+
+impl Shape {
+    fn brackets(self) -> (&'static str, &'static str) {
+        match self {
+            Shape::Tuple => ("(", ")"),
+            Shape::List => ("[", "]"),
+            Shape::Braces => ("{", "}"),
+        }
+    }
+}
+
+// "{:?}"の出力の読み取り。読めない形ならNone
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_spaces();
+        match self.peek()? {
+            quote @ '"' | quote @ '\'' => self.quoted(quote),
+            '(' => self.seq(None, Shape::Tuple),
+            '[' => self.seq(None, Shape::List),
+            '{' => self.seq(None, Shape::Braces),
+            _ => {
+                let atom = self.atom()?;
+                if self.peek() == Some('(') {
+                    return self.seq(Some(atom), Shape::Tuple);
+                }
+                if self.text[self.position..].starts_with(" {") {
+                    self.position += 1;
+                    return self.seq(Some(atom), Shape::Braces);
+                }
+                let kind = match atom.as_str() {
+                    "true" | "false" | "None" => Kind::Keyword,
+                    "NaN" | "inf" | "-inf" => Kind::Number,
+                    _ if atom.trim_start_matches('-').starts_with(|ch: char| ch.is_ascii_digit()) => Kind::Number,
+                    _ => Kind::Ident,
+                };
+                Some(Node::Atom(atom, kind))
+            }
+        }
+    }
+
+    // 引用符の中はエスケープされた引用符を飛ばして閉じる引用符まで
+    fn quoted(&mut self, quote: char) -> Option<Node> {
+        let start = self.position;
+        let mut chars = self.text[start + 1..].char_indices();
+        while let Some((i, ch)) = chars.next() {
+            if ch == '\\' {
+                chars.next();
+            } else if ch == quote {
+                self.position = start + 1 + i + 1;
+                return Some(Node::Atom(self.text[start..self.position].to_string(), Kind::Str));
+            }
+        }
+        None
+    }
+
+    // 区切り記号か空白までの名前や数値。"std::io::Error"の"::"は名前の一部
+    fn atom(&mut self) -> Option<String> {
+        let start = self.position;
+        let bytes = self.text.as_bytes();
+        while self.position < bytes.len() {
+            match bytes[self.position] {
+                b',' | b'(' | b')' | b'[' | b']' | b'{' | b'}' | b'"' => break,
+                b' ' | b'\n' | b'\t' => break,
+                b':' if bytes.get(self.position + 1) == Some(&b':') => self.position += 2,
+                b':' => break,
+                _ => self.position += 1,
+            }
+        }
+        if self.position == start {
+            None
+        } else {
+            Some(self.text[start..self.position].to_string())
+        }
+    }
+
+    fn seq(&mut self, name: Option<String>, shape: Shape) -> Option<Node> {
+        let close = shape.brackets().1;
+        // 開き括弧
+        self.position += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.text[self.position..].starts_with(close) {
+                self.position += 1;
+                return Some(Node::Seq(Seq { name, shape, items }));
+            }
+            let first = self.value()?;
+            self.skip_spaces();
+            if self.peek() == Some(':') {
+                self.position += 1;
+                let second = self.value()?;
+                items.push(Item::Entry(first, second));
+            } else {
+                items.push(Item::Value(first));
+            }
+            self.skip_spaces();
+            match self.peek()? {
+                ',' => self.position += 1,
+                ch if close.starts_with(ch) => {}
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// "{:?}"の出力を整形して書く
+///
+/// ```text
+/// let printer = PrettyPrinter::new().indent(2).width(40).sort_keys(true);
+/// printer.format(&map)
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrettyPrinter {
+    indent: usize,
+    width: usize,
+    sort_keys: bool,
+    max_depth: Option<usize>,
+    max_items: Option<usize>,
+    colors: bool,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> PrettyPrinter {
+        PrettyPrinter::new()
+    }
+}
+
+// 色付けに使うSGRの番号
+fn color_code(kind: Kind) -> Option<&'static str> {
+    match kind {
+        Kind::Str => Some("32"),
+        Kind::Number => Some("36"),
+        Kind::Keyword => Some("35"),
+        Kind::Field => Some("34"),
+        Kind::Ident => Some("33"),
+        Kind::Punct => None,
+    }
+}
+
+impl PrettyPrinter {
+    /// 字下げ4桁、行幅80桁、キーは元の順、上限なし、色なし
+    pub fn new() -> PrettyPrinter {
+        PrettyPrinter { indent: 4, width: 80, sort_keys: false, max_depth: None, max_items: None, colors: false }
+    }
+
+    pub fn indent(mut self, indent: usize) -> PrettyPrinter {
+        self.indent = indent;
+        self
+    }
+
+    /// 1行に収めようとする桁数（表示幅）
+    pub fn width(mut self, width: usize) -> PrettyPrinter {
+        self.width = width;
+        self
+    }
+
+    /// マップと集合の要素をキーの順に並べる（HashMapの出力を毎回同じにする）。数値は数の大小で比べる
+    pub fn sort_keys(mut self, sort_keys: bool) -> PrettyPrinter {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// これより深い入れ子は"…"で省く
+    pub fn max_depth(mut self, max_depth: usize) -> PrettyPrinter {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// 1つの入れ子で書く要素の数。残りは"… 3 more"とまとめる
+    pub fn max_items(mut self, max_items: usize) -> PrettyPrinter {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// ANSIエスケープシーケンスで文字列・数値・フィールド名などを色分けする
+    pub fn colors(mut self, colors: bool) -> PrettyPrinter {
+        self.colors = colors;
+        self
+    }
+
+    pub fn format<T: fmt::Debug + ?Sized>(&self, value: &T) -> String {
+        self.format_debug(&format!("{:?}", value))
+    }
+
+    /// "{:?}"で書いた文字列を整形する。読めない形ならそのまま返す
+    pub fn format_debug(&self, debug: &str) -> String {
+        let mut parser = Parser { text: debug, position: 0 };
+        let node = match parser.value() {
+            Some(node) if parser.text[parser.position..].trim().is_empty() => node,
+            _ => return debug.to_string(),
+        };
+        let node = self.limit(node, 0);
+        let mut out = String::new();
+        self.layout(&node, 0, 0, 0, &mut out);
+        out
+    }
+
+    // 並べ替えと上限を適用する
+    fn limit(&self, node: Node, depth: usize) -> Node {
+        let mut seq = match node {
+            Node::Seq(seq) => seq,
+            atom => return atom,
+        };
+        if self.max_depth.is_some_and(|max_depth| depth >= max_depth) && !seq.items.is_empty() {
+            seq.items = vec![Item::Value(Node::Atom("…".to_string(), Kind::Punct))];
+            return Node::Seq(seq);
+        }
+        if self.sort_keys && seq.shape == Shape::Braces && seq.name.is_none() {
+            seq.items.sort_by(|a, b| compare_keys(sort_key(a), sort_key(b)));
+        }
+        let total = seq.items.len();
+        let mut items: Vec<Item> = seq
+            .items
+            .into_iter()
+            .take(self.max_items.unwrap_or(usize::MAX))
+            .map(|item| match item {
+                Item::Value(value) => Item::Value(self.limit(value, depth + 1)),
+                Item::Entry(key, value) => Item::Entry(key, self.limit(value, depth + 1)),
+                more => more,
+            })
+            .collect();
+        if items.len() < total {
+            items.push(Item::More(total - items.len()));
+        }
+        seq.items = items;
+        Node::Seq(seq)
+    }
+
+    fn paint(&self, text: &str, kind: Kind, out: &mut String) {
+        match color_code(kind) {
+            Some(code) if self.colors => out.push_str(&format!("\x1b[{}m{}\x1b[0m", code, text)),
+            _ => out.push_str(text),
+        }
+    }
+
+    // 改行せずに書く
+    fn flat(&self, node: &Node, out: &mut String) {
+        match *node {
+            Node::Atom(ref text, kind) => self.paint(text, kind, out),
+            Node::Seq(ref seq) => {
+                let (open, close) = seq.shape.brackets();
+                if let Some(ref name) = seq.name {
+                    self.paint(name, Kind::Ident, out);
+                    if seq.shape == Shape::Braces {
+                        out.push(' ');
+                    }
+                }
+                out.push_str(open);
+                // 構造体は"Point { x: 1 }"のように括弧の内側に空白を置く
+                let padded = seq.shape == Shape::Braces && seq.name.is_some();
+                if padded {
+                    out.push(' ');
+                }
+                for (i, item) in seq.items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.flat_item(item, seq, out);
+                }
+                if padded {
+                    out.push(' ');
+                }
+                out.push_str(close);
+            }
+        }
+    }
+
+    fn flat_item(&self, item: &Item, seq: &Seq, out: &mut String) {
+        match *item {
+            Item::Value(ref value) => self.flat(value, out),
+            Item::Entry(ref key, ref value) => {
+                self.key(key, seq, out);
+                out.push_str(": ");
+                self.flat(value, out);
+            }
+            Item::More(count) => self.paint(&format!("… {} more", count), Kind::Punct, out),
+        }
+    }
+
+    // 構造体のフィールド名はFieldの色にする
+    fn key(&self, key: &Node, seq: &Seq, out: &mut String) {
+        match *key {
+            Node::Atom(ref text, Kind::Ident) if seq.name.is_some() => self.paint(text, Kind::Field, out),
+            _ => self.flat(key, out),
+        }
+    }
+
+    fn flat_width(&self, node: &Node) -> usize {
+        let mut text = String::new();
+        self.flat(node, &mut text);
+        str_width(&strip_ansi(&text))
+    }
+
+    // columnは現在の行で既に書いた桁数、trailingは後に続く","の桁数
+    fn layout(&self, node: &Node, level: usize, column: usize, trailing: usize, out: &mut String) {
+        let seq = match *node {
+            Node::Seq(ref seq) if !seq.items.is_empty() => seq,
+            _ => return self.flat(node, out),
+        };
+        if column + self.flat_width(node) + trailing <= self.width {
+            return self.flat(node, out);
+        }
+        let (open, close) = seq.shape.brackets();
+        if let Some(ref name) = seq.name {
+            self.paint(name, Kind::Ident, out);
+            if seq.shape == Shape::Braces {
+                out.push(' ');
+            }
+        }
+        out.push_str(open);
+        out.push('\n');
+        let inner = " ".repeat(self.indent * (level + 1));
+        for item in &seq.items {
+            out.push_str(&inner);
+            match *item {
+                Item::Entry(ref key, ref value) => {
+                    let start = out.len();
+                    self.key(key, seq, out);
+                    out.push_str(": ");
+                    let key_width = str_width(&strip_ansi(&out[start..]));
+                    self.layout(value, level + 1, inner.len() + key_width, 1, out);
+                }
+                Item::Value(ref value) => self.layout(value, level + 1, inner.len(), 1, out),
+                Item::More(_) => self.flat_item(item, seq, out),
+            }
+            out.push_str(",\n");
+        }
+        out.push_str(&" ".repeat(self.indent * level));
+        out.push_str(close);
+    }
+}
+
+fn sort_key(item: &Item) -> Option<&str> {
+    match *item {
+        Item::Value(Node::Atom(ref text, _)) | Item::Entry(Node::Atom(ref text, _), _) => Some(text),
+        _ => None,
+    }
+}
+
+// 数値どうしなら数の大小で、それ以外は文字列として比べる。比べられない要素は後ろへ
+fn compare_keys(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => a.cmp(b),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::new();
+    let mut escape = false;
+    for ch in text.chars() {
+        match ch {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => plain.push(ch),
+        }
+    }
+    plain
+}
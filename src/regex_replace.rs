@@ -0,0 +1,286 @@
+// regexクレートの上に載せる置換レイヤー
+//
+// 置換テンプレートは$1や${name}でキャプチャを参照し、${1:upper}のように大文字・小文字変換を指定できる。
+// edits()は置換を実行せずに編集内容（バイト範囲と新しいテキスト）を列挙するドライランに使う。
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+use regex::{Captures, Regex};
+
+/// キャプチャに適用する大文字・小文字変換
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseConversion {
+    AsIs,
+    Upper,
+    Lower,
+    /// 先頭だけ大文字、残りは小文字
+    Title,
+}
+
+impl CaseConversion {
+    fn apply(self, text: &str, dst: &mut String) {
+        match self {
+            CaseConversion::AsIs => dst.push_str(text),
+            CaseConversion::Upper => dst.push_str(&text.to_uppercase()),
+            CaseConversion::Lower => dst.push_str(&text.to_lowercase()),
+            CaseConversion::Title => {
+                let mut chars = text.chars();
+                if let Some(first) = chars.next() {
+                    dst.extend(first.to_uppercase());
+                    dst.push_str(&chars.as_str().to_lowercase());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupRef {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Group(GroupRef, CaseConversion),
+}
+
+/// 解析済みの置換テンプレート
+///
+/// `$$`は`$`そのもの、`$1`と`$name`はキャプチャ、`${1:upper}`や`${name:lower}`は変換付きのキャプチャ。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(dollar) = rest.find('$') {
+            literal.push_str(&rest[..dollar]);
+            let position = template.len() - rest.len() + dollar;
+            let after = &rest[dollar + 1..];
+
+            if let Some(after_dollar) = after.strip_prefix('$') {
+                literal.push('$');
+                rest = after_dollar;
+                continue;
+            }
+
+            let (group, case, consumed) = if let Some(braced) = after.strip_prefix('{') {
+                let close = braced.find('}')
+                    .ok_or(TemplateError::Unclosed { position })?;
+                let (name, modifier) = match braced[..close].find(':') {
+                    Some(colon) => (&braced[..colon], Some(&braced[colon + 1..close])),
+                    None => (&braced[..close], None),
+                };
+                let case = match modifier {
+                    None => CaseConversion::AsIs,
+                    Some("upper") => CaseConversion::Upper,
+                    Some("lower") => CaseConversion::Lower,
+                    Some("title") => CaseConversion::Title,
+                    Some(other) => {
+                        return Err(TemplateError::UnknownModifier { position, modifier: other.to_string() })
+                    }
+                };
+                let group = parse_group(name).ok_or(TemplateError::InvalidGroup { position })?;
+                (group, case, close + 2)
+            } else {
+                let len = after
+                    .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                    .unwrap_or(after.len());
+                let group = parse_group(&after[..len]).ok_or(TemplateError::InvalidGroup { position })?;
+                (group, CaseConversion::AsIs, len)
+            };
+
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Group(group, case));
+            rest = &after[consumed..];
+        }
+
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// テンプレートが参照するキャプチャがすべて正規表現に存在するか確かめる
+    pub fn check(&self, regex: &Regex) -> Result<(), TemplateError> {
+        for part in &self.parts {
+            if let Part::Group(ref group, _) = *part {
+                let exists = match *group {
+                    GroupRef::Index(i) => i < regex.captures_len(),
+                    GroupRef::Name(ref name) => regex.capture_names().any(|n| n == Some(name.as_str())),
+                };
+                if !exists {
+                    return Err(TemplateError::UnknownGroup(group.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// キャプチャを展開してdstに追記する。マッチしなかったグループは空文字列になる
+    pub fn expand(&self, caps: &Captures, dst: &mut String) {
+        for part in &self.parts {
+            match *part {
+                Part::Literal(ref text) => dst.push_str(text),
+                Part::Group(ref group, case) => {
+                    let matched = match *group {
+                        GroupRef::Index(i) => caps.get(i),
+                        GroupRef::Name(ref name) => caps.name(name),
+                    };
+                    if let Some(matched) = matched {
+                        case.apply(matched.as_str(), dst);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_group(name: &str) -> Option<GroupRef> {
+    if name.is_empty() {
+        None
+    } else if let Ok(index) = name.parse() {
+        Some(GroupRef::Index(index))
+    } else if name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+    {
+        Some(GroupRef::Name(name.to_string()))
+    } else {
+        None
+    }
+}
+
+// Regex::replace_all(&text, &template)のように、regexクレートの置換APIにもそのまま渡せる
+impl regex::Replacer for &Template {
+    fn replace_append(&mut self, caps: &Captures, dst: &mut String) {
+        self.expand(caps, dst);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// `${`が閉じていない
+    Unclosed { position: usize },
+    /// `$`の後ろがグループ番号でも名前でもない
+    InvalidGroup { position: usize },
+    UnknownModifier { position: usize, modifier: String },
+    /// 正規表現に存在しないグループを参照している
+    UnknownGroup(GroupRef),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::Unclosed { position } => write!(dest, "unclosed '${{' at byte {}", position),
+            TemplateError::InvalidGroup { position } => write!(dest, "invalid group reference at byte {}", position),
+            TemplateError::UnknownModifier { position, ref modifier } => {
+                write!(dest, "unknown modifier '{}' at byte {}", modifier, position)
+            }
+            TemplateError::UnknownGroup(GroupRef::Index(i)) => write!(dest, "no capture group {}", i),
+            TemplateError::UnknownGroup(GroupRef::Name(ref name)) => write!(dest, "no capture group named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// 1つの置換
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    /// 置換される元テキスト上のバイト範囲
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+enum Replacement {
+    Template(Template),
+    Callback(Box<dyn Fn(&Captures) -> String>),
+}
+
+/// 正規表現と置換方法の組
+pub struct RegexReplacer {
+    regex: Regex,
+    replacement: Replacement,
+    limit: Option<usize>,
+}
+
+impl RegexReplacer {
+    /// テンプレートで置換する。存在しないグループを参照していればエラー
+    pub fn with_template(regex: Regex, template: &str) -> Result<RegexReplacer, TemplateError> {
+        let template = Template::parse(template)?;
+        template.check(&regex)?;
+        Ok(RegexReplacer { regex, replacement: Replacement::Template(template), limit: None })
+    }
+
+    /// クロージャがキャプチャから置換文字列を計算する
+    pub fn with_fn<F>(regex: Regex, callback: F) -> RegexReplacer
+    where
+        F: Fn(&Captures) -> String + 'static,
+    {
+        RegexReplacer { regex, replacement: Replacement::Callback(Box::new(callback)), limit: None }
+    }
+
+    /// 先頭から最大limit個のマッチだけを置換する
+    pub fn limit(mut self, limit: usize) -> RegexReplacer {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// 置換を実行せず、行われるはずの編集を順に列挙する
+    pub fn edits(&self, haystack: &str) -> Vec<Edit> {
+        self.regex
+            .captures_iter(haystack)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|caps| {
+                let whole = caps.get(0).expect("group 0 always matches");
+                let replacement = match self.replacement {
+                    Replacement::Template(ref template) => {
+                        let mut text = String::new();
+                        template.expand(&caps, &mut text);
+                        text
+                    }
+                    Replacement::Callback(ref callback) => callback(&caps),
+                };
+                Edit { range: whole.start()..whole.end(), replacement }
+            })
+            .collect()
+    }
+
+    /// すべての置換を行う。マッチがなければ元の文字列を借用したまま返す
+    pub fn replace_all<'a>(&self, haystack: &'a str) -> Cow<'a, str> {
+        let edits = self.edits(haystack);
+        if edits.is_empty() {
+            Cow::Borrowed(haystack)
+        } else {
+            Cow::Owned(apply_edits(haystack, &edits))
+        }
+    }
+}
+
+/// edits()で得た（あるいは取捨選択した）編集を適用する
+///
+/// 編集は範囲の昇順で、互いに重なっていないこと。
+pub fn apply_edits(haystack: &str, edits: &[Edit]) -> String {
+    let mut result = String::with_capacity(haystack.len());
+    let mut last = 0;
+    for edit in edits {
+        assert!(edit.range.start >= last, "edits must be sorted and must not overlap");
+        result.push_str(&haystack[last..edit.range.start]);
+        result.push_str(&edit.replacement);
+        last = edit.range.end;
+    }
+    result.push_str(&haystack[last..]);
+    result
+}
@@ -0,0 +1,105 @@
+// 複数の置換を1回の走査で同時に行う
+//
+// .replace("fear", "spin").replace("spin", …)のように連鎖させると、置換した結果が後の置換に
+// 再びマッチしてしまう。Replacerは元の文字列だけを走査するため、置換結果は二度と置換されない。
+
+use std::borrow::Cow;
+
+use crate::pattern::LiteralSet;
+
+/// 同じ位置で複数のパターンが一致したときにどれを採用するか
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// 最長のパターン
+    LeftmostLongest,
+    /// 先に登録したパターン
+    LeftmostFirst,
+}
+
+/// パターン→置換文字列の対応表から作る一括置換
+#[derive(Clone, Debug)]
+pub struct Replacer {
+    patterns: LiteralSet,
+    replacements: Vec<String>,
+    kind: MatchKind,
+}
+
+impl Replacer {
+    /// 空のパターンは無視し、同じパターンが複数あるときは後のものを採用する
+    pub fn new<I, K, V>(pairs: I) -> Replacer
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let mut patterns = LiteralSet::default();
+        let mut replacements: Vec<String> = Vec::new();
+        for (from, to) in pairs {
+            if let Some(index) = patterns.insert(from) {
+                if index < replacements.len() {
+                    replacements[index] = to.into();
+                } else {
+                    replacements.push(to.into());
+                }
+            }
+        }
+        Replacer { patterns, replacements, kind: MatchKind::LeftmostLongest }
+    }
+
+    pub fn match_kind(mut self, kind: MatchKind) -> Replacer {
+        self.kind = kind;
+        self
+    }
+
+    /// すべての一致を置換する。一致がなければ元の文字列を借用したまま返す
+    pub fn replace<'a>(&self, haystack: &'a str) -> Cow<'a, str> {
+        self.replace_limit(haystack, None)
+    }
+
+    /// str::replacen()と同様、先頭からcount個の一致だけを置換する
+    pub fn replacen<'a>(&self, haystack: &'a str, count: usize) -> Cow<'a, str> {
+        self.replace_limit(haystack, Some(count))
+    }
+
+    fn replace_limit<'a>(&self, haystack: &'a str, limit: Option<usize>) -> Cow<'a, str> {
+        let mut result = String::new();
+        let mut last = 0;
+        let mut replaced = 0;
+        let mut positions = haystack.char_indices();
+
+        while limit.is_none_or(|limit| replaced < limit) {
+            let (at, _) = match positions.next() {
+                Some(position) => position,
+                None => break,
+            };
+            if at < last {
+                continue;
+            }
+            let found = match self.kind {
+                MatchKind::LeftmostLongest => self.patterns.longest_at(haystack, at),
+                MatchKind::LeftmostFirst => self.patterns.matches_at(haystack, at).min(),
+            };
+            if let Some(index) = found {
+                if result.is_empty() {
+                    result.reserve(haystack.len());
+                }
+                result.push_str(&haystack[last..at]);
+                result.push_str(&self.replacements[index]);
+                last = at + self.patterns.get(index).map_or(0, str::len);
+                replaced += 1;
+            }
+        }
+
+        if replaced == 0 {
+            return Cow::Borrowed(haystack);
+        }
+        result.push_str(&haystack[last..]);
+        Cow::Owned(result)
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> std::iter::FromIterator<(K, V)> for Replacer {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> Replacer {
+        Replacer::new(pairs)
+    }
+}
@@ -0,0 +1,119 @@
+// UAX #29による単語・文の区切り
+//
+// split_whitespace()は"night,"を1語として返し、空白のない"うどんを食べる"は分けられない。
+// 境界の判定はunicode-segmentationクレートに任せ、ここでは各区間を語・数・句読点・空白に分類し
+// バイトオフセットを付けて返す。
+// UAX #29は辞書を使わないため、ひらがなと漢字は1文字ずつの区間になる（カタカナの連続は1語）。
+
+use unicode_segmentation::{UWordBoundIndices, UnicodeSegmentation, USentenceBoundIndices};
+
+use crate::pattern::GeneralCategory;
+
+/// 単語境界で区切った区間の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WordKind {
+    /// 文字を含む語（"can't"や"e.g"のように句読点を挟むものも含む）
+    Word,
+    /// 数字だけの語（"3.14"のような小数点を挟むものも含む）
+    Number,
+    Punctuation,
+    Space,
+    /// 記号など
+    Other,
+}
+
+/// 単語境界で区切った1区間
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment<'a> {
+    /// 元テキスト上のバイトオフセット
+    pub start: usize,
+    pub text: &'a str,
+    pub kind: WordKind,
+}
+
+impl<'a> Segment<'a> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// 索引に載せるべき語か
+    pub fn is_word_like(&self) -> bool {
+        self.kind == WordKind::Word || self.kind == WordKind::Number
+    }
+}
+
+fn classify(text: &str) -> WordKind {
+    if text.chars().all(char::is_whitespace) {
+        WordKind::Space
+    } else if text.chars().any(char::is_alphabetic) {
+        WordKind::Word
+    } else if text.chars().any(char::is_numeric) {
+        WordKind::Number
+    } else if text.chars().all(|ch| GeneralCategory::of(ch) == GeneralCategory::Punctuation) {
+        WordKind::Punctuation
+    } else {
+        WordKind::Other
+    }
+}
+
+/// 単語境界で区切った区間を順に返すイテレータ
+pub struct Words<'a> {
+    inner: UWordBoundIndices<'a>,
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        self.inner.next().map(|(start, text)| Segment { start, text, kind: classify(text) })
+    }
+}
+
+impl<'a> DoubleEndedIterator for Words<'a> {
+    fn next_back(&mut self) -> Option<Segment<'a>> {
+        self.inner.next_back().map(|(start, text)| Segment { start, text, kind: classify(text) })
+    }
+}
+
+/// テキストを単語境界で区切る。すべての区間を連結すると元のテキストに戻る
+pub fn words(text: &str) -> Words<'_> {
+    Words { inner: text.split_word_bound_indices() }
+}
+
+/// 語と数だけを返す。split_whitespace()の代わりに索引づけに使う
+pub fn tokens(text: &str) -> impl Iterator<Item = Segment<'_>> {
+    words(text).filter(Segment::is_word_like)
+}
+
+/// 文境界で区切った1文
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sentence<'a> {
+    pub start: usize,
+    /// 文末の空白や改行を含む
+    pub text: &'a str,
+}
+
+impl<'a> Sentence<'a> {
+    /// 末尾の空白を除いた文
+    pub fn trimmed(&self) -> &'a str {
+        self.text.trim_end()
+    }
+}
+
+/// 文境界で区切った文を順に返すイテレータ
+pub struct Sentences<'a> {
+    inner: USentenceBoundIndices<'a>,
+}
+
+impl<'a> Iterator for Sentences<'a> {
+    type Item = Sentence<'a>;
+
+    fn next(&mut self) -> Option<Sentence<'a>> {
+        self.inner.next().map(|(start, text)| Sentence { start, text })
+    }
+}
+
+/// テキストを文境界で区切る。"。"や"！"のような全角の句読点も文末として扱う
+pub fn sentences(text: &str) -> Sentences<'_> {
+    Sentences { inner: text.split_sentence_bound_indices() }
+}
@@ -0,0 +1,594 @@
+// JSON・YAML・TOMLへの書き出し
+//
+// "{:?}"や"{:#?}"はRustの構文で書くので、他のツールでは読めない。ToTextは値を共通のデータモデル（Data）に
+// 変換し、そこからJSON・YAML・TOMLを正しくエスケープして書く。構造体には#[derive(ToText)]で全フィールドを
+// 書く実装を生成する。HashMapのキーの順序と浮動小数点数の書き方はTextOptionsで指定する。
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::complex::{Complex, Real};
+use crate::float::Shortest;
+
+/// 構造体のフィールドを宣言の順に書くToTextの実装を生成する
+///
+/// 名前付きフィールドはData::Record、タプル構造体はData::Listになる。型引数にはToTextを求める。
+pub use to_text_derive::ToText;
+
+/// 書き出す値のデータモデル
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Data>),
+    /// HashMapなどのマップ。KeyOrder::Sortedならキーの順に並べる
+    Map(Vec<(String, Data)>),
+    /// 構造体。フィールドは常に宣言の順
+    Record(Vec<(String, Data)>),
+}
+
+/// Dataに変換できる値
+pub trait ToText {
+    fn to_data(&self) -> Data;
+
+    fn to_json(&self) -> String {
+        TextOptions::new().json(self)
+    }
+
+    fn to_yaml(&self) -> String {
+        TextOptions::new().yaml(self)
+    }
+
+    fn to_toml(&self) -> Result<String, TomlError> {
+        TextOptions::new().toml(self)
+    }
+}
+
+/// 構造体のToTextを、指定したフィールドだけを並べた順に書く実装として生成する
+///
+/// 全フィールドを書くなら#[derive(ToText)]を使う。このマクロは書き出すフィールドを選びたいときに使い、
+/// 並べなかったフィールドは出力に含まれない。
+///
+/// ```text
+/// to_text!(Station { name, location, tags });
+/// ```
+#[macro_export]
+macro_rules! to_text {
+    ($type:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::serialize::ToText for $type {
+            fn to_data(&self) -> $crate::serialize::Data {
+                $crate::serialize::Data::Record(vec![
+                    $((stringify!($field).to_string(), $crate::serialize::ToText::to_data(&self.$field)),)*
+                ])
+            }
+        }
+    };
+}
+
+impl ToText for Data {
+    fn to_data(&self) -> Data {
+        self.clone()
+    }
+}
+
+impl ToText for bool {
+    fn to_data(&self) -> Data {
+        Data::Bool(*self)
+    }
+}
+
+macro_rules! int_to_text {
+    ($($t:ty),*) => {
+        $(impl ToText for $t {
+            fn to_data(&self) -> Data {
+                Data::Int(*self as i64)
+            }
+        })*
+    };
+}
+
+int_to_text!(i8, i16, i32, i64, isize, u8, u16, u32);
+
+// i64に収まらない値は浮動小数点数にする
+macro_rules! wide_int_to_text {
+    ($($t:ty),*) => {
+        $(impl ToText for $t {
+            fn to_data(&self) -> Data {
+                i64::try_from(*self).map_or(Data::Float(*self as f64), Data::Int)
+            }
+        })*
+    };
+}
+
+wide_int_to_text!(u64, usize, i128, u128);
+
+impl ToText for f32 {
+    fn to_data(&self) -> Data {
+        // f32の最短表記をf64に写す（0.1f32を0.10000000149011612にしない）
+        Data::Float(self.to_string().parse().unwrap_or(f64::from(*self)))
+    }
+}
+
+impl ToText for f64 {
+    fn to_data(&self) -> Data {
+        Data::Float(*self)
+    }
+}
+
+impl ToText for str {
+    fn to_data(&self) -> Data {
+        Data::Str(self.to_string())
+    }
+}
+
+impl ToText for String {
+    fn to_data(&self) -> Data {
+        Data::Str(self.clone())
+    }
+}
+
+impl ToText for char {
+    fn to_data(&self) -> Data {
+        Data::Str(self.to_string())
+    }
+}
+
+impl<T: ToText + ?Sized> ToText for &T {
+    fn to_data(&self) -> Data {
+        (**self).to_data()
+    }
+}
+
+impl<T: ToText> ToText for Option<T> {
+    fn to_data(&self) -> Data {
+        self.as_ref().map_or(Data::Null, ToText::to_data)
+    }
+}
+
+impl<T: ToText> ToText for [T] {
+    fn to_data(&self) -> Data {
+        Data::List(self.iter().map(ToText::to_data).collect())
+    }
+}
+
+impl<T: ToText, const N: usize> ToText for [T; N] {
+    fn to_data(&self) -> Data {
+        self[..].to_data()
+    }
+}
+
+impl<T: ToText> ToText for Vec<T> {
+    fn to_data(&self) -> Data {
+        self[..].to_data()
+    }
+}
+
+impl<A: ToText, B: ToText> ToText for (A, B) {
+    fn to_data(&self) -> Data {
+        Data::List(vec![self.0.to_data(), self.1.to_data()])
+    }
+}
+
+impl<A: ToText, B: ToText, C: ToText> ToText for (A, B, C) {
+    fn to_data(&self) -> Data {
+        Data::List(vec![self.0.to_data(), self.1.to_data(), self.2.to_data()])
+    }
+}
+
+impl<K: fmt::Display, V: ToText, S> ToText for HashMap<K, V, S> {
+    fn to_data(&self) -> Data {
+        Data::Map(self.iter().map(|(key, value)| (key.to_string(), value.to_data())).collect())
+    }
+}
+
+impl<K: fmt::Display, V: ToText> ToText for BTreeMap<K, V> {
+    fn to_data(&self) -> Data {
+        Data::Map(self.iter().map(|(key, value)| (key.to_string(), value.to_data())).collect())
+    }
+}
+
+impl<T: Real + ToText> ToText for Complex<T> {
+    fn to_data(&self) -> Data {
+        Data::Record(vec![("re".to_string(), self.re.to_data()), ("im".to_string(), self.im.to_data())])
+    }
+}
+
+/// マップのキーの順序
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// キーの文字列の順（HashMapでも毎回同じ出力になる）
+    #[default]
+    Sorted,
+    /// マップを走査した順（HashMapでは実行ごとに変わりうる）
+    Iteration,
+}
+
+/// 浮動小数点数の書き方。整数に見える値には".0"を付け、整数と区別できるようにする
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// 読み戻して同じ値になる最短の表記（"0.1", "1e21"）
+    #[default]
+    Shortest,
+    /// 小数点以下の桁数を固定する（"{:.2}"）
+    Fixed(usize),
+}
+
+/// 書き出しの設定
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextOptions {
+    key_order: KeyOrder,
+    float_format: FloatFormat,
+    indent: Option<usize>,
+}
+
+impl Default for TextOptions {
+    fn default() -> TextOptions {
+        TextOptions::new()
+    }
+}
+
+impl TextOptions {
+    /// キーを整列し、浮動小数点数は最短表記、JSONは2桁の字下げ
+    pub fn new() -> TextOptions {
+        TextOptions { key_order: KeyOrder::Sorted, float_format: FloatFormat::Shortest, indent: Some(2) }
+    }
+
+    pub fn key_order(mut self, key_order: KeyOrder) -> TextOptions {
+        self.key_order = key_order;
+        self
+    }
+
+    pub fn float_format(mut self, float_format: FloatFormat) -> TextOptions {
+        self.float_format = float_format;
+        self
+    }
+
+    /// JSONとYAMLの字下げ。JSONでNoneなら1行に詰めて書く（YAMLは2桁になる）
+    pub fn indent(mut self, indent: Option<usize>) -> TextOptions {
+        self.indent = indent;
+        self
+    }
+
+    pub fn json<T: ToText + ?Sized>(&self, value: &T) -> String {
+        let mut out = String::new();
+        self.write_json(&self.ordered(value.to_data()), 0, &mut out);
+        out
+    }
+
+    /// ブロック形式のYAML。末尾は改行で終わる
+    pub fn yaml<T: ToText + ?Sized>(&self, value: &T) -> String {
+        let mut out = String::new();
+        let data = self.ordered(value.to_data());
+        match data {
+            Data::List(ref items) if !items.is_empty() => self.write_yaml_list(items, 0, &mut out),
+            Data::Map(ref entries) | Data::Record(ref entries) if !entries.is_empty() => {
+                self.write_yaml_map(entries, 0, &mut out)
+            }
+            ref scalar => {
+                out.push_str(&self.yaml_scalar(scalar));
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// TOMLの文書。最上位はマップか構造体でなければならない。Noneのフィールドは書かない
+    pub fn toml<T: ToText + ?Sized>(&self, value: &T) -> Result<String, TomlError> {
+        let data = self.ordered(value.to_data());
+        let entries = match data {
+            Data::Map(ref entries) | Data::Record(ref entries) => entries,
+            _ => return Err(TomlError::NotTable),
+        };
+        let mut out = String::new();
+        self.write_toml_table(entries, &[], &mut out)?;
+        Ok(out)
+    }
+
+    // KeyOrder::Sortedならマップを再帰的に整列する
+    fn ordered(&self, data: Data) -> Data {
+        match data {
+            Data::List(items) => Data::List(items.into_iter().map(|item| self.ordered(item)).collect()),
+            Data::Map(entries) => {
+                let mut entries: Vec<(String, Data)> =
+                    entries.into_iter().map(|(key, value)| (key, self.ordered(value))).collect();
+                if self.key_order == KeyOrder::Sorted {
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                Data::Map(entries)
+            }
+            Data::Record(fields) => {
+                Data::Record(fields.into_iter().map(|(key, value)| (key, self.ordered(value))).collect())
+            }
+            scalar => scalar,
+        }
+    }
+
+    // 有限の浮動小数点数の表記
+    fn float(&self, value: f64) -> String {
+        let text = match self.float_format {
+            FloatFormat::Shortest => Shortest(value).to_string(),
+            FloatFormat::Fixed(places) => format!("{:.*}", places, value),
+        };
+        if text.contains(['.', 'e']) {
+            text
+        } else {
+            format!("{}.0", text)
+        }
+    }
+
+    fn write_json(&self, data: &Data, level: usize, out: &mut String) {
+        let (newline, inner, outer, colon) = match self.indent {
+            Some(indent) => ("\n", " ".repeat(indent * (level + 1)), " ".repeat(indent * level), ": "),
+            None => ("", String::new(), String::new(), ":"),
+        };
+        match *data {
+            Data::Null => out.push_str("null"),
+            Data::Bool(value) => out.push_str(if value { "true" } else { "false" }),
+            Data::Int(value) => out.push_str(&value.to_string()),
+            // JSONには非数と無限大がない
+            Data::Float(value) if !value.is_finite() => out.push_str("null"),
+            Data::Float(value) => out.push_str(&self.float(value)),
+            Data::Str(ref text) => out.push_str(&json_string(text)),
+            Data::List(ref items) if items.is_empty() => out.push_str("[]"),
+            Data::List(ref items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(if i > 0 { "," } else { "" });
+                    out.push_str(newline);
+                    out.push_str(&inner);
+                    self.write_json(item, level + 1, out);
+                }
+                out.push_str(newline);
+                out.push_str(&outer);
+                out.push(']');
+            }
+            Data::Map(ref entries) | Data::Record(ref entries) if entries.is_empty() => out.push_str("{}"),
+            Data::Map(ref entries) | Data::Record(ref entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(if i > 0 { "," } else { "" });
+                    out.push_str(newline);
+                    out.push_str(&inner);
+                    out.push_str(&json_string(key));
+                    out.push_str(colon);
+                    self.write_json(value, level + 1, out);
+                }
+                out.push_str(newline);
+                out.push_str(&outer);
+                out.push('}');
+            }
+        }
+    }
+
+    fn yaml_indent(&self) -> usize {
+        self.indent.unwrap_or(2).max(1)
+    }
+
+    fn yaml_scalar(&self, data: &Data) -> String {
+        match *data {
+            Data::Null => "null".to_string(),
+            Data::Bool(value) => value.to_string(),
+            Data::Int(value) => value.to_string(),
+            Data::Float(value) if value.is_nan() => ".nan".to_string(),
+            Data::Float(value) if value.is_infinite() => if value > 0.0 { ".inf" } else { "-.inf" }.to_string(),
+            Data::Float(value) => self.float(value),
+            Data::Str(ref text) => yaml_string(text),
+            Data::List(_) => "[]".to_string(),
+            Data::Map(_) | Data::Record(_) => "{}".to_string(),
+        }
+    }
+
+    // 空でない入れ子は次の行から字下げして書き、それ以外は同じ行に書く
+    fn is_block(data: &Data) -> bool {
+        match *data {
+            Data::List(ref items) => !items.is_empty(),
+            Data::Map(ref entries) | Data::Record(ref entries) => !entries.is_empty(),
+            _ => false,
+        }
+    }
+
+    // columnは行頭の空白の数
+    fn write_yaml_map(&self, entries: &[(String, Data)], column: usize, out: &mut String) {
+        let indent = " ".repeat(column);
+        for (i, (key, value)) in entries.iter().enumerate() {
+            // リストの要素の最初のキーは"- "の後に続ける
+            if i > 0 || !out.ends_with("- ") {
+                out.push_str(&indent);
+            }
+            out.push_str(&yaml_string(key));
+            out.push(':');
+            self.write_yaml_value(value, column + self.yaml_indent(), out);
+        }
+    }
+
+    // 要素の中のブロックは、最初の行が"- "の後に続くので、2行目以降も"- "の後の桁に揃える
+    fn write_yaml_list(&self, items: &[Data], column: usize, out: &mut String) {
+        let indent = " ".repeat(column);
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 || !out.ends_with("- ") {
+                out.push_str(&indent);
+            }
+            out.push_str("- ");
+            match *item {
+                Data::List(ref inner) if !inner.is_empty() => self.write_yaml_list(inner, column + 2, out),
+                Data::Map(ref entries) | Data::Record(ref entries) if !entries.is_empty() => {
+                    self.write_yaml_map(entries, column + 2, out)
+                }
+                ref scalar => {
+                    out.push_str(&self.yaml_scalar(scalar));
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    // マップの値を"key:"の後に書く
+    fn write_yaml_value(&self, value: &Data, column: usize, out: &mut String) {
+        if !TextOptions::is_block(value) {
+            out.push(' ');
+            out.push_str(&self.yaml_scalar(value));
+            out.push('\n');
+            return;
+        }
+        out.push('\n');
+        match *value {
+            Data::List(ref items) => self.write_yaml_list(items, column, out),
+            Data::Map(ref entries) | Data::Record(ref entries) => self.write_yaml_map(entries, column, out),
+            _ => {}
+        }
+    }
+
+    // 値を書き、下位のテーブルは"[a.b]"や"[[a.b]]"の節として後に書く
+    fn write_toml_table(&self, entries: &[(String, Data)], path: &[String], out: &mut String) -> Result<(), TomlError> {
+        let is_table = |data: &Data| matches!(*data, Data::Map(_) | Data::Record(_));
+        let is_table_array =
+            |data: &Data| matches!(*data, Data::List(ref items) if !items.is_empty() && items.iter().all(is_table));
+        for (key, value) in entries {
+            if *value == Data::Null || is_table(value) || is_table_array(value) {
+                continue;
+            }
+            let key_path = child_path(path, key);
+            out.push_str(&format!("{} = {}\n", toml_key(key), self.toml_inline(value, &key_path)?));
+        }
+        for (key, value) in entries {
+            let key_path = child_path(path, key);
+            let header = key_path.iter().map(|key| toml_key(key)).collect::<Vec<_>>().join(".");
+            match *value {
+                Data::Map(ref inner) | Data::Record(ref inner) => {
+                    separate(out);
+                    out.push_str(&format!("[{}]\n", header));
+                    self.write_toml_table(inner, &key_path, out)?;
+                }
+                Data::List(ref items) if is_table_array(value) => {
+                    for item in items {
+                        if let Data::Map(ref inner) | Data::Record(ref inner) = *item {
+                            separate(out);
+                            out.push_str(&format!("[[{}]]\n", header));
+                            self.write_toml_table(inner, &key_path, out)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn toml_inline(&self, data: &Data, path: &[String]) -> Result<String, TomlError> {
+        Ok(match *data {
+            Data::Null => return Err(TomlError::Null { path: path.join(".") }),
+            Data::Bool(value) => value.to_string(),
+            Data::Int(value) => value.to_string(),
+            Data::Float(value) if value.is_nan() => "nan".to_string(),
+            Data::Float(value) if value.is_infinite() => if value > 0.0 { "inf" } else { "-inf" }.to_string(),
+            Data::Float(value) => self.float(value),
+            Data::Str(ref text) => json_string(text),
+            Data::List(ref items) => {
+                let items: Result<Vec<String>, TomlError> =
+                    items.iter().map(|item| self.toml_inline(item, path)).collect();
+                format!("[{}]", items?.join(", "))
+            }
+            Data::Map(ref entries) | Data::Record(ref entries) => {
+                let mut fields = Vec::new();
+                for (key, value) in entries {
+                    if *value != Data::Null {
+                        let value = self.toml_inline(value, &child_path(path, key))?;
+                        fields.push(format!("{} = {}", toml_key(key), value));
+                    }
+                }
+                if fields.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{ {} }}", fields.join(", "))
+                }
+            }
+        })
+    }
+}
+
+fn child_path(path: &[String], key: &str) -> Vec<String> {
+    let mut child = path.to_vec();
+    child.push(key.to_string());
+    child
+}
+
+// 節の前に空行を置く
+fn separate(out: &mut String) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+}
+
+/// TOMLで表せない値
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TomlError {
+    /// 最上位がマップでも構造体でもない
+    NotTable,
+    /// TOMLにはnullがない（リストの要素のNoneなど）
+    Null { path: String },
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TomlError::NotTable => write!(dest, "top-level value must be a table"),
+            TomlError::Null { ref path } => write!(dest, "key '{}': null is not representable in TOML", path),
+        }
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// JSONの文字列リテラル。TOMLの基本文字列とYAMLの二重引用符の文字列もこの書き方で読める
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// 引用符なしで書くと別の型や構文に読まれる文字列は二重引用符で囲む
+fn yaml_string(text: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let reserved = ["", "~", "null", "true", "false", "yes", "no", "on", "off", "y", "n", ".nan", ".inf", "-.inf"];
+    let looks_numeric = text.parse::<f64>().is_ok() || text.starts_with(|ch: char| ch.is_ascii_digit());
+    let plain = !reserved.contains(&lower.as_str())
+        && !looks_numeric
+        && !text.starts_with(|ch: char| "-?:,[]{}#&*!|>'\"%@` ".contains(ch))
+        && !text.ends_with([' ', ':'])
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.chars().any(|ch| ch.is_control());
+    if plain {
+        text.to_string()
+    } else {
+        json_string(text)
+    }
+}
+
+// 英数字と'_'・'-'だけなら引用符なしのキー
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    if bare {
+        key.to_string()
+    } else {
+        json_string(key)
+    }
+}
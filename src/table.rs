@@ -0,0 +1,292 @@
+// 表の組版
+//
+// format!("{description:.<25}{quantity:2} @{price:5.2}", ...)のように1行ずつ幅を手で決める代わりに、
+// 列ごとに寄せ方・詰め物・小数点以下の桁数を指定し、列幅は全セルの表示幅から自動で決める。
+// 同じ表をプレーンテキスト、Markdown、CSV、罫線付きの表として出力できる。
+
+use std::fmt;
+
+use crate::align::{Align, Padded};
+use crate::csv::{Dialect, Writer};
+use crate::width::str_width;
+
+/// セルの値。数値は既定で右寄せになる
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Integer(i128),
+    Float(f64),
+    Empty,
+}
+
+impl Cell {
+    fn is_numeric(&self) -> bool {
+        matches!(*self, Cell::Integer(_) | Cell::Float(_))
+    }
+
+    fn format(&self, precision: Option<usize>) -> String {
+        match *self {
+            Cell::Text(ref text) => text.clone(),
+            Cell::Integer(value) => value.to_string(),
+            Cell::Float(value) => match precision {
+                Some(precision) => format!("{:.*}", precision, value),
+                None => value.to_string(),
+            },
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Cell {
+    fn from(text: &'a str) -> Cell {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Cell {
+        Cell::Text(text)
+    }
+}
+
+macro_rules! integer_cell {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Cell {
+            fn from(value: $t) -> Cell {
+                // i128ならu64::MAXやi64::MINも値を変えずに入る
+                Cell::Integer(value as i128)
+            }
+        })*
+    };
+}
+
+integer_cell!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Cell {
+        Cell::Float(value)
+    }
+}
+
+impl From<f32> for Cell {
+    fn from(value: f32) -> Cell {
+        Cell::Float(value as f64)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Cell {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// 列の見出しと書式
+#[derive(Clone, Debug)]
+pub struct Column {
+    header: String,
+    align: Option<Align>,
+    fill: char,
+    precision: Option<usize>,
+}
+
+impl Column {
+    pub fn new(header: &str) -> Column {
+        Column { header: header.to_string(), align: None, fill: ' ', precision: None }
+    }
+
+    /// 指定しなければ、すべてのセルが数値の列は右寄せ、それ以外は左寄せ
+    pub fn align(mut self, align: Align) -> Column {
+        self.align = Some(align);
+        self
+    }
+
+    /// セルの詰め物（"{:.<25}"の'.'）。見出しは常に空白で詰める
+    pub fn fill(mut self, fill: char) -> Column {
+        self.fill = fill;
+        self
+    }
+
+    /// 浮動小数点数の小数点以下の桁数（"{:.2}"）
+    pub fn precision(mut self, precision: usize) -> Column {
+        self.precision = Some(precision);
+        self
+    }
+}
+
+/// 出力形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableStyle {
+    /// 空白2つで列を区切り、見出しの下に'-'を引く
+    Plain,
+    /// GitHub Flavored Markdownの表
+    Markdown,
+    /// RFC 4180のCSV（詰め物はしない）
+    Csv,
+    /// 罫線素片で囲む
+    Box,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table::default()
+    }
+
+    pub fn column(mut self, column: Column) -> Table {
+        self.columns.push(column);
+        self
+    }
+
+    /// 行を追加する。列より少ないセルは空として扱い、多いセルは捨てる
+    pub fn add_row<I, C>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<Cell>,
+    {
+        let mut row: Vec<Cell> = cells.into_iter().map(Into::into).take(self.columns.len()).collect();
+        row.resize(self.columns.len(), Cell::Empty);
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, style: TableStyle) -> String {
+        match style {
+            TableStyle::Plain => self.render_plain(),
+            TableStyle::Markdown => self.render_markdown(),
+            TableStyle::Csv => self.render_csv(),
+            TableStyle::Box => self.render_box(),
+        }
+    }
+
+    // 各列の寄せ方
+    fn aligns(&self) -> Vec<Align> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                column.align.unwrap_or_else(|| {
+                    let numeric = !self.rows.is_empty()
+                        && self.rows.iter().all(|row| row[i].is_numeric() || row[i] == Cell::Empty);
+                    if numeric {
+                        Align::Right
+                    } else {
+                        Align::Left
+                    }
+                })
+            })
+            .collect()
+    }
+
+    // 書式を適用したセルの文字列
+    fn formatted(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| row.iter().zip(&self.columns).map(|(cell, column)| cell.format(column.precision)).collect())
+            .collect()
+    }
+
+    fn headers(&self) -> Vec<String> {
+        self.columns.iter().map(|column| column.header.clone()).collect()
+    }
+
+    fn widths(&self, headers: &[String], cells: &[Vec<String>], minimum: usize) -> Vec<usize> {
+        headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| cells.iter().map(|row| str_width(&row[i])).fold(str_width(header), usize::max))
+            .map(|width| width.max(minimum))
+            .collect()
+    }
+
+    // 見出しと各行のセルを列幅に詰める。見出しは常に空白で詰める
+    fn padded_lines(&self, headers: &[String], cells: &[Vec<String>], widths: &[usize]) -> Vec<Vec<String>> {
+        let aligns = self.aligns();
+        let pad_row = |row: &[String], header: bool| -> Vec<String> {
+            row.iter()
+                .zip(&self.columns)
+                .zip(widths)
+                .zip(&aligns)
+                .map(|(((text, column), &width), &align)| {
+                    let fill = if header { ' ' } else { column.fill };
+                    Padded::new(text, width).align(align).fill(fill).to_string()
+                })
+                .collect()
+        };
+        std::iter::once(pad_row(headers, true)).chain(cells.iter().map(|row| pad_row(row, false))).collect()
+    }
+
+    fn render_plain(&self) -> String {
+        let headers = self.headers();
+        let cells = self.formatted();
+        let widths = self.widths(&headers, &cells, 0);
+        let mut lines = self.padded_lines(&headers, &cells, &widths);
+        lines.insert(1, widths.iter().map(|&width| "-".repeat(width)).collect());
+        lines.iter().map(|line| format!("{}\n", line.join("  ").trim_end())).collect()
+    }
+
+    fn render_markdown(&self) -> String {
+        let headers: Vec<String> = self.headers().iter().map(|header| escape_markdown(header)).collect();
+        let cells: Vec<Vec<String>> =
+            self.formatted().iter().map(|row| row.iter().map(|text| escape_markdown(text)).collect()).collect();
+        // 区切り行の"---"は3文字以上必要
+        let widths = self.widths(&headers, &cells, 3);
+        let mut lines = self.padded_lines(&headers, &cells, &widths);
+        let rule = widths
+            .iter()
+            .zip(self.aligns())
+            .map(|(&width, align)| match align {
+                Align::Left => format!(":{}", "-".repeat(width - 1)),
+                Align::Center => format!(":{}:", "-".repeat(width - 2)),
+                Align::Right => format!("{}:", "-".repeat(width - 1)),
+            })
+            .collect();
+        lines.insert(1, rule);
+        lines.iter().map(|line| format!("| {} |\n", line.join(" | "))).collect()
+    }
+
+    fn render_csv(&self) -> String {
+        let mut writer = Writer::new(Vec::new(), Dialect::csv());
+        let headers = self.columns.iter().map(|column| column.header.as_str());
+        // Vecへの書き込みは失敗しない
+        writer.write_record(headers).expect("write to Vec");
+        for row in self.formatted() {
+            writer.write_record(row).expect("write to Vec");
+        }
+        String::from_utf8(writer.into_inner()).expect("UTF-8 fields")
+    }
+
+    fn render_box(&self) -> String {
+        let headers = self.headers();
+        let cells = self.formatted();
+        let widths = self.widths(&headers, &cells, 0);
+        let rule = |left: &str, middle: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|&width| "─".repeat(width + 2)).collect();
+            format!("{}{}{}\n", left, segments.join(middle), right)
+        };
+        let lines = self.padded_lines(&headers, &cells, &widths);
+        let mut result = rule("┌", "┬", "┐");
+        for (index, line) in lines.iter().enumerate() {
+            result.push_str(&format!("│ {} │\n", line.join(" │ ")));
+            if index == 0 {
+                result.push_str(&rule("├", "┼", "┤"));
+            }
+        }
+        result.push_str(&rule("└", "┴", "┘"));
+        result
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        dest.write_str(&self.render(TableStyle::Plain))
+    }
+}
+
+// セル中の'|'と改行はMarkdownの表を壊す
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
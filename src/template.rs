@@ -0,0 +1,722 @@
+// 複数行の文書を生成するテンプレートエンジン
+//
+// {{ name | upper }}で値を埋め込み、{% for x in xs %}〜{% endfor %}、{% if c %}〜{% elif c %}〜{% else %}〜
+// {% endif %}、{% include "name" %}、{# コメント #}を使える。
+// format!のようなコンパイル時の検査は手続きマクロなしにはできないため、テンプレートは起動時に一度だけ
+// 解析し、構文・フィルタ名の誤りはその時点で行・桁付きのエラーにする。出力は任意のfmt::Writeに書く。
+// 行にタグだけがある場合は、その行の字下げと改行を出力しない。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::align::{Align, Padded};
+
+/// テンプレートに渡す値
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    // ifでの真偽。空文字列・0・空のリスト・空のマップは偽
+    fn is_truthy(&self) -> bool {
+        match *self {
+            Value::Str(ref s) => !s.is_empty(),
+            Value::Int(n) => n != 0,
+            Value::Float(f) => f != 0.0,
+            Value::Bool(b) => b,
+            Value::List(ref items) => !items.is_empty(),
+            Value::Map(ref map) => !map.is_empty(),
+        }
+    }
+}
+
+/// リストは", "で区切って出力する
+impl fmt::Display for Value {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Str(ref s) => dest.write_str(s),
+            Value::Int(n) => write!(dest, "{}", n),
+            Value::Float(f) => write!(dest, "{}", f),
+            Value::Bool(b) => write!(dest, "{}", b),
+            Value::List(ref items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    write!(dest, "{}", item)?;
+                }
+                Ok(())
+            }
+            Value::Map(ref map) => write!(dest, "{:?}", map),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Value {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::Str(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Value {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Value {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Context> for Value {
+    fn from(context: Context) -> Value {
+        Value::Map(context.values)
+    }
+}
+
+/// 名前と値の組
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Context {
+    values: BTreeMap<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Context {
+        Context::default()
+    }
+
+    pub fn set<V: Into<Value>>(mut self, name: &str, value: V) -> Context {
+        self.values.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+/// 埋め込んだ値のエスケープ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escape {
+    None,
+    /// & < > " 'を文字参照にする
+    Html,
+    /// シェルの引数1つになるよう、必要なら単一引用符で囲む
+    Shell,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    Upper,
+    Lower,
+    Trim,
+    Nfc,
+    /// エスケープしない
+    Raw,
+    Pad(usize, Align),
+    Join(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Expr {
+    path: Vec<String>,
+    negate: bool,
+    filters: Vec<Filter>,
+    line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    If { branches: Vec<(Expr, Vec<Node>)>, otherwise: Vec<Node> },
+    For { variable: String, iterable: Expr, body: Vec<Node> },
+    Include { name: String, line: usize },
+}
+
+/// 解析済みのテンプレート
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+    escape: Escape,
+}
+
+impl Template {
+    /// エスケープなしのテンプレートとして解析する
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        let segments = lex(source)?;
+        let mut parser = Parser { segments, index: 0 };
+        let (nodes, end) = parser.parse_block(&[])?;
+        debug_assert!(end.is_none());
+        Ok(Template { nodes, escape: Escape::None })
+    }
+
+    pub fn escape(mut self, escape: Escape) -> Template {
+        self.escape = escape;
+        self
+    }
+
+    /// 出力する。{% include %}はTemplateSetから描画したときだけ使える
+    pub fn render<W: fmt::Write>(&self, context: &Context, dest: &mut W) -> Result<(), RenderError> {
+        Renderer { set: None, context, scopes: Vec::new(), depth: 0 }.render(self, dest)
+    }
+
+    pub fn render_to_string(&self, context: &Context) -> Result<String, RenderError> {
+        let mut result = String::new();
+        self.render(context, &mut result)?;
+        Ok(result)
+    }
+}
+
+/// 名前を付けたテンプレートの集まり。{% include "name" %}で互いに参照できる
+#[derive(Clone, Debug, Default)]
+pub struct TemplateSet {
+    templates: HashMap<String, Template>,
+}
+
+impl TemplateSet {
+    pub fn new() -> TemplateSet {
+        TemplateSet::default()
+    }
+
+    /// 名前が".html"で終わればHTML、".sh"で終わればシェルのエスケープを使う
+    pub fn add(&mut self, name: &str, source: &str) -> Result<(), TemplateError> {
+        let escape = if name.ends_with(".html") {
+            Escape::Html
+        } else if name.ends_with(".sh") {
+            Escape::Shell
+        } else {
+            Escape::None
+        };
+        self.templates.insert(name.to_string(), Template::parse(source)?.escape(escape));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    pub fn render<W: fmt::Write>(&self, name: &str, context: &Context, dest: &mut W) -> Result<(), RenderError> {
+        let template = self.get(name).ok_or_else(|| RenderError::UnknownTemplate { line: 0, name: name.to_string() })?;
+        Renderer { set: Some(self), context, scopes: Vec::new(), depth: 0 }.render(template, dest)
+    }
+}
+
+// 字句解析の結果
+#[derive(Clone, Debug)]
+enum Segment {
+    Text(String),
+    Expr { content: String, line: usize, column: usize },
+    Tag { content: String, line: usize, column: usize },
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |s| s.chars().count()) + 1;
+    (line, column)
+}
+
+fn lex(source: &str) -> Result<Vec<Segment>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut pos = 0;
+    while pos < source.len() {
+        let open = match ["{{", "{%", "{#"].iter().filter_map(|open| source[pos..].find(open)).min() {
+            Some(i) => pos + i,
+            None => {
+                text.push_str(&source[pos..]);
+                break;
+            }
+        };
+        text.push_str(&source[pos..open]);
+        let (line, column) = line_column(source, open);
+        let (opener, closer) = match &source[open..open + 2] {
+            "{{" => ("{{", "}}"),
+            "{%" => ("{%", "%}"),
+            _ => ("{#", "#}"),
+        };
+        let close = source[open + 2..]
+            .find(closer)
+            .map(|i| open + 2 + i)
+            .ok_or(TemplateError { line, column, kind: TemplateErrorKind::Unclosed(opener) })?;
+        let content = source[open + 2..close].trim().to_string();
+        pos = close + 2;
+
+        if opener != "{{" {
+            // タグだけの行は字下げと改行ごと取り除く
+            let line_begin = source[..open].rfind('\n').map_or(0, |i| i + 1);
+            let indent = open - line_begin;
+            let line_start = source[line_begin..open].trim_matches([' ', '\t']).is_empty();
+            let rest = &source[pos..];
+            let line_end = rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n");
+            if line_start && line_end {
+                text.truncate(text.len() - indent);
+                pos += if rest.starts_with("\r\n") { 2 } else { rest.len().min(1) };
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        match opener {
+            "{{" => segments.push(Segment::Expr { content, line, column }),
+            "{%" => segments.push(Segment::Tag { content, line, column }),
+            _ => {}
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+struct Parser {
+    segments: Vec<Segment>,
+    index: usize,
+}
+
+// ブロックを終えたタグ（"endfor"や"elif x"）と位置
+type EndTag = (String, usize, usize);
+
+impl Parser {
+    // end_tagsのいずれかのタグまでを解析する。end_tagsが空なら入力の終わりまで
+    fn parse_block(&mut self, end_tags: &[&str]) -> Result<(Vec<Node>, Option<EndTag>), TemplateError> {
+        let mut nodes = Vec::new();
+        while self.index < self.segments.len() {
+            let segment = self.segments[self.index].clone();
+            self.index += 1;
+            match segment {
+                Segment::Text(text) => nodes.push(Node::Text(text)),
+                Segment::Expr { content, line, column } => {
+                    nodes.push(Node::Output(parse_value_expr(&content, line, column)?));
+                }
+                Segment::Tag { content, line, column } => {
+                    let keyword = content.split_whitespace().next().unwrap_or("");
+                    if end_tags.contains(&keyword) {
+                        return Ok((nodes, Some((content, line, column))));
+                    }
+                    nodes.push(self.parse_tag(&content, line, column)?);
+                }
+            }
+        }
+        match end_tags.first() {
+            None => Ok((nodes, None)),
+            Some(_) => {
+                let (line, column) = self.last_position();
+                Err(TemplateError { line, column, kind: TemplateErrorKind::UnclosedBlock(end_tags.join("/")) })
+            }
+        }
+    }
+
+    fn last_position(&self) -> (usize, usize) {
+        self.segments
+            .iter()
+            .rev()
+            .find_map(|segment| match *segment {
+                Segment::Tag { line, column, .. } | Segment::Expr { line, column, .. } => Some((line, column)),
+                Segment::Text(_) => None,
+            })
+            .unwrap_or((1, 1))
+    }
+
+    fn parse_tag(&mut self, content: &str, line: usize, column: usize) -> Result<Node, TemplateError> {
+        let error = |kind| TemplateError { line, column, kind };
+        let (keyword, rest) = match content.find(char::is_whitespace) {
+            Some(i) => (&content[..i], content[i..].trim()),
+            None => (content, ""),
+        };
+        match keyword {
+            "if" => {
+                let mut branches = Vec::new();
+                let mut condition = parse_expr(rest, line, column)?;
+                loop {
+                    let (body, end) = self.parse_block(&["elif", "else", "endif"])?;
+                    branches.push((condition, body));
+                    let (tag, line, column) = end.expect("parse_block returns the end tag");
+                    if let Some(expr) = tag.strip_prefix("elif") {
+                        condition = parse_expr(expr.trim(), line, column)?;
+                    } else if tag == "else" {
+                        let (otherwise, _) = self.parse_block(&["endif"])?;
+                        return Ok(Node::If { branches, otherwise });
+                    } else {
+                        return Ok(Node::If { branches, otherwise: Vec::new() });
+                    }
+                }
+            }
+            "for" => {
+                let mut words = rest.splitn(3, char::is_whitespace);
+                let (variable, keyword_in, iterable) = (words.next(), words.next(), words.next());
+                let variable = match (variable, keyword_in) {
+                    (Some(variable), Some("in")) if is_identifier(variable) => variable.to_string(),
+                    _ => return Err(error(TemplateErrorKind::InvalidTag(content.to_string()))),
+                };
+                let iterable = parse_value_expr(iterable.unwrap_or("").trim(), line, column)?;
+                let (body, _) = self.parse_block(&["endfor"])?;
+                Ok(Node::For { variable, iterable, body })
+            }
+            "include" => match parse_string(rest) {
+                Some(name) if rest.len() == name.len() + 2 => Ok(Node::Include { name, line }),
+                _ => Err(error(TemplateErrorKind::InvalidTag(content.to_string()))),
+            },
+            _ => Err(error(TemplateErrorKind::UnexpectedTag(content.to_string()))),
+        }
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|ch| ch == '_' || ch.is_alphabetic()) && chars.all(|ch| ch == '_' || ch.is_alphanumeric())
+}
+
+// 引用符で囲まれた文字列リテラル
+fn parse_string(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|&ch| ch == '"' || ch == '\'')?;
+    let end = text[1..].find(quote)? + 1;
+    Some(text[1..end].to_string())
+}
+
+// 引用符の外の区切り文字で分ける
+fn split_outside_quotes(text: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, ch) in text.char_indices() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => {}
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None if ch == delimiter => {
+                parts.push(&text[start..i]);
+                start = i + ch.len_utf8();
+            }
+            None => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+// "{{ }}"とforのリストの式。notは条件でしか意味を持たないので受け付けない
+fn parse_value_expr(text: &str, line: usize, column: usize) -> Result<Expr, TemplateError> {
+    let expr = parse_expr(text, line, column)?;
+    if expr.negate {
+        return Err(TemplateError { line, column, kind: TemplateErrorKind::InvalidExpression(text.to_string()) });
+    }
+    Ok(expr)
+}
+
+// [not] a.b.c | filter | filter(arg, ...)
+fn parse_expr(text: &str, line: usize, column: usize) -> Result<Expr, TemplateError> {
+    let error = |kind| TemplateError { line, column, kind };
+    let mut parts = split_outside_quotes(text, '|').into_iter().map(str::trim);
+    let mut head = parts.next().unwrap_or("");
+    let negate = head.starts_with("not ");
+    if negate {
+        head = head[4..].trim_start();
+    }
+    let path: Vec<String> = head.split('.').map(str::to_string).collect();
+    let valid_path = is_identifier(&path[0])
+        && path[1..].iter().all(|key| is_identifier(key) || key.parse::<usize>().is_ok());
+    if !valid_path {
+        return Err(error(TemplateErrorKind::InvalidExpression(text.to_string())));
+    }
+    let filters = parts
+        .map(|filter| parse_filter(filter).ok_or_else(|| error(TemplateErrorKind::UnknownFilter(filter.to_string()))))
+        .collect::<Result<Vec<_>, _>>()?;
+    // joinはリストを文字列にするので、文字列になった後には置けない
+    if filters.iter().skip(1).any(|filter| matches!(*filter, Filter::Join(_))) {
+        return Err(error(TemplateErrorKind::MisplacedFilter("join".to_string())));
+    }
+    Ok(Expr { path, negate, filters, line })
+}
+
+fn parse_filter(text: &str) -> Option<Filter> {
+    let (name, args) = match text.find('(') {
+        Some(i) => (text[..i].trim(), split_outside_quotes(text[i + 1..].strip_suffix(')')?, ',')),
+        None => (text, Vec::new()),
+    };
+    let args: Vec<&str> = args.into_iter().map(str::trim).filter(|arg| !arg.is_empty()).collect();
+    match (name, args.as_slice()) {
+        ("upper", []) => Some(Filter::Upper),
+        ("lower", []) => Some(Filter::Lower),
+        ("trim", []) => Some(Filter::Trim),
+        ("nfc", []) => Some(Filter::Nfc),
+        ("raw", []) => Some(Filter::Raw),
+        ("pad", [width]) => Some(Filter::Pad(width.parse().ok()?, Align::Left)),
+        ("pad", [width, align]) => {
+            let align = parse_string(align)?.chars().next().and_then(Align::from_char)?;
+            Some(Filter::Pad(width.parse().ok()?, align))
+        }
+        ("join", [separator]) => Some(Filter::Join(parse_string(separator)?)),
+        _ => None,
+    }
+}
+
+// includeの入れ子の上限（自分自身をincludeしたときに止めるため）
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+struct Renderer<'a> {
+    set: Option<&'a TemplateSet>,
+    context: &'a Context,
+    // forで導入した変数。後ろほど内側
+    scopes: Vec<(String, Value)>,
+    depth: usize,
+}
+
+impl<'a> Renderer<'a> {
+    fn render<W: fmt::Write>(&mut self, template: &Template, dest: &mut W) -> Result<(), RenderError> {
+        self.render_nodes(&template.nodes, template.escape, dest)
+    }
+
+    fn render_nodes<W: fmt::Write>(&mut self, nodes: &[Node], escape: Escape, dest: &mut W) -> Result<(), RenderError> {
+        for node in nodes {
+            match *node {
+                Node::Text(ref text) => dest.write_str(text)?,
+                Node::Output(ref expr) => {
+                    let value = self.lookup(expr).ok_or_else(|| undefined(expr))?;
+                    let (text, raw) = apply_filters(&value, &expr.filters);
+                    let escape = if raw { Escape::None } else { escape };
+                    dest.write_str(&escape_text(&text, escape))?;
+                }
+                Node::If { ref branches, ref otherwise } => {
+                    let chosen = branches.iter().find(|(condition, _)| self.is_true(condition));
+                    let body = chosen.map_or(otherwise, |(_, body)| body);
+                    self.render_nodes(body, escape, dest)?;
+                }
+                Node::For { ref variable, ref iterable, ref body } => {
+                    let items = match self.lookup(iterable).ok_or_else(|| undefined(iterable))? {
+                        Value::List(items) => items,
+                        _ => {
+                            let name = iterable.path.join(".");
+                            return Err(RenderError::NotIterable { line: iterable.line, name });
+                        }
+                    };
+                    let count = items.len();
+                    for (index, item) in items.into_iter().enumerate() {
+                        let meta = Context::new()
+                            .set("index", index as i64 + 1)
+                            .set("first", index == 0)
+                            .set("last", index + 1 == count);
+                        self.scopes.push(("loop".to_string(), meta.into()));
+                        self.scopes.push((variable.clone(), item));
+                        let result = self.render_nodes(body, escape, dest);
+                        self.scopes.truncate(self.scopes.len() - 2);
+                        result?;
+                    }
+                }
+                Node::Include { ref name, line } => {
+                    let template = self
+                        .set
+                        .and_then(|set| set.get(name))
+                        .ok_or_else(|| RenderError::UnknownTemplate { line, name: name.clone() })?;
+                    if self.depth >= MAX_INCLUDE_DEPTH {
+                        return Err(RenderError::IncludeTooDeep { line, name: name.clone() });
+                    }
+                    self.depth += 1;
+                    let result = self.render(template, dest);
+                    self.depth -= 1;
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, expr: &Expr) -> Option<Value> {
+        let first = &expr.path[0];
+        let mut value = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|&(name, _)| name == first)
+            .map(|(_, value)| value)
+            .or_else(|| self.context.values.get(first))?;
+        for key in &expr.path[1..] {
+            value = match *value {
+                Value::Map(ref map) => map.get(key)?,
+                Value::List(ref items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value.clone())
+    }
+
+    // 未定義の変数は偽
+    fn is_true(&self, condition: &Expr) -> bool {
+        let truthy = self.lookup(condition).is_some_and(|value| {
+            if condition.filters.is_empty() {
+                value.is_truthy()
+            } else {
+                !apply_filters(&value, &condition.filters).0.is_empty()
+            }
+        });
+        truthy != condition.negate
+    }
+}
+
+fn undefined(expr: &Expr) -> RenderError {
+    RenderError::UndefinedVariable { line: expr.line, name: expr.path.join(".") }
+}
+
+// フィルタを順に適用し、(文字列, rawが指定されたか)を返す
+fn apply_filters(value: &Value, filters: &[Filter]) -> (String, bool) {
+    let mut text = match (value, filters.first()) {
+        (Value::List(items), Some(Filter::Join(separator))) => {
+            items.iter().map(Value::to_string).collect::<Vec<_>>().join(separator)
+        }
+        _ => value.to_string(),
+    };
+    let mut raw = false;
+    for filter in filters {
+        text = match *filter {
+            Filter::Upper => text.to_uppercase(),
+            Filter::Lower => text.to_lowercase(),
+            Filter::Trim => text.trim().to_string(),
+            Filter::Nfc => text.nfc().collect(),
+            Filter::Raw => {
+                raw = true;
+                text
+            }
+            Filter::Pad(width, align) => Padded::new(&text, width).align(align).to_string(),
+            // 先頭のjoinは上で適用済み
+            Filter::Join(_) => text,
+        };
+    }
+    (text, raw)
+}
+
+fn escape_text(text: &str, escape: Escape) -> String {
+    match escape {
+        Escape::None => text.to_string(),
+        Escape::Html => {
+            let mut result = String::with_capacity(text.len());
+            for ch in text.chars() {
+                match ch {
+                    '&' => result.push_str("&amp;"),
+                    '<' => result.push_str("&lt;"),
+                    '>' => result.push_str("&gt;"),
+                    '"' => result.push_str("&quot;"),
+                    '\'' => result.push_str("&#39;"),
+                    _ => result.push(ch),
+                }
+            }
+            result
+        }
+        Escape::Shell => {
+            let safe = !text.is_empty()
+                && text.chars().all(|ch| ch.is_ascii_alphanumeric() || "_@%+=:,./-".contains(ch));
+            if safe {
+                text.to_string()
+            } else {
+                format!("'{}'", text.replace('\'', "'\\''"))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateErrorKind {
+    /// "{{"・"{%"・"{#"が閉じていない
+    Unclosed(&'static str),
+    /// "endfor"などが見つからないままテンプレートが終わった
+    UnclosedBlock(String),
+    /// 対応する開始タグのない"endif"や、知らないタグ
+    UnexpectedTag(String),
+    InvalidTag(String),
+    InvalidExpression(String),
+    UnknownFilter(String),
+    /// 先頭にしか置けないフィルタ（join）が途中にある
+    MisplacedFilter(String),
+}
+
+/// 解析時のエラー。行・桁は1始まり
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: TemplateErrorKind,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        write!(dest, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            TemplateErrorKind::Unclosed(opener) => write!(dest, "unclosed '{}'", opener),
+            TemplateErrorKind::UnclosedBlock(ref tags) => write!(dest, "missing {{% {} %}}", tags),
+            TemplateErrorKind::UnexpectedTag(ref tag) => write!(dest, "unexpected tag {:?}", tag),
+            TemplateErrorKind::InvalidTag(ref tag) => write!(dest, "invalid tag {:?}", tag),
+            TemplateErrorKind::InvalidExpression(ref expr) => write!(dest, "invalid expression {:?}", expr),
+            TemplateErrorKind::UnknownFilter(ref filter) => write!(dest, "unknown filter {:?}", filter),
+            TemplateErrorKind::MisplacedFilter(ref filter) => write!(dest, "filter {:?} must come first", filter),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// 描画時のエラー。lineはテンプレート上の行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenderError {
+    UndefinedVariable { line: usize, name: String },
+    NotIterable { line: usize, name: String },
+    UnknownTemplate { line: usize, name: String },
+    IncludeTooDeep { line: usize, name: String },
+    /// 出力先への書き込みに失敗した
+    Write,
+}
+
+impl From<fmt::Error> for RenderError {
+    fn from(_: fmt::Error) -> RenderError {
+        RenderError::Write
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, dest: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderError::UndefinedVariable { line, ref name } => {
+                write!(dest, "line {}: undefined variable '{}'", line, name)
+            }
+            RenderError::NotIterable { line, ref name } => write!(dest, "line {}: '{}' is not a list", line, name),
+            RenderError::UnknownTemplate { line, ref name } => {
+                write!(dest, "line {}: unknown template {:?}", line, name)
+            }
+            RenderError::IncludeTooDeep { line, ref name } => {
+                write!(dest, "line {}: includes nested too deeply at {:?}", line, name)
+            }
+            RenderError::Write => write!(dest, "failed to write output"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
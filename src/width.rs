@@ -0,0 +1,171 @@
+// 端末での表示幅（桁数）
+//
+// "{:10}"の幅は文字数で数えるため、端末で2桁を占める漢字・かなや幅を持たない結合文字が混ざると揃わない。
+// 幅はUnicodeのEastAsianWidth.txtの分類に従う。Ambiguous（"○"や"Α"など）は環境によって1桁にも
+// 2桁にもなるため、AmbiguousWidthで呼び出し側が選ぶ。
+// 文字列の幅は書記素クラスタごとに数え、絵文字の異体字セレクタやZWJ連結、国旗を1つの絵文字として扱う。
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::pattern::{in_table, GeneralCategory};
+
+/// East Asian WidthがAmbiguousの文字の幅
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AmbiguousWidth {
+    /// 1桁（欧米のロケール）
+    #[default]
+    Narrow,
+    /// 2桁（日本語・中国語・韓国語のロケール）
+    Wide,
+}
+
+/// 端末で占める桁数
+pub trait DisplayWidth {
+    /// Ambiguousを1桁として数える
+    fn display_width(&self) -> usize {
+        self.display_width_with(AmbiguousWidth::Narrow)
+    }
+
+    fn display_width_with(&self, ambiguous: AmbiguousWidth) -> usize;
+}
+
+impl DisplayWidth for char {
+    /// 制御文字・結合文字・書式文字は0、Wide/Fullwidthは2、Ambiguousは指定に従い、それ以外は1
+    fn display_width_with(&self, ambiguous: AmbiguousWidth) -> usize {
+        let ch = *self;
+        if ch.is_control() || in_table(ZERO_WIDTH, ch) || GeneralCategory::of(ch) == GeneralCategory::Mark {
+            0
+        } else if in_table(WIDE, ch) || (ambiguous == AmbiguousWidth::Wide && in_table(AMBIGUOUS, ch)) {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+impl DisplayWidth for str {
+    fn display_width_with(&self, ambiguous: AmbiguousWidth) -> usize {
+        self.graphemes(true).map(|grapheme| grapheme_width(grapheme, ambiguous)).sum()
+    }
+}
+
+const TEXT_PRESENTATION: char = '\u{fe0e}';
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+// 1つの書記素クラスタの幅
+fn grapheme_width(grapheme: &str, ambiguous: AmbiguousWidth) -> usize {
+    let mut chars = grapheme.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return 0,
+    };
+    let first_width = first.display_width_with(ambiguous);
+    if chars.as_str().is_empty() {
+        return first_width;
+    }
+    if grapheme.contains(TEXT_PRESENTATION) {
+        // "⌚︎"のようにテキスト表示を指定した絵文字は1桁
+        first_width.min(1)
+    } else if grapheme.contains(EMOJI_PRESENTATION) || is_regional_indicator(first) || first_width == 2 {
+        // "❤️"や"1️⃣"、国旗、肌の色やZWJで連結した絵文字は全体で1つの全角文字
+        2
+    } else {
+        grapheme.chars().map(|ch| ch.display_width_with(ambiguous)).sum()
+    }
+}
+
+fn is_regional_indicator(ch: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&ch)
+}
+
+/// 1文字が占める桁数（Ambiguousは1桁）
+pub fn char_width(ch: char) -> usize {
+    ch.display_width()
+}
+
+/// 文字列が占める桁数（Ambiguousは1桁）
+pub fn str_width(text: &str) -> usize {
+    text.display_width()
+}
+
+// 書式文字（ゼロ幅空白・結合子・方向制御・異体字セレクタ・BOM）とハングルの中声・終声字母
+const ZERO_WIDTH: &[(char, char)] = &[
+    ('\u{ad}', '\u{ad}'), ('\u{1160}', '\u{11ff}'), ('\u{200b}', '\u{200f}'), ('\u{202a}', '\u{202e}'),
+    ('\u{2060}', '\u{2064}'), ('\u{3099}', '\u{309a}'), ('\u{fe00}', '\u{fe0f}'), ('\u{feff}', '\u{feff}'),
+];
+
+// EastAsianWidth.txtでW（Wide）またはF（Fullwidth）の範囲
+const WIDE: &[(char, char)] = &[
+    ('\u{1100}', '\u{115f}'), ('\u{231a}', '\u{231b}'), ('\u{2329}', '\u{232a}'), ('\u{23e9}', '\u{23ec}'),
+    ('\u{23f0}', '\u{23f0}'), ('\u{23f3}', '\u{23f3}'), ('\u{25fd}', '\u{25fe}'), ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'), ('\u{267f}', '\u{267f}'), ('\u{2693}', '\u{2693}'), ('\u{26a1}', '\u{26a1}'),
+    ('\u{26aa}', '\u{26ab}'), ('\u{26bd}', '\u{26be}'), ('\u{26c4}', '\u{26c5}'), ('\u{26ce}', '\u{26ce}'),
+    ('\u{26d4}', '\u{26d4}'), ('\u{26ea}', '\u{26ea}'), ('\u{26f2}', '\u{26f3}'), ('\u{26f5}', '\u{26f5}'),
+    ('\u{26fa}', '\u{26fa}'), ('\u{26fd}', '\u{26fd}'), ('\u{2705}', '\u{2705}'), ('\u{270a}', '\u{270b}'),
+    ('\u{2728}', '\u{2728}'), ('\u{274c}', '\u{274c}'), ('\u{274e}', '\u{274e}'), ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'), ('\u{2795}', '\u{2797}'), ('\u{27b0}', '\u{27b0}'), ('\u{27bf}', '\u{27bf}'),
+    ('\u{2b1b}', '\u{2b1c}'), ('\u{2b50}', '\u{2b50}'), ('\u{2b55}', '\u{2b55}'), ('\u{2e80}', '\u{303e}'),
+    ('\u{3041}', '\u{3247}'), ('\u{3250}', '\u{4dbf}'), ('\u{4e00}', '\u{a4cf}'), ('\u{a960}', '\u{a97f}'),
+    ('\u{ac00}', '\u{d7a3}'), ('\u{f900}', '\u{faff}'), ('\u{fe10}', '\u{fe19}'), ('\u{fe30}', '\u{fe6f}'),
+    ('\u{ff00}', '\u{ff60}'), ('\u{ffe0}', '\u{ffe6}'), ('\u{16fe0}', '\u{16fe4}'), ('\u{17000}', '\u{18aff}'),
+    ('\u{1b000}', '\u{1b16f}'), ('\u{1f004}', '\u{1f004}'), ('\u{1f0cf}', '\u{1f0cf}'), ('\u{1f18e}', '\u{1f18e}'),
+    ('\u{1f191}', '\u{1f19a}'), ('\u{1f200}', '\u{1f202}'), ('\u{1f210}', '\u{1f23b}'), ('\u{1f240}', '\u{1f248}'),
+    ('\u{1f250}', '\u{1f251}'), ('\u{1f260}', '\u{1f265}'), ('\u{1f300}', '\u{1f320}'), ('\u{1f32d}', '\u{1f335}'),
+    ('\u{1f337}', '\u{1f37c}'), ('\u{1f37e}', '\u{1f393}'), ('\u{1f3a0}', '\u{1f3ca}'), ('\u{1f3cf}', '\u{1f3d3}'),
+    ('\u{1f3e0}', '\u{1f3f0}'), ('\u{1f3f4}', '\u{1f3f4}'), ('\u{1f3f8}', '\u{1f43e}'), ('\u{1f440}', '\u{1f440}'),
+    ('\u{1f442}', '\u{1f4fc}'), ('\u{1f4ff}', '\u{1f53d}'), ('\u{1f54b}', '\u{1f54e}'), ('\u{1f550}', '\u{1f567}'),
+    ('\u{1f57a}', '\u{1f57a}'), ('\u{1f595}', '\u{1f596}'), ('\u{1f5a4}', '\u{1f5a4}'), ('\u{1f5fb}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6c5}'), ('\u{1f6cc}', '\u{1f6cc}'), ('\u{1f6d0}', '\u{1f6d2}'), ('\u{1f6d5}', '\u{1f6d7}'),
+    ('\u{1f6dc}', '\u{1f6df}'), ('\u{1f6eb}', '\u{1f6ec}'), ('\u{1f6f4}', '\u{1f6fc}'), ('\u{1f7e0}', '\u{1f7eb}'),
+    ('\u{1f7f0}', '\u{1f7f0}'), ('\u{1f90c}', '\u{1f93a}'), ('\u{1f93c}', '\u{1f945}'), ('\u{1f947}', '\u{1f9ff}'),
+    ('\u{1fa70}', '\u{1fa7c}'), ('\u{1fa80}', '\u{1fa89}'), ('\u{1fa8f}', '\u{1fac6}'), ('\u{1face}', '\u{1fadc}'),
+    ('\u{1fadf}', '\u{1fae9}'), ('\u{1faf0}', '\u{1faf8}'), ('\u{20000}', '\u{2fffd}'), ('\u{30000}', '\u{3fffd}'),
+];
+
+// EastAsianWidth.txtでA（Ambiguous）の範囲（結合文字と異体字セレクタはZERO_WIDTHが優先する）
+const AMBIGUOUS: &[(char, char)] = &[
+    ('\u{a1}', '\u{a1}'), ('\u{a4}', '\u{a4}'), ('\u{a7}', '\u{a8}'), ('\u{aa}', '\u{aa}'),
+    ('\u{ae}', '\u{ae}'), ('\u{b0}', '\u{b4}'), ('\u{b6}', '\u{ba}'), ('\u{bc}', '\u{bf}'),
+    ('\u{c6}', '\u{c6}'), ('\u{d0}', '\u{d0}'), ('\u{d7}', '\u{d8}'), ('\u{de}', '\u{e1}'),
+    ('\u{e6}', '\u{e6}'), ('\u{e8}', '\u{ea}'), ('\u{ec}', '\u{ed}'), ('\u{f0}', '\u{f0}'),
+    ('\u{f2}', '\u{f3}'), ('\u{f7}', '\u{fa}'), ('\u{fc}', '\u{fc}'), ('\u{fe}', '\u{fe}'),
+    ('\u{101}', '\u{101}'), ('\u{111}', '\u{111}'), ('\u{113}', '\u{113}'), ('\u{11b}', '\u{11b}'),
+    ('\u{126}', '\u{127}'), ('\u{12b}', '\u{12b}'), ('\u{131}', '\u{133}'), ('\u{138}', '\u{138}'),
+    ('\u{13f}', '\u{142}'), ('\u{144}', '\u{144}'), ('\u{148}', '\u{14b}'), ('\u{14d}', '\u{14d}'),
+    ('\u{152}', '\u{153}'), ('\u{166}', '\u{167}'), ('\u{16b}', '\u{16b}'), ('\u{1ce}', '\u{1ce}'),
+    ('\u{1d0}', '\u{1d0}'), ('\u{1d2}', '\u{1d2}'), ('\u{1d4}', '\u{1d4}'), ('\u{1d6}', '\u{1d6}'),
+    ('\u{1d8}', '\u{1d8}'), ('\u{1da}', '\u{1da}'), ('\u{1dc}', '\u{1dc}'), ('\u{251}', '\u{251}'),
+    ('\u{261}', '\u{261}'), ('\u{2c4}', '\u{2c4}'), ('\u{2c7}', '\u{2c7}'), ('\u{2c9}', '\u{2cb}'),
+    ('\u{2cd}', '\u{2cd}'), ('\u{2d0}', '\u{2d0}'), ('\u{2d8}', '\u{2db}'), ('\u{2dd}', '\u{2dd}'),
+    ('\u{2df}', '\u{2df}'), ('\u{391}', '\u{3a1}'), ('\u{3a3}', '\u{3a9}'), ('\u{3b1}', '\u{3c1}'),
+    ('\u{3c3}', '\u{3c9}'), ('\u{401}', '\u{401}'), ('\u{410}', '\u{44f}'), ('\u{451}', '\u{451}'),
+    ('\u{2010}', '\u{2010}'), ('\u{2013}', '\u{2016}'), ('\u{2018}', '\u{2019}'), ('\u{201c}', '\u{201d}'),
+    ('\u{2020}', '\u{2022}'), ('\u{2024}', '\u{2027}'), ('\u{2030}', '\u{2030}'), ('\u{2032}', '\u{2033}'),
+    ('\u{2035}', '\u{2035}'), ('\u{203b}', '\u{203b}'), ('\u{203e}', '\u{203e}'), ('\u{2074}', '\u{2074}'),
+    ('\u{207f}', '\u{207f}'), ('\u{2081}', '\u{2084}'), ('\u{20ac}', '\u{20ac}'), ('\u{2103}', '\u{2103}'),
+    ('\u{2105}', '\u{2105}'), ('\u{2109}', '\u{2109}'), ('\u{2113}', '\u{2113}'), ('\u{2116}', '\u{2116}'),
+    ('\u{2121}', '\u{2122}'), ('\u{2126}', '\u{2126}'), ('\u{212b}', '\u{212b}'), ('\u{2153}', '\u{2154}'),
+    ('\u{215b}', '\u{215e}'), ('\u{2160}', '\u{216b}'), ('\u{2170}', '\u{2179}'), ('\u{2189}', '\u{2189}'),
+    ('\u{2190}', '\u{2199}'), ('\u{21b8}', '\u{21b9}'), ('\u{21d2}', '\u{21d2}'), ('\u{21d4}', '\u{21d4}'),
+    ('\u{21e7}', '\u{21e7}'), ('\u{2200}', '\u{2200}'), ('\u{2202}', '\u{2203}'), ('\u{2207}', '\u{2208}'),
+    ('\u{220b}', '\u{220b}'), ('\u{220f}', '\u{220f}'), ('\u{2211}', '\u{2211}'), ('\u{2215}', '\u{2215}'),
+    ('\u{221a}', '\u{221a}'), ('\u{221d}', '\u{2220}'), ('\u{2223}', '\u{2223}'), ('\u{2225}', '\u{2225}'),
+    ('\u{2227}', '\u{222c}'), ('\u{222e}', '\u{222e}'), ('\u{2234}', '\u{2237}'), ('\u{223c}', '\u{223d}'),
+    ('\u{2248}', '\u{2248}'), ('\u{224c}', '\u{224c}'), ('\u{2252}', '\u{2252}'), ('\u{2260}', '\u{2261}'),
+    ('\u{2264}', '\u{2267}'), ('\u{226a}', '\u{226b}'), ('\u{226e}', '\u{226f}'), ('\u{2282}', '\u{2283}'),
+    ('\u{2286}', '\u{2287}'), ('\u{2295}', '\u{2295}'), ('\u{2299}', '\u{2299}'), ('\u{22a5}', '\u{22a5}'),
+    ('\u{22bf}', '\u{22bf}'), ('\u{2312}', '\u{2312}'), ('\u{2460}', '\u{24e9}'), ('\u{24eb}', '\u{254b}'),
+    ('\u{2550}', '\u{2573}'), ('\u{2580}', '\u{258f}'), ('\u{2592}', '\u{2595}'), ('\u{25a0}', '\u{25a1}'),
+    ('\u{25a3}', '\u{25a9}'), ('\u{25b2}', '\u{25b3}'), ('\u{25b6}', '\u{25b7}'), ('\u{25bc}', '\u{25bd}'),
+    ('\u{25c0}', '\u{25c1}'), ('\u{25c6}', '\u{25c8}'), ('\u{25cb}', '\u{25cb}'), ('\u{25ce}', '\u{25d1}'),
+    ('\u{25e2}', '\u{25e5}'), ('\u{25ef}', '\u{25ef}'), ('\u{2605}', '\u{2606}'), ('\u{2609}', '\u{2609}'),
+    ('\u{260e}', '\u{260f}'), ('\u{261c}', '\u{261c}'), ('\u{261e}', '\u{261e}'), ('\u{2640}', '\u{2640}'),
+    ('\u{2642}', '\u{2642}'), ('\u{2660}', '\u{2661}'), ('\u{2663}', '\u{2665}'), ('\u{2667}', '\u{266a}'),
+    ('\u{266c}', '\u{266d}'), ('\u{266f}', '\u{266f}'), ('\u{269e}', '\u{269f}'), ('\u{26bf}', '\u{26bf}'),
+    ('\u{26c6}', '\u{26cd}'), ('\u{26cf}', '\u{26d3}'), ('\u{26d5}', '\u{26e1}'), ('\u{26e3}', '\u{26e3}'),
+    ('\u{26e8}', '\u{26e9}'), ('\u{26eb}', '\u{26f1}'), ('\u{26f4}', '\u{26f4}'), ('\u{26f6}', '\u{26f9}'),
+    ('\u{26fb}', '\u{26fc}'), ('\u{26fe}', '\u{26ff}'), ('\u{273d}', '\u{273d}'), ('\u{2776}', '\u{277f}'),
+    ('\u{2b56}', '\u{2b59}'), ('\u{3248}', '\u{324f}'), ('\u{e000}', '\u{f8ff}'), ('\u{fffd}', '\u{fffd}'),
+    ('\u{1f100}', '\u{1f10a}'), ('\u{1f110}', '\u{1f12d}'), ('\u{1f130}', '\u{1f169}'), ('\u{1f170}', '\u{1f18d}'),
+    ('\u{1f18f}', '\u{1f190}'), ('\u{1f19b}', '\u{1f1ac}'),
+];
@@ -0,0 +1,294 @@
+// 桁数を指定した折り返しと段落の詰め直し
+//
+// 幅は端末の桁数（漢字・かなは2桁）で数える。英語は空白で、日本語は文字の間で折り返すが、
+// 禁則処理により"、"や"」"を行頭に、"「"を行末に置かない。
+
+use crate::width::{char_width, str_width};
+
+/// 改行位置の決め方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapAlgorithm {
+    /// 入るだけ詰め込む
+    Greedy,
+    /// 最終行以外の行末の余白の二乗和が最小になるよう段落全体で決める（Knuth-Plass方式）
+    OptimalFit,
+}
+
+/// 折り返しの設定
+#[derive(Clone, Debug)]
+pub struct Wrapper {
+    width: usize,
+    algorithm: WrapAlgorithm,
+    initial_indent: String,
+    subsequent_indent: String,
+    kinsoku: bool,
+    preserve_newlines: bool,
+}
+
+impl Wrapper {
+    /// 貪欲法・字下げなし・禁則処理あり・改行を保持する設定
+    pub fn new(width: usize) -> Wrapper {
+        Wrapper {
+            width,
+            algorithm: WrapAlgorithm::Greedy,
+            initial_indent: String::new(),
+            subsequent_indent: String::new(),
+            kinsoku: true,
+            preserve_newlines: true,
+        }
+    }
+
+    pub fn algorithm(mut self, algorithm: WrapAlgorithm) -> Wrapper {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// 段落の1行目の前に置く文字列
+    pub fn initial_indent(mut self, indent: &str) -> Wrapper {
+        self.initial_indent = indent.to_string();
+        self
+    }
+
+    /// 段落の2行目以降の前に置く文字列（ぶら下げインデント）
+    pub fn subsequent_indent(mut self, indent: &str) -> Wrapper {
+        self.subsequent_indent = indent.to_string();
+        self
+    }
+
+    pub fn kinsoku(mut self, kinsoku: bool) -> Wrapper {
+        self.kinsoku = kinsoku;
+        self
+    }
+
+    /// trueなら元の改行をすべて残す。falseなら空行で区切られた段落ごとに行をつなげて詰め直す
+    pub fn preserve_newlines(mut self, preserve: bool) -> Wrapper {
+        self.preserve_newlines = preserve;
+        self
+    }
+
+    /// 折り返した行を返す（改行文字は含まない）
+    pub fn wrap(&self, text: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.preserve_newlines {
+            for line in text.lines() {
+                self.wrap_paragraph(line, &mut lines);
+            }
+        } else {
+            let mut paragraph = String::new();
+            for line in text.lines().chain(std::iter::once("")) {
+                let line = line.trim();
+                if !line.is_empty() {
+                    join_line(&mut paragraph, line);
+                } else if !paragraph.is_empty() {
+                    if !lines.is_empty() {
+                        lines.push(String::new());
+                    }
+                    self.wrap_paragraph(&paragraph, &mut lines);
+                    paragraph.clear();
+                }
+            }
+        }
+        lines
+    }
+
+    /// 折り返した行を改行でつなげる
+    pub fn fill(&self, text: &str) -> String {
+        self.wrap(text).join("\n")
+    }
+
+    fn wrap_paragraph(&self, text: &str, lines: &mut Vec<String>) {
+        if text.trim().is_empty() {
+            lines.push(String::new());
+            return;
+        }
+        let first_width = self.width.saturating_sub(str_width(&self.initial_indent)).max(1);
+        let rest_width = self.width.saturating_sub(str_width(&self.subsequent_indent)).max(1);
+        let fragments = split_long(fragments(text, self.kinsoku), first_width.min(rest_width));
+        let breaks = match self.algorithm {
+            WrapAlgorithm::Greedy => greedy(&fragments, first_width, rest_width),
+            WrapAlgorithm::OptimalFit => optimal_fit(&fragments, first_width, rest_width),
+        };
+
+        let mut start = 0;
+        for (index, &end) in breaks.iter().enumerate() {
+            let mut line = if index == 0 { self.initial_indent.clone() } else { self.subsequent_indent.clone() };
+            for (i, fragment) in fragments[start..end].iter().enumerate() {
+                line.push_str(fragment.word);
+                if start + i + 1 < end {
+                    line.push_str(fragment.space);
+                }
+            }
+            lines.push(line);
+            start = end;
+        }
+    }
+}
+
+/// 貪欲法で折り返す
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    Wrapper::new(width).wrap(text)
+}
+
+/// 段落ごとに詰め直す
+pub fn fill(text: &str, width: usize) -> String {
+    Wrapper::new(width).preserve_newlines(false).fill(text)
+}
+
+// 段落内の行をつなげる。日本語の文字どうしの間には空白を入れない
+fn join_line(paragraph: &mut String, line: &str) {
+    let wide_join = paragraph.chars().next_back().is_some_and(|ch| char_width(ch) == 2)
+        && line.chars().next().is_some_and(|ch| char_width(ch) == 2);
+    if !paragraph.is_empty() && !wide_join {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(line);
+}
+
+// 行頭に置かない文字（行頭禁則）
+const NO_START: &str = ")]},.!?:;、。，．・：；？！゛゜ヽヾゝゞ々ー’”）〕］｝〉》」』】〙〗〟｠»\
+                        ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶ‐゠–〜";
+// 行末に置かない文字（行末禁則）
+const NO_END: &str = "([{‘“（〔［｛〈《「『【〘〖〝｟«";
+
+// 空白のない位置で改行してよいか。全角文字の前後だけで、禁則文字の前後は除く
+fn can_break_between(prev: char, next: char, kinsoku: bool) -> bool {
+    if char_width(next) == 0 || (char_width(prev) != 2 && char_width(next) != 2) {
+        return false;
+    }
+    !kinsoku || !(NO_START.contains(next) || NO_END.contains(prev))
+}
+
+// 改行できる位置で区切った断片と、その後ろの空白
+#[derive(Clone, Copy, Debug)]
+struct Fragment<'a> {
+    word: &'a str,
+    space: &'a str,
+    width: usize,
+    space_width: usize,
+}
+
+impl<'a> Fragment<'a> {
+    fn new(word: &'a str, space: &'a str) -> Fragment<'a> {
+        Fragment { word, space, width: str_width(word), space_width: str_width(space) }
+    }
+}
+
+fn fragments(text: &str, kinsoku: bool) -> Vec<Fragment<'_>> {
+    let mut fragments = Vec::new();
+    let mut word_start = 0;
+    let mut prev: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        if ch.is_whitespace() {
+            let mut space_end = i + ch.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                space_end = j + next.len_utf8();
+                chars.next();
+            }
+            fragments.push(Fragment::new(&text[word_start..i], &text[i..space_end]));
+            word_start = space_end;
+            prev = None;
+            continue;
+        }
+        if let Some(prev) = prev {
+            if can_break_between(prev, ch, kinsoku) {
+                fragments.push(Fragment::new(&text[word_start..i], ""));
+                word_start = i;
+            }
+        }
+        prev = Some(ch);
+    }
+    if word_start < text.len() {
+        fragments.push(Fragment::new(&text[word_start..], ""));
+    }
+    fragments
+}
+
+// 1行に収まらない断片を文字の境界で分ける（結合文字の前では分けない）
+fn split_long(fragments: Vec<Fragment<'_>>, width: usize) -> Vec<Fragment<'_>> {
+    let mut result = Vec::with_capacity(fragments.len());
+    for fragment in fragments {
+        if fragment.width <= width {
+            result.push(fragment);
+            continue;
+        }
+        let word = fragment.word;
+        let mut start = 0;
+        let mut current = 0;
+        for (i, ch) in word.char_indices() {
+            let w = char_width(ch);
+            if current + w > width && i > start && w > 0 {
+                result.push(Fragment::new(&word[start..i], ""));
+                start = i;
+                current = 0;
+            }
+            current += w;
+        }
+        result.push(Fragment::new(&word[start..], fragment.space));
+    }
+    result
+}
+
+// 各行の終わり（断片の添字、排他的）を返す
+fn greedy(fragments: &[Fragment], first_width: usize, rest_width: usize) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    let mut line_start = 0;
+    let mut line_width = 0;
+    for (i, fragment) in fragments.iter().enumerate() {
+        let available = if breaks.is_empty() { first_width } else { rest_width };
+        if i > line_start && line_width + fragment.width > available {
+            breaks.push(i);
+            line_start = i;
+            line_width = 0;
+        }
+        line_width += fragment.width + fragment.space_width;
+    }
+    breaks.push(fragments.len());
+    breaks
+}
+
+fn optimal_fit(fragments: &[Fragment], first_width: usize, rest_width: usize) -> Vec<usize> {
+    let n = fragments.len();
+    // cost[j]: 断片0..jを折り返したときの最小コスト、from[j]: そのときの最終行の始まり
+    let mut cost = vec![u64::MAX; n + 1];
+    let mut from = vec![0; n + 1];
+    cost[0] = 0;
+    for j in 1..=n {
+        let mut width = 0;
+        for i in (0..j).rev() {
+            width += fragments[i].width;
+            if i + 1 < j {
+                width += fragments[i].space_width;
+            }
+            let available = if i == 0 { first_width } else { rest_width };
+            if width > available && i + 1 < j {
+                // 1行目の方が広ければ、i == 0まで遡ると収まることがある
+                if width > first_width.max(rest_width) {
+                    break;
+                }
+                continue;
+            }
+            if cost[i] == u64::MAX {
+                continue;
+            }
+            let slack = available.saturating_sub(width) as u64;
+            let badness = if j == n { 0 } else { slack * slack };
+            if cost[i] + badness < cost[j] {
+                cost[j] = cost[i] + badness;
+                from[j] = i;
+            }
+        }
+    }
+
+    let mut breaks = Vec::new();
+    let mut j = n;
+    while j > 0 {
+        breaks.push(j);
+        j = from[j];
+    }
+    breaks.reverse();
+    breaks
+}
@@ -0,0 +1,236 @@
+// #[derive(ToText)]の実装
+//
+// serialize::to_text!マクロはフィールド名を手で並べるので、フィールドを足しても書き忘れに気付けなかった。
+// deriveなら構造体の定義から全フィールドを宣言の順に書く。synやquoteには頼らず、トークン列を直接読む。
+// 名前付きフィールドはData::Record、タプル構造体はData::List、ユニット構造体は空のData::Recordになる。
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+const SERIALIZE: &str = "::rust_string_and_text_study::serialize";
+
+#[proc_macro_derive(ToText)]
+pub fn derive_to_text(input: TokenStream) -> TokenStream {
+    let generated = match parse_struct(input) {
+        Ok(item) => item.implementation(),
+        Err(message) => format!("compile_error!({:?});", message),
+    };
+    generated.parse().expect("generated impl is valid Rust")
+}
+
+enum Fields {
+    Named(Vec<String>),
+    Tuple(usize),
+    Unit,
+}
+
+struct Struct {
+    name: String,
+    // "<'a, T: Clone>"の中身と、引数の名前（"'a", "T"）
+    generics: String,
+    parameters: Vec<String>,
+    type_parameters: Vec<String>,
+    where_clause: String,
+    fields: Fields,
+}
+
+impl Struct {
+    fn implementation(&self) -> String {
+        let body = match self.fields {
+            Fields::Named(ref names) => {
+                let entries: Vec<String> = names
+                    .iter()
+                    .map(|name| {
+                        let field = name.trim_start_matches("r#");
+                        format!("({:?}.to_string(), {}::ToText::to_data(&self.{})),", field, SERIALIZE, name)
+                    })
+                    .collect();
+                format!("{}::Data::Record(vec![{}])", SERIALIZE, entries.concat())
+            }
+            Fields::Tuple(count) => {
+                let items: Vec<String> =
+                    (0..count).map(|i| format!("{}::ToText::to_data(&self.{}),", SERIALIZE, i)).collect();
+                format!("{}::Data::List(vec![{}])", SERIALIZE, items.concat())
+            }
+            Fields::Unit => format!("{}::Data::Record(vec![])", SERIALIZE),
+        };
+        // 型引数はそれぞれToTextであることを求める
+        let mut predicates: Vec<String> =
+            self.type_parameters.iter().map(|name| format!("{}: {}::ToText", name, SERIALIZE)).collect();
+        if !self.where_clause.is_empty() {
+            predicates.insert(0, self.where_clause.trim_end_matches(',').to_string());
+        }
+        let where_clause = if predicates.is_empty() {
+            String::new()
+        } else {
+            format!("where {}", predicates.join(", "))
+        };
+        format!(
+            "impl<{}> {}::ToText for {}<{}> {} {{ fn to_data(&self) -> {}::Data {{ {} }} }}",
+            self.generics,
+            SERIALIZE,
+            self.name,
+            self.parameters.join(", "),
+            where_clause,
+            SERIALIZE,
+            body
+        )
+    }
+}
+
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let mut tokens = input.into_iter().peekable();
+    // 属性と可視性を読み飛ばし、"struct"まで進む
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ref ident)) if ident.to_string() == "struct" => break,
+            Some(TokenTree::Ident(ref ident)) if ident.to_string() == "enum" || ident.to_string() == "union" => {
+                return Err("ToText can only be derived for structs".to_string());
+            }
+            Some(_) => {}
+            None => return Err("expected a struct".to_string()),
+        }
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a struct name".to_string()),
+    };
+
+    // "<...>"。'<'と'>'はグループにならないので深さを数える
+    let mut generic_tokens = Vec::new();
+    if matches!(tokens.peek(), Some(TokenTree::Punct(ref punct)) if punct.as_char() == '<') {
+        tokens.next();
+        let mut depth = 1;
+        let mut previous = ' ';
+        for token in tokens.by_ref() {
+            depth += angle_depth_change(&token, previous);
+            if depth == 0 {
+                break;
+            }
+            previous = punct_char(&token);
+            generic_tokens.push(token);
+        }
+    }
+    let (generics, parameters, type_parameters) = parameter_names(&generic_tokens);
+
+    // where句（タプル構造体では本体の後）と本体
+    let mut where_clause = Vec::new();
+    let mut in_where = false;
+    let mut fields = Fields::Unit;
+    for token in tokens {
+        match token {
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Brace => {
+                fields = Fields::Named(named_fields(group.stream()));
+            }
+            // where句の中の括弧（F: Fn(u8)）は本体ではない
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Parenthesis && !in_where => {
+                fields = Fields::Tuple(split_top_level(group.stream().into_iter().collect()).len());
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => {}
+            TokenTree::Ident(ref ident) if ident.to_string() == "where" => in_where = true,
+            other => where_clause.push(other),
+        }
+    }
+
+    Ok(Struct {
+        name,
+        generics,
+        parameters,
+        type_parameters,
+        where_clause: to_source(&where_clause),
+        fields,
+    })
+}
+
+// 型引数の並びから、(既定値を除いたimplの型引数, すべての引数の名前, 型引数の名前)を返す
+fn parameter_names(tokens: &[TokenTree]) -> (String, Vec<String>, Vec<String>) {
+    let mut declarations = Vec::new();
+    let mut parameters = Vec::new();
+    let mut type_parameters = Vec::new();
+    for parameter in split_top_level(tokens.to_vec()) {
+        // implには"T = u8"の既定値を書けない（"Iterator<Item = u8>"の'='は既定値ではない）
+        let default = find_top_level(&parameter, '=');
+        declarations.push(to_source(&parameter[..default.unwrap_or(parameter.len())]));
+        match (parameter.first(), parameter.get(1)) {
+            // 'a
+            (Some(TokenTree::Punct(ref punct)), Some(lifetime)) if punct.as_char() == '\'' => {
+                parameters.push(format!("'{}", lifetime));
+            }
+            // const N: usize
+            (Some(TokenTree::Ident(ref keyword)), Some(name)) if keyword.to_string() == "const" => {
+                parameters.push(name.to_string());
+            }
+            (Some(name), _) => {
+                parameters.push(name.to_string());
+                type_parameters.push(name.to_string());
+            }
+            (None, _) => {}
+        }
+    }
+    (declarations.join(", "), parameters, type_parameters)
+}
+
+// "{ #[attr] pub a: T, b: Vec<(u8, u8)> }"の中身からフィールド名を取り出す
+fn named_fields(stream: TokenStream) -> Vec<String> {
+    split_top_level(stream.into_iter().collect())
+        .into_iter()
+        .filter_map(|field| {
+            // ':'の直前の識別子がフィールド名
+            let colon = find_top_level(&field, ':')?;
+            field[..colon].last().map(ToString::to_string)
+        })
+        .collect()
+}
+
+// 山括弧の外にある','で分ける。括弧の中の','はグループの中にあるので数えなくてよい
+fn split_top_level(tokens: Vec<TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0;
+    let mut previous = ' ';
+    for token in tokens {
+        depth += angle_depth_change(&token, previous);
+        previous = punct_char(&token);
+        if depth == 0 && previous == ',' {
+            parts.push(Vec::new());
+        } else {
+            parts.last_mut().expect("at least one part").push(token);
+        }
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+// 山括弧の外で最初にchが現れる位置
+fn find_top_level(tokens: &[TokenTree], ch: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut previous = ' ';
+    for (i, token) in tokens.iter().enumerate() {
+        depth += angle_depth_change(token, previous);
+        previous = punct_char(token);
+        if depth == 0 && previous == ch {
+            return Some(i);
+        }
+    }
+    None
+}
+
+// '<'と'>'による山括弧の深さの変化。"->"の'>'は山括弧ではない
+fn angle_depth_change(token: &TokenTree, previous: char) -> i32 {
+    match punct_char(token) {
+        '<' => 1,
+        '>' if previous != '-' => -1,
+        _ => 0,
+    }
+}
+
+fn punct_char(token: &TokenTree) -> char {
+    match *token {
+        TokenTree::Punct(ref punct) => punct.as_char(),
+        _ => ' ',
+    }
+}
+
+fn to_source(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}